@@ -13,14 +13,22 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixListener;
 use tokio::sync::{Mutex, RwLock};
 
-use vibefs::db::MetadataStore;
+use vibefs::commands::fork::copy_session;
+use vibefs::commands::rebase::{move_store_to_commit, MergeOutcome};
+use vibefs::commands::transplant::{apply_transplant, TransplantFile, TransplantOutcome};
+use vibefs::commands::snapshot::record_checkpoint_base;
+use vibefs::commands::spawn::SpawnInfo;
+use vibefs::config::Config;
+use vibefs::db::{InodeMetadata, MetadataStore};
 use vibefs::git::GitRepo;
+use vibefs::nfs::blob_cache::{BlobCache, BlobCacheStats};
 use vibefs::nfs::VibeNFS;
 use vibefs::platform;
+use vibefs::session_state::{list_tree, lookup_inode, SessionChanges, SessionStats};
 use vibefs::VERSION_FULL;
 
-/// Default idle timeout: 20 minutes
-const IDLE_TIMEOUT_SECS: u64 = 20 * 60;
+/// Budget for the blob cache shared by all sessions: 256 MB
+const BLOB_CACHE_BYTES: u64 = 256 * 1024 * 1024;
 
 /// Session state managed by the daemon
 struct Session {
@@ -43,6 +51,8 @@ struct DaemonState {
     repo_path: PathBuf,
     metadata: Arc<RwLock<MetadataStore>>,
     git: Arc<RwLock<GitRepo>>,
+    /// Decompressed blobs shared across sessions, keyed by oid
+    blob_cache: Arc<BlobCache>,
     sessions: HashMap<String, Session>,
     last_activity: Instant
 }
@@ -66,13 +76,36 @@ enum DaemonRequest {
     /// Get daemon status
     Status,
     /// Create/export a new session
-    ExportSession { vibe_id: String },
+    ExportSession {
+        vibe_id: String,
+        /// Commit a new session's inode map is built from (HEAD if absent)
+        #[serde(default)]
+        base: Option<String>,
+    },
     /// Unexport/remove a session
     UnexportSession { vibe_id: String },
     /// Reset session (discard all changes, keep NFS alive)
     ResetSession { vibe_id: String, no_backup: bool },
     /// Rebase session to current HEAD (keep NFS alive)
     RebaseSession { vibe_id: String, force: bool },
+    /// Make buffered NFS writes durable before the CLI reads the session
+    SyncSession { vibe_id: String },
+    /// Copy a session's delta and metadata into a new session directory
+    ForkSession { source: String, vibe_id: String },
+    /// Write files taken from another session into a session
+    TransplantFiles {
+        source: String,
+        vibe_id: String,
+        files: Vec<TransplantFile>,
+    },
+    /// Dirty and deleted paths of a session
+    GetDirtyPaths { vibe_id: String },
+    /// Metadata of one path in a session
+    GetInode { vibe_id: String, path: String },
+    /// Metadata of a path and everything under it ("" for the whole tree)
+    ListTree { vibe_id: String, path: String },
+    /// Inode, change and delta counts of a session
+    SessionStats { vibe_id: String },
     /// List active sessions
     ListSessions,
     /// Graceful shutdown
@@ -93,6 +126,8 @@ enum DaemonResponse {
         uptime_secs: u64,
         #[serde(skip_serializing_if = "Option::is_none")]
         version: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        blob_cache: Option<BlobCacheStats>,
     },
     SessionExported {
         vibe_id: String,
@@ -110,6 +145,44 @@ enum DaemonResponse {
         old_base: String,
         new_base: String,
         reconciled_count: usize,
+        base_changes: usize,
+        merged: usize,
+        conflicts: Vec<String>,
+        unmerged: Vec<String>,
+    },
+    SessionSynced {
+        vibe_id: String,
+        synced_files: usize,
+    },
+    SessionForked {
+        source: String,
+        vibe_id: String,
+        dirty_count: usize,
+    },
+    FilesTransplanted {
+        vibe_id: String,
+        outcome: TransplantOutcome,
+    },
+    /// The daemon isn't serving the session, so its store is free to open
+    SessionNotExported {
+        vibe_id: String,
+    },
+    DirtyPaths {
+        vibe_id: String,
+        dirty: Vec<String>,
+        deleted: Vec<String>,
+    },
+    Inode {
+        vibe_id: String,
+        inode: Option<InodeMetadata>,
+    },
+    Tree {
+        vibe_id: String,
+        entries: Vec<InodeMetadata>,
+    },
+    Stats {
+        vibe_id: String,
+        stats: SessionStats,
     },
     Sessions {
         sessions: Vec<SessionInfo>
@@ -180,10 +253,11 @@ async fn handle_client(
                     session_count: state.sessions.len(),
                     uptime_secs: start_time.elapsed().as_secs(),
                     version: Some(VERSION_FULL.to_string()),
+                    blob_cache: Some(state.blob_cache.stats()),
                 }
             }
 
-            DaemonRequest::ExportSession { vibe_id } => {
+            DaemonRequest::ExportSession { vibe_id, base } => {
                 let mut state_guard = state.lock().await;
 
                 // Check if session already exists
@@ -194,112 +268,15 @@ async fn handle_client(
                         mount_point: session.mount_point.display().to_string(),
                     }
                 } else {
-                    // Create new session
-                    let session_dir = state_guard.repo_path.join(".vibe/sessions").join(&vibe_id);
-
-                    // Get repo name for mount point
-                    let repo_name = state_guard.repo_path
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "repo".to_string());
-
-                    // Mount point format: <platform-specific-cache>/vibe/mounts/<repo_name>-<vibe_id>
-                    let mount_point = platform::get_vibe_mounts_dir()
-                        .join(format!("{}-{}", repo_name, vibe_id));
-
-                    match setup_session_resources(&session_dir, &mount_point) {
-                        Ok(_) => {
-                            // Create per-session metadata store (clone from base)
-                            let session_db_path = session_dir.join("metadata.db");
-                            let session_metadata = {
-                                let base_store = state_guard.metadata.read().await;
-                                base_store.clone_to(&session_db_path)
-                            };
-
-                            match session_metadata {
-                                Err(e) => DaemonResponse::Error {
-                                    message: format!("Failed to create session metadata: {}", e),
-                                },
-                                Ok(session_store) => {
-                                    let session_metadata = Arc::new(RwLock::new(session_store));
-
-                                    // Set up artifact symlinks using session-specific metadata
-                                    if let Err(e) = setup_artifact_symlinks(
-                                        &session_dir,
-                                        &vibe_id,
-                                        &session_metadata
-                                    ).await {
-                                        eprintln!("[vibed] Warning: Failed to setup artifact symlinks: {}", e);
-                                    }
-
-                                    let nfs = VibeNFS::new(
-                                        session_metadata.clone(),
-                                        state_guard.git.clone(),
-                                        session_dir.clone(),
-                                        state_guard.repo_path.clone(),
-                                        vibe_id.clone()
-                                    );
-
-                                    // Clone before passing to NFSTcpListener so we keep
-                                    // a handle with shared Arc-wrapped state.
-                                    let nfs_clone = nfs.clone();
-
-                                    if let Err(e) = nfs.build_directory_cache().await {
-                                        DaemonResponse::Error {
-                                            message: format!("Failed to build cache: {}", e),
-                                        }
-                                    } else {
-                                        match NFSTcpListener::bind("127.0.0.1:0", nfs).await {
-                                            Ok(listener) => {
-                                                let port = listener.get_listen_port();
-                                                let (sess_shutdown_tx, mut sess_shutdown_rx) = tokio::sync::broadcast::channel(1);
-                                                let vid = vibe_id.clone();
-
-                                                let nfs_handle = tokio::spawn(async move {
-                                                    eprintln!("[vibed] NFS server running for {} on port {}", vid, port);
-                                                    tokio::select! {
-                                                        res = listener.handle_forever() => {
-                                                            if let Err(e) = res {
-                                                                eprintln!("[vibed] NFS server error for {}: {}", vid, e);
-                                                            }
-                                                        }
-                                                        _ = sess_shutdown_rx.recv() => {
-                                                            eprintln!("[vibed] Stopping NFS server for {}", vid);
-                                                        }
-                                                    }
-                                                });
-
-                                                let session = Session {
-                                                    vibe_id: vibe_id.clone(),
-                                                    session_dir,
-                                                    mount_point: mount_point.clone(),
-                                                    nfs_port: port,
-                                                    created_at: Instant::now(),
-                                                    shutdown_tx: sess_shutdown_tx,
-                                                    nfs_task: Some(nfs_handle),
-                                                    metadata: session_metadata,
-                                                    nfs: nfs_clone,
-                                                };
-
-                                                state_guard.sessions.insert(vibe_id.clone(), session);
-
-                                                DaemonResponse::SessionExported {
-                                                    vibe_id,
-                                                    nfs_port: port,
-                                                    mount_point: mount_point.display().to_string(),
-                                                }
-                                            }
-                                            Err(e) => DaemonResponse::Error {
-                                                message: format!("Failed to bind NFS port: {}", e),
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    match export_session(&mut state_guard, &vibe_id, base, 0).await {
+                        Ok((nfs_port, mount_point)) => DaemonResponse::SessionExported {
+                            vibe_id,
+                            nfs_port,
+                            mount_point: mount_point.display().to_string(),
+                        },
                         Err(e) => DaemonResponse::Error {
-                            message: format!("Failed to create directories: {}", e),
-                        }
+                            message: format!("{:#}", e),
+                        },
                     }
                 }
             }
@@ -307,6 +284,9 @@ async fn handle_client(
             DaemonRequest::UnexportSession { vibe_id } => {
                 let mut state_guard = state.lock().await;
                 if let Some(mut session) = state_guard.sessions.remove(&vibe_id) {
+                    if let Err(e) = session.nfs.sync().await {
+                        eprintln!("[vibed] Failed to sync writes for {}: {}", vibe_id, e);
+                    }
                     // Stop the NFS server and wait for it to fully shut down
                     // (releases the metadata.db lock so CLI can access it)
                     let _ = session.shutdown_tx.send(());
@@ -337,10 +317,10 @@ async fn handle_client(
                     let session_dir = session.session_dir.clone();
                     let nfs = session.nfs.clone();
                     let metadata = session.metadata.clone();
-                    let sessions_dir = state_guard.repo_path.join(".vibe/sessions");
+                    let repo_path = state_guard.repo_path.clone();
                     drop(state_guard);
 
-                    match handle_reset_session(&vibe_id, &session_dir, &sessions_dir, &nfs, &metadata, no_backup).await {
+                    match handle_reset_session(&vibe_id, &session_dir, &repo_path, &nfs, &metadata, no_backup).await {
                         Ok(_) => DaemonResponse::SessionReset { vibe_id },
                         Err(e) => DaemonResponse::Error {
                             message: format!("Reset failed: {}", e),
@@ -364,11 +344,15 @@ async fn handle_client(
                     drop(state_guard);
 
                     match handle_rebase_session(&vibe_id, &session_dir, &repo_path, &nfs, &metadata, &git, force).await {
-                        Ok((old_base, new_base, reconciled_count)) => DaemonResponse::SessionRebased {
+                        Ok((old_base, new_base, reconciled_count, base_changes, merge)) => DaemonResponse::SessionRebased {
                             vibe_id,
                             old_base,
                             new_base,
                             reconciled_count,
+                            base_changes,
+                            merged: merge.merged.len(),
+                            conflicts: merge.conflicts,
+                            unmerged: merge.unmerged,
                         },
                         Err(e) => DaemonResponse::Error {
                             message: format!("Rebase failed: {}", e),
@@ -381,6 +365,87 @@ async fn handle_client(
                 }
             }
 
+            DaemonRequest::SyncSession { vibe_id } => {
+                let nfs = state.lock().await.sessions.get(&vibe_id).map(|s| s.nfs.clone());
+                match nfs {
+                    Some(nfs) => match nfs.sync().await {
+                        Ok(synced_files) => DaemonResponse::SessionSynced { vibe_id, synced_files },
+                        Err(e) => DaemonResponse::Error {
+                            message: format!("Sync failed: {}", e),
+                        },
+                    },
+                    None => DaemonResponse::Error {
+                        message: format!("Session '{}' not found", vibe_id),
+                    },
+                }
+            }
+
+            DaemonRequest::ForkSession { source, vibe_id } => {
+                let state_guard = state.lock().await;
+                let sessions_dir = state_guard.repo_path.join(".vibe/sessions");
+                let live = state_guard
+                    .sessions
+                    .get(&source)
+                    .map(|s| (s.nfs.clone(), s.metadata.clone()));
+                let base_metadata = state_guard.metadata.clone();
+                drop(state_guard);
+
+                match handle_fork_session(&sessions_dir.join(&source), &sessions_dir.join(&vibe_id), live, &base_metadata).await {
+                    Ok(dirty_count) => DaemonResponse::SessionForked { source, vibe_id, dirty_count },
+                    Err(e) => DaemonResponse::Error {
+                        message: format!("Fork failed: {}", e),
+                    },
+                }
+            }
+
+            DaemonRequest::TransplantFiles { source, vibe_id, files } => {
+                let state_guard = state.lock().await;
+                if let Some(session) = state_guard.sessions.get(&vibe_id) {
+                    let session_dir = session.session_dir.clone();
+                    let nfs = session.nfs.clone();
+                    let metadata = session.metadata.clone();
+                    let git = state_guard.git.clone();
+                    drop(state_guard);
+
+                    match handle_transplant_files(&source, &vibe_id, &files, &session_dir, &nfs, &metadata, &git).await {
+                        Ok(outcome) => DaemonResponse::FilesTransplanted { vibe_id, outcome },
+                        Err(e) => DaemonResponse::Error {
+                            message: format!("Transplant failed: {}", e),
+                        },
+                    }
+                } else {
+                    DaemonResponse::SessionNotExported { vibe_id }
+                }
+            }
+
+            DaemonRequest::GetDirtyPaths { vibe_id } => {
+                match query_session_store(&state, &vibe_id, |store, _| SessionChanges::read(store)).await {
+                    Ok(changes) => DaemonResponse::DirtyPaths { vibe_id, dirty: changes.dirty, deleted: changes.deleted },
+                    Err(e) => DaemonResponse::Error { message: format!("{:#}", e) },
+                }
+            }
+
+            DaemonRequest::GetInode { vibe_id, path } => {
+                match query_session_store(&state, &vibe_id, move |store, _| lookup_inode(store, &path)).await {
+                    Ok(inode) => DaemonResponse::Inode { vibe_id, inode },
+                    Err(e) => DaemonResponse::Error { message: format!("{:#}", e) },
+                }
+            }
+
+            DaemonRequest::ListTree { vibe_id, path } => {
+                match query_session_store(&state, &vibe_id, move |store, _| list_tree(store, &path)).await {
+                    Ok(entries) => DaemonResponse::Tree { vibe_id, entries },
+                    Err(e) => DaemonResponse::Error { message: format!("{:#}", e) },
+                }
+            }
+
+            DaemonRequest::SessionStats { vibe_id } => {
+                match query_session_store(&state, &vibe_id, SessionStats::collect).await {
+                    Ok(stats) => DaemonResponse::Stats { vibe_id, stats },
+                    Err(e) => DaemonResponse::Error { message: format!("{:#}", e) },
+                }
+            }
+
             DaemonRequest::ListSessions => {
                 let state = state.lock().await;
                 let sessions: Vec<SessionInfo> = state
@@ -411,28 +476,216 @@ async fn handle_client(
     Ok(())
 }
 
+/// Create a session's store and NFS server and register it in `state`.
+/// Serves on `port` when given and free, otherwise on any port. Returns
+/// the port and the mount point.
+async fn export_session(
+    state: &mut DaemonState,
+    vibe_id: &str,
+    base: Option<String>,
+    port: u16,
+) -> Result<(u16, PathBuf)> {
+    let session_dir = state.repo_path.join(".vibe/sessions").join(vibe_id);
+
+    // Get repo name for mount point
+    let repo_name = state.repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+
+    // Mount point format: <platform-specific-cache>/vibe/mounts/<repo_name>-<vibe_id>
+    let mount_point = platform::get_vibe_mounts_dir()
+        .join(format!("{}-{}", repo_name, vibe_id));
+
+    setup_session_resources(&session_dir, &mount_point).context("Failed to create directories")?;
+
+    // Create per-session metadata store (clone from base)
+    let session_db_path = session_dir.join("metadata.db");
+    let fresh_store = !session_db_path.exists();
+    let session_store = {
+        let base_store = state.metadata.read().await;
+        base_store.clone_to(&session_db_path)
+    }
+    .context("Failed to create session metadata")?;
+
+    // The base store describes the init-time tree; a new session starts
+    // from its own base commit
+    if fresh_store {
+        let g = state.git.read().await;
+        let base = base
+            .or_else(|| {
+                SpawnInfo::load(&state.repo_path, vibe_id)
+                    .ok()
+                    .and_then(|info| info.spawn_commit)
+            })
+            .or_else(|| g.head_commit().ok());
+        if let Some(base) = base {
+            move_store_to_commit(&g, &session_store, &base).context("Failed to create session metadata")?;
+        }
+    }
+
+    let handle_generation = session_store
+        .handle_generation()
+        .context("Failed to create session metadata")?;
+    let session_metadata = Arc::new(RwLock::new(session_store));
+
+    // Settings may have changed since the daemon started
+    let config = Config::load(&state.repo_path)?;
+
+    // Set up artifact symlinks using session-specific metadata
+    if let Err(e) = setup_artifact_symlinks(&session_dir, vibe_id, &session_metadata, &config).await {
+        eprintln!("[vibed] Warning: Failed to setup artifact symlinks: {}", e);
+    }
+
+    let nfs = VibeNFS::new(
+        session_metadata.clone(),
+        state.git.clone(),
+        session_dir.clone(),
+        state.repo_path.clone(),
+        vibe_id.to_string(),
+    )
+    .with_blob_cache(state.blob_cache.clone())
+    .with_handle_generation(handle_generation);
+
+    // Clone before passing to NFSTcpListener so we keep
+    // a handle with shared Arc-wrapped state.
+    let nfs_clone = nfs.clone();
+
+    nfs.build_directory_cache().await.context("Failed to build cache")?;
+
+    let listener = match NFSTcpListener::bind(&format!("127.0.0.1:{}", port), nfs.clone()).await {
+        Ok(listener) => listener,
+        Err(e) if port != 0 => {
+            eprintln!("[vibed] Port {} unavailable for {} ({}), using another", port, vibe_id, e);
+            NFSTcpListener::bind("127.0.0.1:0", nfs).await.context("Failed to bind NFS port")?
+        }
+        Err(e) => return Err(anyhow::Error::from(e).context("Failed to bind NFS port")),
+    };
+    let port = listener.get_listen_port();
+    let (sess_shutdown_tx, mut sess_shutdown_rx) = tokio::sync::broadcast::channel(1);
+    let vid = vibe_id.to_string();
+
+    let nfs_handle = tokio::spawn(async move {
+        eprintln!("[vibed] NFS server running for {} on port {}", vid, port);
+        tokio::select! {
+            res = listener.handle_forever() => {
+                if let Err(e) = res {
+                    eprintln!("[vibed] NFS server error for {}: {}", vid, e);
+                }
+            }
+            _ = sess_shutdown_rx.recv() => {
+                eprintln!("[vibed] Stopping NFS server for {}", vid);
+            }
+        }
+    });
+
+    let session = Session {
+        vibe_id: vibe_id.to_string(),
+        session_dir,
+        mount_point: mount_point.clone(),
+        nfs_port: port,
+        created_at: Instant::now(),
+        shutdown_tx: sess_shutdown_tx,
+        nfs_task: Some(nfs_handle),
+        metadata: session_metadata,
+        nfs: nfs_clone,
+    };
+
+    state.sessions.insert(vibe_id.to_string(), session);
+
+    Ok((port, mount_point))
+}
+
+/// Re-export every session a previous daemon was serving, so mounts left
+/// behind by a crash or idle shutdown work again. Sessions come back on
+/// their old port with the same file handles when possible, in which case
+/// existing mounts reconnect on their own; otherwise they are remounted.
+///
+/// Runs in the background once the daemon accepts connections, taking the
+/// state lock for one session at a time so requests are served in between.
+async fn recover_sessions(state: Arc<Mutex<DaemonState>>) {
+    let repo_path = state.lock().await.repo_path.clone();
+    let sessions_dir = repo_path.join(".vibe/sessions");
+    let Ok(entries) = std::fs::read_dir(&sessions_dir) else {
+        return;
+    };
+
+    let mut vibe_ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    vibe_ids.sort();
+
+    let mut recovered = Vec::new();
+    let mut failed = Vec::new();
+    for vibe_id in vibe_ids {
+        let Ok(mut info) = SpawnInfo::load(&repo_path, &vibe_id) else {
+            continue;
+        };
+        // Never served by a daemon (local sessions), or deleted since
+        if info.port == 0 || !sessions_dir.join(&vibe_id).is_dir() {
+            continue;
+        }
+
+        let exported = {
+            let mut state = state.lock().await;
+            // A client exported it while recovery was running
+            if state.sessions.contains_key(&vibe_id) {
+                continue;
+            }
+            export_session(&mut state, &vibe_id, info.spawn_commit.clone(), info.port).await
+        };
+
+        match exported {
+            Ok((port, mount_point)) => {
+                if port != info.port || mount_point != info.mount_point {
+                    info.port = port;
+                    info.mount_point = mount_point.clone();
+                    if let Err(e) = info.save(&repo_path) {
+                        eprintln!("[vibed] Failed to save spawn info for {}: {}", vibe_id, e);
+                    }
+                    let mount_point = mount_point.display().to_string();
+                    let mount = {
+                        let mount_point = mount_point.clone();
+                        move || platform::mount_nfs(&mount_point, port)
+                    };
+                    let mounted = tokio::task::spawn_blocking(mount)
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result);
+                    if let Err(e) = mounted {
+                        eprintln!("[vibed] Could not remount {} at {}: {}", vibe_id, mount_point, e);
+                    }
+                }
+                recovered.push(format!("{} (port {})", vibe_id, port));
+            }
+            Err(e) => {
+                eprintln!("[vibed] Failed to recover session {}: {:#}", vibe_id, e);
+                failed.push(vibe_id);
+            }
+        }
+    }
+
+    if !recovered.is_empty() {
+        eprintln!("[vibed] Recovered {} session(s): {}", recovered.len(), recovered.join(", "));
+    }
+    if !failed.is_empty() {
+        eprintln!("[vibed] Could not recover: {}", failed.join(", "));
+    }
+    state.lock().await.touch();
+}
+
 fn setup_session_resources(session_dir: &Path, mount_point: &Path) -> Result<()> {
     std::fs::create_dir_all(session_dir)?;
     std::fs::create_dir_all(mount_point)?;
     Ok(())
 }
 
-/// Directories that should be symlinked to local storage for performance
-/// and to avoid macOS NFS xattr issues with build tools.
-const ARTIFACT_DIRS: &[&str] = &[
-    "target",           // Rust/Cargo
-    "node_modules",     // Node.js/npm
-    ".venv",            // Python virtualenv
-    "__pycache__",      // Python cache
-    ".next",            // Next.js
-    ".nuxt",            // Nuxt.js
-    "dist",             // Common build output
-    "build",            // Common build output
-];
-
 /// Set up symlinks for build artifact directories and register them in metadata.
-/// These directories are symlinked to local storage (/tmp/vibe-artifacts/<session>/)
-/// to avoid macOS NFS xattr issues and improve build performance.
+/// These directories (`artifacts.dirs` in the config) are symlinked to local
+/// storage (`<artifacts_root>/<session>/`, /tmp/vibe-artifacts by default) to
+/// avoid macOS NFS xattr issues and improve build performance.
 ///
 /// Artifact dirs are OUTSIDE the CoW system:
 /// - NFS clients follow the symlink and read/write to local storage directly
@@ -443,19 +696,42 @@ async fn setup_artifact_symlinks(
     session_dir: &Path,
     vibe_id: &str,
     metadata: &Arc<RwLock<MetadataStore>>,
+    config: &Config,
 ) -> Result<()> {
     use vibefs::db::InodeMetadata;
 
-    let artifacts_base = PathBuf::from("/tmp/vibe-artifacts").join(vibe_id);
+    let artifacts_base = config.artifacts_dir(vibe_id);
+    let dir_names = {
+        let store = metadata.read().await;
+        config.artifact_paths(|| {
+            Ok(store
+                .get_all_inodes()?
+                .into_iter()
+                .filter(|(_, meta)| meta.is_dir)
+                .map(|(_, meta)| meta.path)
+                .collect())
+        })?
+    };
 
-    for dir_name in ARTIFACT_DIRS {
+    for dir_name in &dir_names {
+        let dir_name = dir_name.as_str();
         let local_path = artifacts_base.join(dir_name);
         let symlink_path = session_dir.join(dir_name);
 
+        // Links copied from another session (fork, snapshot) point at its storage
+        if std::fs::read_link(&symlink_path).is_ok_and(|target| target != local_path) {
+            std::fs::remove_file(&symlink_path)
+                .with_context(|| format!("Failed to replace symlink: {}", symlink_path.display()))?;
+        }
+
         // Ensure physical symlink exists in session directory
         if !symlink_path.exists() && !symlink_path.is_symlink() {
             std::fs::create_dir_all(&local_path)
                 .with_context(|| format!("Failed to create local artifact dir: {}", local_path.display()))?;
+            if let Some(parent) = symlink_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
 
             #[cfg(unix)]
             std::os::unix::fs::symlink(&local_path, &symlink_path)
@@ -466,20 +742,19 @@ async fn setup_artifact_symlinks(
         // clone_to may have copied an inode pointing to a different session's artifacts.
         let store = metadata.write().await;
         let target_str = local_path.to_string_lossy().to_string();
-        let expected_oid = format!("symlink:{}", target_str);
 
         if let Some(existing_id) = store.get_inode_by_path(dir_name)? {
             // Inode exists - verify it points to this session's artifacts
             if let Some(existing_meta) = store.get_inode(existing_id)? {
-                if existing_meta.git_oid.as_deref() != Some(&expected_oid) {
-                    // Wrong target (inherited from another session via clone_to) - fix it
+                if existing_meta.git_oid.is_some() || existing_meta.symlink_target.as_deref() != Some(&target_str) {
+                    // Wrong target (inherited from another session via clone_to,
+                    // or the older `symlink:` encoding) - fix it
                     let meta = InodeMetadata {
                         path: dir_name.to_string(),
-                        git_oid: Some(expected_oid),
-                        is_dir: false,
                         size: target_str.len() as u64,
                         volatile: true,
-                        mtime: 0,
+                        symlink_target: Some(target_str.clone()),
+                        ..Default::default()
                     };
                     store.put_inode(existing_id, &meta)?;
                 }
@@ -489,11 +764,10 @@ async fn setup_artifact_symlinks(
             let inode_id = store.next_inode_id()?;
             let meta = InodeMetadata {
                 path: dir_name.to_string(),
-                git_oid: Some(expected_oid),
-                is_dir: false,
                 size: target_str.len() as u64,
                 volatile: true,
-                mtime: 0,
+                symlink_target: Some(target_str),
+                ..Default::default()
             };
             store.put_inode(inode_id, &meta)?;
         }
@@ -502,12 +776,12 @@ async fn setup_artifact_symlinks(
     Ok(())
 }
 
-/// Handle ResetSession: clear session files and dirty markers, rebuild cache.
+/// Handle ResetSession: clear session files, dirty markers and whiteouts, rebuild cache.
 /// NFS server stays running throughout — no unmount/remount needed.
 async fn handle_reset_session(
     vibe_id: &str,
     session_dir: &Path,
-    sessions_dir: &Path,
+    repo_path: &Path,
     nfs: &VibeNFS,
     metadata: &Arc<RwLock<MetadataStore>>,
     no_backup: bool,
@@ -518,7 +792,9 @@ async fn handle_reset_session(
             "pre-reset-{}",
             chrono::Utc::now().format("%Y%m%d_%H%M%S")
         );
-        let backup_dir = sessions_dir.join(format!("{}_snapshot_{}", vibe_id, backup_name));
+        let backup_dir = repo_path
+            .join(".vibe/sessions")
+            .join(format!("{}_snapshot_{}", vibe_id, backup_name));
         eprintln!("[vibed] Creating backup snapshot: {}", backup_dir.display());
 
         #[cfg(target_os = "macos")]
@@ -543,15 +819,23 @@ async fn handle_reset_session(
                 .args(["-r", &session_dir.to_string_lossy(), &backup_dir.to_string_lossy()])
                 .output();
         }
+
+        if let Err(e) = record_checkpoint_base(repo_path, vibe_id, &backup_name) {
+            eprintln!("[vibed] Warning: failed to record backup snapshot base: {}", e);
+        }
     }
 
+    // Buffered writes belong to the changes being thrown away
+    nfs.discard_pending_writes().await;
+
     // Delete session files (keep symlinks for artifact dirs and metadata.db)
     clear_session_files(session_dir)?;
 
-    // Clear dirty markers in metadata
+    // Clear dirty markers and bring back deleted files
     {
         let store = metadata.write().await;
         store.clear_dirty()?;
+        store.restore_whiteouts()?;
     }
 
     // Rebuild the directory cache so NFS serves updated listings
@@ -564,6 +848,96 @@ async fn handle_reset_session(
     Ok(())
 }
 
+/// Handle ForkSession: copy the source's delta and metadata store into the
+/// new session's directory. A live source is paused (in-flight NFS changes
+/// finish, new ones wait, buffered writes are synced) for the copy.
+async fn handle_fork_session(
+    source_dir: &Path,
+    dest_dir: &Path,
+    live: Option<(VibeNFS, Arc<RwLock<MetadataStore>>)>,
+    base_metadata: &Arc<RwLock<MetadataStore>>,
+) -> Result<usize> {
+    if let Some((nfs, metadata)) = live {
+        let _paused = nfs.pause_mutations().await?;
+        let store = metadata.read().await;
+        return copy_session(source_dir, &store, dest_dir);
+    }
+
+    if !source_dir.exists() {
+        anyhow::bail!("Session directory {} does not exist", source_dir.display());
+    }
+    let source_db = source_dir.join("metadata.db");
+    if source_db.exists() {
+        let store = MetadataStore::open(&source_db)?;
+        copy_session(source_dir, &store, dest_dir)
+    } else {
+        // Not exported yet: its changes are tracked in the base store
+        let store = base_metadata.read().await;
+        copy_session(source_dir, &store, dest_dir)
+    }
+}
+
+/// Answer a metadata query from a session's store: the live one when the
+/// session is exported, otherwise the one on disk. The daemon lock is only
+/// held to find the store; the query itself (which may walk the session
+/// directory) runs on a blocking thread.
+async fn query_session_store<T, F>(state: &Mutex<DaemonState>, vibe_id: &str, query: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&MetadataStore, &Path) -> Result<T> + Send + 'static,
+{
+    // `None`: the session's own store on disk, opened for this query
+    let (store, session_dir) = {
+        let state = state.lock().await;
+        match state.sessions.get(vibe_id) {
+            Some(session) => (Some(session.metadata.clone()), session.session_dir.clone()),
+            None => {
+                let session_dir = state.repo_path.join(".vibe/sessions").join(vibe_id);
+                if !session_dir.is_dir() {
+                    anyhow::bail!("Session '{}' not found", vibe_id);
+                }
+                // Not exported yet: without a store of its own, its changes
+                // are tracked in the base store
+                let store = (!session_dir.join("metadata.db").exists()).then(|| state.metadata.clone());
+                (store, session_dir)
+            }
+        }
+    };
+
+    tokio::task::spawn_blocking(move || match store {
+        Some(store) => query(&store.blocking_read(), &session_dir),
+        None => {
+            let store = MetadataStore::open_readonly(session_dir.join("metadata.db"))
+                .context("Failed to open session metadata")?;
+            query(&store, &session_dir)
+        }
+    })
+    .await?
+}
+
+/// Handle TransplantFiles: write the files into the paused session, then
+/// rebuild the directory cache so NFS clients see new and removed files.
+async fn handle_transplant_files(
+    source: &str,
+    vibe_id: &str,
+    files: &[TransplantFile],
+    session_dir: &Path,
+    nfs: &VibeNFS,
+    metadata: &Arc<RwLock<MetadataStore>>,
+    git: &Arc<RwLock<GitRepo>>,
+) -> Result<TransplantOutcome> {
+    let outcome = {
+        let _paused = nfs.pause_mutations().await?;
+        let store = metadata.write().await;
+        let g = git.read().await;
+        apply_transplant(&g, &store, session_dir, source, vibe_id, files)?
+    };
+
+    nfs.invalidate_and_rebuild_cache().await?;
+    nfs.bump_init_time();
+    Ok(outcome)
+}
+
 /// Handle RebaseSession: reconcile stale files, update spawn_commit, rebuild cache.
 /// NFS server stays running throughout — no unmount/remount needed.
 async fn handle_rebase_session(
@@ -574,17 +948,22 @@ async fn handle_rebase_session(
     metadata: &Arc<RwLock<MetadataStore>>,
     git: &Arc<RwLock<GitRepo>>,
     _force: bool,
-) -> Result<(String, String, usize)> {
-    use vibefs::commands::spawn::SpawnInfo;
+) -> Result<(String, String, usize, usize, MergeOutcome)> {
+    use vibefs::commands::rebase::{apply_base_changes, merge_session_changes, record_merge_outcome, unresolved_conflicts};
+
+    // Reconciliation reads session files and inodes from disk
+    nfs.sync().await.context("Failed to sync buffered writes")?;
 
     // Load SpawnInfo
     let mut spawn_info = SpawnInfo::load(repo_path, vibe_id)
         .with_context(|| format!("Session '{}' not found", vibe_id))?;
 
-    // Get current HEAD
+    // Get current HEAD (or the tip of the session's base ref)
     let head_commit = {
         let g = git.read().await;
-        g.head_commit().context("Failed to get HEAD commit")?
+        spawn_info
+            .upstream_commit(&g)
+            .with_context(|| format!("Failed to resolve {}", spawn_info.upstream_name()))?
     };
 
     let old_base = spawn_info
@@ -594,7 +973,7 @@ async fn handle_rebase_session(
 
     // Already at HEAD?
     if old_base == head_commit {
-        return Ok((old_base, head_commit, 0));
+        return Ok((old_base, head_commit, 0, 0, MergeOutcome::default()));
     }
 
     eprintln!(
@@ -604,6 +983,44 @@ async fn handle_rebase_session(
         &head_commit[..12.min(head_commit.len())]
     );
 
+    // Three-way merge session files that also changed upstream, before
+    // reconciliation compares them with the new HEAD. The merged files are
+    // written only once every merge is computed, and put back if the inode
+    // map can't be moved, so a failure leaves the session on its old base
+    // with its own files.
+    let (merge, base_changes) = {
+        let g = git.read().await;
+        let merge = merge_session_changes(
+            &g,
+            session_dir,
+            vibe_id,
+            spawn_info.upstream_name(),
+            &old_base,
+            &head_commit,
+        )
+        .context("Failed to merge session changes")?;
+
+        // Move the inode map to the new base tree: upstream additions, deletions
+        // and new blob ids for files the session hasn't touched
+        let store = metadata.write().await;
+        merge
+            .write(Some(&*store), session_dir)
+            .context("Failed to write merged session files")?;
+        match apply_base_changes(&g, &store, &old_base, &head_commit) {
+            Ok(changes) => (merge.outcome(), changes.total()),
+            Err(e) => {
+                merge.restore(Some(&*store), session_dir);
+                return Err(e.context("Failed to move the session to the new base"));
+            }
+        }
+    };
+
+    // Save the new base together with the conflicts it brought
+    spawn_info.spawn_commit = Some(head_commit.clone());
+    spawn_info.conflicts = unresolved_conflicts(session_dir, &spawn_info.conflicts);
+    record_merge_outcome(&mut spawn_info, &merge);
+    spawn_info.save(repo_path)?;
+
     // Reconcile stale session files: remove files that match the new HEAD
     let reconciled = {
         let g = git.read().await;
@@ -647,17 +1064,20 @@ async fn handle_rebase_session(
                 _ => {}
             }
         }
+
+        // Deletions that HEAD now also has are no longer session changes
+        {
+            let store = metadata.write().await;
+            for path in store.get_whiteout_paths()? {
+                if let Ok(None) = g.read_file_at_commit(&head_commit, &path) {
+                    store.clear_whiteout(&path)?;
+                    reconciled += 1;
+                }
+            }
+        }
         reconciled
     };
 
-    // Update spawn_commit in session JSON
-    spawn_info.spawn_commit = Some(head_commit.clone());
-    let info_path = repo_path
-        .join(".vibe/sessions")
-        .join(format!("{}.json", vibe_id));
-    let info_json = serde_json::to_string_pretty(&spawn_info)?;
-    std::fs::write(&info_path, info_json)?;
-
     // Rebuild the directory cache so NFS serves updated listings
     nfs.invalidate_and_rebuild_cache().await?;
 
@@ -665,10 +1085,10 @@ async fn handle_rebase_session(
     nfs.bump_init_time();
 
     eprintln!(
-        "[vibed] Session '{}' rebased ({} stale files reconciled)",
-        vibe_id, reconciled
+        "[vibed] Session '{}' rebased ({} base paths updated, {} stale files reconciled)",
+        vibe_id, base_changes, reconciled
     );
-    Ok((old_base, head_commit, reconciled))
+    Ok((old_base, head_commit, reconciled, base_changes, merge))
 }
 
 /// Clear all user files from a session directory, keeping symlinks (artifact dirs)
@@ -796,6 +1216,9 @@ async fn run_daemon(repo_path: PathBuf, foreground: bool) -> Result<()> {
         );
     }
 
+    // An invalid config stops the daemon before it takes the socket
+    let config = Config::load(&repo_path)?;
+
     let socket_path = get_socket_path(&repo_path);
     let pid_path = get_pid_path(&repo_path);
 
@@ -829,6 +1252,7 @@ async fn run_daemon(repo_path: PathBuf, foreground: bool) -> Result<()> {
         repo_path: repo_path.clone(),
         metadata: Arc::new(RwLock::new(metadata)),
         git: Arc::new(RwLock::new(git)),
+        blob_cache: Arc::new(BlobCache::new(BLOB_CACHE_BYTES)),
         sessions: HashMap::new(),
         last_activity: Instant::now(),
     }));
@@ -846,6 +1270,11 @@ async fn run_daemon(repo_path: PathBuf, foreground: bool) -> Result<()> {
     eprintln!("[vibed] Listening on {}", socket_path.display());
     eprintln!("[vibed] Daemon ready to accept connections");
 
+    // Bring back sessions a previous daemon was serving. This can take a
+    // while (exports and remounts), so clients aren't kept waiting for it.
+    eprintln!("[vibed] Recovering sessions in the background...");
+    tokio::spawn(recover_sessions(state.clone()));
+
     // Shutdown channel
     let (shutdown_tx, _shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
     let start_time = Instant::now();
@@ -853,7 +1282,8 @@ async fn run_daemon(repo_path: PathBuf, foreground: bool) -> Result<()> {
     // Start idle checker task
     let idle_state = state.clone();
     let idle_shutdown_tx = shutdown_tx.clone();
-    let idle_timeout = Duration::from_secs(IDLE_TIMEOUT_SECS);
+    let idle_timeout = config.idle_timeout();
+    eprintln!("[vibed] Idle timeout: {}s", idle_timeout.as_secs());
     let idle_handle = tokio::spawn(async move {
         run_idle_checker(idle_state, idle_shutdown_tx, idle_timeout).await;
     });
@@ -895,6 +1325,9 @@ async fn run_daemon(repo_path: PathBuf, foreground: bool) -> Result<()> {
     {
         let mut s = state.lock().await;
         for (_, session) in s.sessions.drain() {
+            if let Err(e) = session.nfs.sync().await {
+                eprintln!("[vibed] Failed to sync writes for {}: {}", session.vibe_id, e);
+            }
             let _ = session.shutdown_tx.send(());
         }
     }
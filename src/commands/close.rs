@@ -8,6 +8,7 @@ use crate::commands::spawn::cleanup_artifact_symlinks;
 use crate::daemon_client::DaemonClient;
 use crate::daemon_ipc::DaemonResponse;
 use crate::platform;
+use crate::session_state::SessionState;
 
 /// Close a single session, unmounting and cleaning up its data
 pub async fn close<P: AsRef<Path>>(
@@ -24,8 +25,9 @@ pub async fn close<P: AsRef<Path>>(
         anyhow::bail!("Session '{}' not found", session_id);
     }
 
-    // Show dirty files if requested or before confirmation
-    let dirty_files = collect_dirty_files(&session_dir)?;
+    // Show dirty files if requested or before confirmation. Read them before
+    // unexporting, while the daemon still holds the session's store.
+    let dirty_files = list_dirty(repo_path, session_id).await?;
 
     if show_dirty || !dirty_files.is_empty() {
         if !dirty_files.is_empty() {
@@ -141,7 +143,7 @@ pub async fn close<P: AsRef<Path>>(
     }
 
     // Clean up local artifact directories
-    if let Err(e) = cleanup_artifact_symlinks(session_id) {
+    if let Err(e) = cleanup_artifact_symlinks(repo_path, session_id) {
         eprintln!("Warning: Failed to cleanup artifact directories: {}", e);
     }
 
@@ -160,133 +162,9 @@ pub async fn close<P: AsRef<Path>>(
     Ok(())
 }
 
-/// Collect dirty files in a session directory
-fn collect_dirty_files(session_dir: &Path) -> Result<Vec<String>> {
-    let mut dirty = Vec::new();
-    collect_files_recursive(session_dir, session_dir, &mut dirty)?;
-    Ok(dirty)
-}
-
-fn collect_files_recursive(
-    base: &Path,
-    current: &Path,
-    files: &mut Vec<String>,
-) -> Result<()> {
-    if !current.exists() {
-        return Ok(());
-    }
-
-    for entry in std::fs::read_dir(current)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // Skip macOS resource fork files (._filename) and .DS_Store
-        if let Some(name) = path.file_name() {
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with("._") || name_str == ".DS_Store" {
-                continue;
-            }
-        }
-
-        // Skip symlinks - they point to external storage (like /tmp/vibe-artifacts)
-        // and their contents are not part of the session's dirty files
-        if path.is_symlink() {
-            continue;
-        }
-
-        // Skip metadata.db (per-session RocksDB store lives inside session dir)
-        if let Some(name) = path.file_name() {
-            if name == "metadata.db" {
-                continue;
-            }
-        }
-
-        if path.is_dir() {
-            collect_files_recursive(base, &path, files)?;
-        } else {
-            // Get relative path from session dir
-            if let Ok(rel) = path.strip_prefix(base) {
-                files.push(rel.display().to_string());
-            }
-        }
-    }
-
-    Ok(())
-}
-
-/// List dirty files for a session without closing
+/// List dirty files for a session without closing, deleted ones marked as such
 pub async fn list_dirty<P: AsRef<Path>>(repo_path: P, session_id: &str) -> Result<Vec<String>> {
-    let repo_path = repo_path.as_ref();
-    let vibe_dir = repo_path.join(".vibe");
-    let session_dir = vibe_dir.join("sessions").join(session_id);
-
-    if !session_dir.exists() {
-        anyhow::bail!("Session '{}' not found", session_id);
-    }
-
-    collect_dirty_files(&session_dir)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
-
-    #[test]
-    fn test_collect_dirty_files_skips_symlinks() {
-        let temp_dir = TempDir::new().unwrap();
-        let session_dir = temp_dir.path();
-
-        // Create a regular file
-        fs::write(session_dir.join("regular.txt"), "content").unwrap();
-
-        // Create a subdirectory with a file
-        fs::create_dir(session_dir.join("subdir")).unwrap();
-        fs::write(session_dir.join("subdir/nested.txt"), "nested").unwrap();
-
-        // Create a symlink to an external directory
-        let external_dir = TempDir::new().unwrap();
-        fs::write(external_dir.path().join("external.txt"), "external").unwrap();
-
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(external_dir.path(), session_dir.join("target")).unwrap();
-
-        // Collect dirty files
-        let dirty = collect_dirty_files(session_dir).unwrap();
-
-        // Should include regular files but NOT files inside symlinked directories
-        assert!(dirty.contains(&"regular.txt".to_string()));
-        assert!(dirty.contains(&"subdir/nested.txt".to_string()));
-
-        // Should NOT contain files from symlinked directory
-        assert!(!dirty.iter().any(|f| f.starts_with("target/")));
-        assert!(!dirty.contains(&"target/external.txt".to_string()));
-    }
-
-    #[test]
-    fn test_collect_dirty_files_skips_macos_metadata() {
-        let temp_dir = TempDir::new().unwrap();
-        let session_dir = temp_dir.path();
-
-        // Create regular files
-        fs::write(session_dir.join("normal.txt"), "content").unwrap();
-
-        // Create macOS metadata files that should be skipped
-        fs::write(session_dir.join("._hidden"), "apple double").unwrap();
-        fs::write(session_dir.join(".DS_Store"), "ds store").unwrap();
-
-        let dirty = collect_dirty_files(session_dir).unwrap();
-
-        assert!(dirty.contains(&"normal.txt".to_string()));
-        assert!(!dirty.contains(&"._hidden".to_string()));
-        assert!(!dirty.contains(&".DS_Store".to_string()));
-    }
-
-    #[test]
-    fn test_collect_dirty_files_empty_dir() {
-        let temp_dir = TempDir::new().unwrap();
-        let dirty = collect_dirty_files(temp_dir.path()).unwrap();
-        assert!(dirty.is_empty());
-    }
+    let changes = SessionState::open(repo_path.as_ref(), session_id).await?.changes().await?;
+    let deleted = changes.deleted.into_iter().map(|path| format!("{} (deleted)", path));
+    Ok(changes.dirty.into_iter().chain(deleted).collect())
 }
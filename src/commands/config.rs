@@ -0,0 +1,46 @@
+//! `vibe config` command - Show and change VibeFS settings
+
+use anyhow::Result;
+use std::path::Path;
+use toml::Value;
+
+use crate::config::{self, Config};
+
+/// Print the effective value of `key` (strings unquoted)
+pub fn get(repo_path: &Path, key: &str) -> Result<()> {
+    match Config::load(repo_path)?.get(key)? {
+        Some(Value::String(value)) => println!("{}", value),
+        Some(value) => println!("{}", value),
+        None => anyhow::bail!("'{}' is not set", key),
+    }
+    Ok(())
+}
+
+/// Set `key` in the repository's `.vibe/config.toml`, or in the user config
+/// with `global`
+pub fn set(repo_path: &Path, key: &str, value: &str, global: bool) -> Result<()> {
+    let path = if global {
+        config::user_config_path()
+            .ok_or_else(|| anyhow::anyhow!("Cannot locate the user config: HOME is not set"))?
+    } else {
+        if !repo_path.join(".vibe").exists() {
+            anyhow::bail!("VibeFS not initialized. Run 'vibe init' first, or use --global.");
+        }
+        if key == "paths.mounts_root" {
+            anyhow::bail!("'paths.mounts_root' is read from the user config only; use --global");
+        }
+        config::repo_config_path(repo_path)
+    };
+
+    config::set_value(&path, key, value)?;
+    println!("Set {} in {}", key, path.display());
+    Ok(())
+}
+
+/// Print every effective setting, defaults included
+pub fn list(repo_path: &Path) -> Result<()> {
+    for (key, value) in Config::load(repo_path)?.entries()? {
+        println!("{} = {}", key, value);
+    }
+    Ok(())
+}
@@ -1,163 +1,731 @@
 //! `vibe diff` command - Show unified diff of session changes
 
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
 use std::io::{IsTerminal, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use crate::commands::spawn::SpawnInfo;
-use crate::db::MetadataStore;
+use crate::db::{InodeMetadata, MetadataStore};
 use crate::git::GitRepo;
 use crate::gitignore::PromoteFilter;
+use crate::linediff::{DiffOptions, LineDiff, LineKind};
+use crate::session_state::{lookup_inode, SessionChanges, SessionState};
 
-/// Show unified diff of session changes against base commit
-pub async fn diff<P: AsRef<Path>>(
-    repo_path: P,
-    session: &str,
-    stat_only: bool,
-    color: ColorOption,
-    no_pager: bool,
-) -> Result<()> {
-    let repo_path = repo_path.as_ref();
-    let vibe_dir = repo_path.join(".vibe");
+/// A session's virtual tree: changed paths are read from the session delta,
+/// everything else resolves through the session's base commit. A checkpoint
+/// or a plain commit can stand in for a session.
+pub struct SessionTree {
+    pub session: String,
+    pub base_commit: String,
+    session_dir: PathBuf,
+    /// Metadata of the changed paths that still exist
+    inodes: HashMap<String, InodeMetadata>,
+    /// Promotable dirty paths and whiteouts
+    changed: BTreeSet<String>,
+}
+
+impl SessionTree {
+    /// The tree of a commit, with no session changes on top
+    pub fn at_commit(name: &str, commit: &str) -> Self {
+        Self {
+            session: name.to_string(),
+            base_commit: commit.to_string(),
+            session_dir: PathBuf::new(),
+            inodes: HashMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+
+    /// Open the tree a session had when checkpoint `name` was saved
+    /// (`<session>_snapshot_<name>`), on the session's current base commit
+    pub fn open_checkpoint(repo_path: &Path, session: &str, name: &str) -> Result<Self> {
+        let snapshot_dir = repo_path
+            .join(".vibe/sessions")
+            .join(format!("{}_snapshot_{}", session, name));
+        if !snapshot_dir.is_dir() {
+            anyhow::bail!("Checkpoint '{}' not found for session '{}'", name, session);
+        }
+        // Compare against the base the checkpoint was taken on. Checkpoints
+        // saved before bases were recorded fall back to the current one.
+        let (spawn_info, current_base) = load_base_commit(repo_path, session)?;
+        let base_commit = spawn_info.checkpoints.get(name).cloned().unwrap_or(current_base);
+
+        // Snapshots copy the session's own store when it has one; otherwise
+        // the files present in the snapshot are what the session had changed.
+        // The daemon never serves a snapshot's store, so it is read directly.
+        let snapshot_db = snapshot_dir.join("metadata.db");
+        let (inodes, changed) = if snapshot_db.exists() {
+            let store = MetadataStore::open_readonly(&snapshot_db)
+                .with_context(|| format!("Failed to open metadata store of checkpoint '{}'", name))?;
+            let changed = changed_paths(repo_path, &snapshot_dir, SessionChanges::read(&store)?);
+            let mut inodes = HashMap::new();
+            for path in &changed {
+                if let Some(inode) = lookup_inode(&store, path)? {
+                    inodes.insert(path.clone(), inode);
+                }
+            }
+            (inodes, changed)
+        } else {
+            let mut files = Vec::new();
+            collect_files(&snapshot_dir, "", &mut files)?;
+            let changed = match PromoteFilter::new(repo_path, Some(&snapshot_dir)) {
+                Ok(filter) => filter.filter_promotable(&files).into_iter().cloned().collect(),
+                Err(_) => files.into_iter().collect(),
+            };
+            (HashMap::new(), changed)
+        };
+
+        Ok(Self {
+            session: format!("{}@{}", session, name),
+            base_commit,
+            session_dir: snapshot_dir,
+            inodes,
+            changed,
+        })
+    }
+
+    /// Open a session's tree, querying its metadata through the daemon when
+    /// it is running
+    pub async fn open(repo_path: &Path, session: &str) -> Result<Self> {
+        let (spawn_info, base_commit) = load_base_commit(repo_path, session)?;
+
+        let mut state = SessionState::open(repo_path, session).await?;
+        let changed = changed_paths(repo_path, &spawn_info.session_dir, state.changes().await?);
+        let mut inodes = HashMap::new();
+        for path in &changed {
+            if let Some(inode) = state.inode(path).await? {
+                inodes.insert(path.clone(), inode);
+            }
+        }
+
+        Ok(Self {
+            session: session.to_string(),
+            base_commit,
+            session_dir: spawn_info.session_dir,
+            inodes,
+            changed,
+        })
+    }
+
+    /// Paths the session changed relative to its base commit
+    pub fn changed_paths(&self) -> &BTreeSet<String> {
+        &self.changed
+    }
+
+    /// Content of `path` in the session's tree, `None` if it doesn't exist
+    pub fn read(&self, git: &GitRepo, path: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.read_entry(git, path)?.map(|(content, _)| content))
+    }
+
+    /// Content and Git mode (100644, 100755 or 120000) of `path` in the
+    /// session's tree, `None` if it doesn't exist
+    pub fn read_entry(&self, git: &GitRepo, path: &str) -> Result<Option<(Vec<u8>, u32)>> {
+        if !self.changed.contains(path) {
+            return git.read_entry_at_commit(&self.base_commit, path);
+        }
+
+        // Symlinks hold their target in metadata
+        let inode = self.inodes.get(path);
+        if let Some(target) = inode.and_then(|m| m.link_target()) {
+            return Ok(Some((target.as_bytes().to_vec(), 0o120000)));
+        }
+
+        let session_file = self.session_dir.join(path);
+        if !session_file.is_file() {
+            return Ok(None);
+        }
+        let mode = match inode {
+            Some(inode) => u32::from_str_radix(inode.git_file_mode(), 8).unwrap_or(0o100644),
+            None => 0o100644,
+        };
+        Ok(std::fs::read(&session_file).ok().map(|content| (content, mode)))
+    }
+}
 
-    // Load session info
+/// A session's spawn info and the commit its changes apply to
+fn load_base_commit(repo_path: &Path, session: &str) -> Result<(SpawnInfo, String)> {
     let spawn_info = SpawnInfo::load(repo_path, session)
         .with_context(|| format!("Session '{}' not found. Run 'vibe status' to see active sessions.", session))?;
 
-    // Get spawn commit
-    let spawn_commit = spawn_info.spawn_commit.clone().ok_or_else(|| {
+    let base_commit = spawn_info.spawn_commit.clone().ok_or_else(|| {
         anyhow::anyhow!(
             "Session '{}' has no spawn commit recorded. Cannot compute diff.\n\
              This may be a session created with an older version of VibeFS.",
             session
         )
     })?;
+    Ok((spawn_info, base_commit))
+}
+
+/// Promotable dirty paths and whiteouts of a session
+fn changed_paths(repo_path: &Path, session_dir: &Path, changes: SessionChanges) -> BTreeSet<String> {
+    // Filter out gitignored files
+    let mut changed: BTreeSet<String> = if let Ok(filter) = PromoteFilter::new(repo_path, Some(session_dir)) {
+        filter.filter_promotable(&changes.dirty).into_iter().cloned().collect()
+    } else {
+        changes.dirty.into_iter().collect()
+    };
+
+    // Deleted base files are tracked as whiteouts rather than dirty marks
+    changed.extend(changes.deleted);
+    changed
+}
+
+/// Regular files under `dir`, relative to the session root. The metadata
+/// store and symlinks (artifact directories) are skipped.
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        let file_type = entry.file_type()?;
+        if path == "metadata.db" || file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(&entry.path(), &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// What `vibe diff --against` compares a session with: `head` for the
+/// current HEAD, the name of one of the session's checkpoints, or any
+/// revision
+pub fn resolve_against(repo_path: &Path, git: &GitRepo, session: &str, against: &str) -> Result<SessionTree> {
+    if against.eq_ignore_ascii_case("head") {
+        let head = git.head_commit().context("Failed to resolve HEAD")?;
+        return Ok(SessionTree::at_commit("HEAD", &head));
+    }
+
+    let snapshot_dir = repo_path
+        .join(".vibe/sessions")
+        .join(format!("{}_snapshot_{}", session, against));
+    if snapshot_dir.is_dir() {
+        return SessionTree::open_checkpoint(repo_path, session, against);
+    }
+
+    let commit = git.resolve_commit(against).with_context(|| {
+        format!("'{}' is neither a checkpoint of session '{}' nor a revision", against, session)
+    })?;
+    Ok(SessionTree::at_commit(against, &commit))
+}
+
+/// How a file differs between the two sides of a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    /// Moved from `old_path`, possibly with changes
+    Renamed,
+    /// Same content, different mode
+    ModeChanged,
+}
+
+impl FileStatus {
+    fn label(self) -> &'static str {
+        match self {
+            FileStatus::Added => "new file",
+            FileStatus::Deleted => "deleted file",
+            FileStatus::Modified => "modified",
+            FileStatus::Renamed => "renamed",
+            FileStatus::ModeChanged => "mode changed",
+        }
+    }
+}
+
+/// One file that differs, with both sides' content (empty when absent)
+/// and Git mode (0 when absent)
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    pub path: String,
+    /// Where a renamed file was moved from
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+    pub old_mode: u32,
+    pub new_mode: u32,
+}
+
+impl FileDiff {
+    /// Compare two versions of a file, given as `(content, mode)`; `None` if
+    /// they are the same
+    pub fn new(path: &str, old: Option<(Vec<u8>, u32)>, new: Option<(Vec<u8>, u32)>) -> Option<Self> {
+        let status = match (&old, &new) {
+            (None, Some(_)) => FileStatus::Added,
+            (Some(_), None) => FileStatus::Deleted,
+            (Some(old), Some(new)) if old.0 != new.0 => FileStatus::Modified,
+            (Some(old), Some(new)) if old.1 != new.1 => FileStatus::ModeChanged,
+            _ => return None,
+        };
+        let (old, old_mode) = old.unwrap_or_default();
+        let (new, new_mode) = new.unwrap_or_default();
+        Some(Self { path: path.to_string(), old_path: None, status, old, new, old_mode, new_mode })
+    }
+
+    /// Path on the old side
+    pub fn old_path(&self) -> &str {
+        self.old_path.as_deref().unwrap_or(&self.path)
+    }
+
+    pub fn is_binary(&self) -> bool {
+        is_binary(&self.old) || is_binary(&self.new)
+    }
+
+    /// (insertions, deletions) in changed lines, as reported by `--stat`
+    pub fn line_counts(&self, options: DiffOptions) -> (usize, usize) {
+        if self.is_binary() {
+            return (0, 0);
+        }
+        let (old, new) = (String::from_utf8_lossy(&self.old), String::from_utf8_lossy(&self.new));
+        LineDiff::new(&old, &new, options).counts()
+    }
+
+    /// Hunks as listed by `--json`; none for binary files
+    pub fn json_hunks(&self, options: DiffOptions) -> Vec<JsonHunk> {
+        if self.is_binary() {
+            return Vec::new();
+        }
+        let (old, new) = (String::from_utf8_lossy(&self.old), String::from_utf8_lossy(&self.new));
+        let diff = LineDiff::new(&old, &new, options);
+        diff.hunks()
+            .iter()
+            .map(|hunk| JsonHunk {
+                header: hunk.header(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old.len(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new.len(),
+                lines: diff
+                    .hunk_lines(hunk)
+                    .into_iter()
+                    .map(|line| JsonLine {
+                        kind: line.kind,
+                        old_line: line.old_line,
+                        new_line: line.new_line,
+                        text: line.text.strip_suffix('\n').unwrap_or(line.text).to_string(),
+                        no_newline: !line.text.ends_with('\n'),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// One line of a `--json` hunk; `text` has no line terminator
+#[derive(Debug, Serialize)]
+pub struct JsonLine {
+    pub kind: LineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+    /// The line ends its file without a newline
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_newline: bool,
+}
+
+/// One hunk of `--json` output. Starts are numbered as in the `@@` header.
+#[derive(Debug, Serialize)]
+pub struct JsonHunk {
+    pub header: String,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<JsonLine>,
+}
+
+/// Per-file entry of `--json` output. Oids and modes are absent on the side
+/// where the file doesn't exist.
+#[derive(Debug, Serialize)]
+pub struct FileSummary {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+    pub old_oid: Option<String>,
+    pub new_oid: Option<String>,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub binary: bool,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<JsonHunk>,
+}
+
+/// `vibe diff --json` output
+#[derive(Debug, Serialize)]
+pub struct DiffOutput {
+    /// Old side: a commit, or a session when comparing two sessions
+    pub from: String,
+    /// New side: a session
+    pub to: String,
+    pub files: Vec<FileSummary>,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffOutput {
+    pub fn new(git: &GitRepo, from: &str, to: &str, files: &[FileDiff], options: DiffOptions) -> Self {
+        let files: Vec<FileSummary> = files
+            .iter()
+            .map(|file| {
+                let (insertions, deletions) = file.line_counts(options);
+                let has_old = file.status != FileStatus::Added;
+                let has_new = file.status != FileStatus::Deleted;
+                FileSummary {
+                    path: file.path.clone(),
+                    old_path: file.old_path.clone(),
+                    status: file.status,
+                    old_oid: has_old.then(|| git.hash_blob(&file.old)),
+                    new_oid: has_new.then(|| git.hash_blob(&file.new)),
+                    old_mode: has_old.then(|| format!("{:06o}", file.old_mode)),
+                    new_mode: has_new.then(|| format!("{:06o}", file.new_mode)),
+                    binary: file.is_binary(),
+                    insertions,
+                    deletions,
+                    hunks: file.json_hunks(options),
+                }
+            })
+            .collect();
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            files_changed: files.len(),
+            insertions: files.iter().map(|f| f.insertions).sum(),
+            deletions: files.iter().map(|f| f.deletions).sum(),
+            files,
+        }
+    }
+}
+
+/// How `vibe diff` prints its result
+#[derive(Debug, Clone, Copy)]
+pub struct DiffDisplay {
+    pub stat_only: bool,
+    pub name_status: bool,
+    pub numstat: bool,
+    pub json: bool,
+    pub format: DiffFormat,
+    pub options: DiffOptions,
+    pub color: ColorOption,
+    pub no_pager: bool,
+}
+
+impl DiffDisplay {
+    /// Whether the output comes from `git diff` (patches that `git apply`
+    /// accepts, and Git's file listings)
+    fn uses_git(&self) -> bool {
+        self.format == DiffFormat::Git || self.name_status || self.numstat
+    }
+}
+
+/// Show unified diff of session changes against base commit, or against
+/// `against` (see `resolve_against`)
+pub async fn diff<P: AsRef<Path>>(
+    repo_path: P,
+    session: &str,
+    against: Option<&str>,
+    display: DiffDisplay,
+) -> Result<()> {
+    let repo_path = repo_path.as_ref();
+    let tree = SessionTree::open(repo_path, session).await?;
+    let spawn_commit = &tree.base_commit;
+
+    if let Some(against) = against {
+        let git_repo = GitRepo::open(repo_path)?;
+        let old = resolve_against(repo_path, &git_repo, session, against)?;
+        let files = compare_sessions(&git_repo, &old, &tree)?;
+        let no_changes = format!("No changes in session '{}' against {}", session, against);
+        return show(&git_repo, &old.session, session, files, display, &no_changes);
+    }
 
     // Check if session is behind HEAD
     let git_repo = GitRepo::open(repo_path)?;
     if let Ok(head_commit) = git_repo.head_commit() {
-        if spawn_commit != head_commit {
+        if *spawn_commit != head_commit {
             eprintln!("⚠ Note: Session '{}' is based on {} but HEAD is at {}",
                 session, &spawn_commit[..7.min(spawn_commit.len())], &head_commit[..7.min(head_commit.len())]);
-            eprintln!("  Diff shows changes against session base, not current HEAD.");
+            eprintln!("  Diff shows changes against session base, not current HEAD (see --against head).");
             eprintln!("  Run 'vibe rebase {}' to update if needed.\n", session);
         }
     }
 
-    // Open per-session metadata store (fallback to base for backward compat)
-    let db_path = {
-        let session_db = vibe_dir.join("sessions").join(session).join("metadata.db");
-        if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") }
-    };
-    let store = MetadataStore::open_readonly(&db_path)
-        .context("Failed to open metadata store. Is another vibe command running?")?;
-    let all_dirty_paths = store.get_dirty_paths()?;
+    let mut files = Vec::new();
+    for path in tree.changed_paths() {
+        let base_entry = git_repo.read_entry_at_commit(spawn_commit, path)?;
+        files.extend(FileDiff::new(path, base_entry, tree.read_entry(&git_repo, path)?));
+    }
 
-    // Filter out gitignored files
-    let session_dir_path = vibe_dir.join("sessions").join(session);
-    let dirty_paths = if let Ok(filter) = PromoteFilter::new(repo_path, Some(&session_dir_path)) {
-        filter.filter_promotable(&all_dirty_paths).into_iter().cloned().collect::<Vec<_>>()
-    } else {
-        all_dirty_paths
-    };
+    let no_changes = format!("No changes in session '{}'", session);
+    show(&git_repo, spawn_commit, session, files, display, &no_changes)
+}
+
+/// Show the differences between two sessions' trees: files either session
+/// changed, plus files that differ between their base commits
+pub async fn compare<P: AsRef<Path>>(repo_path: P, a: &str, b: &str, display: DiffDisplay) -> Result<()> {
+    let repo_path = repo_path.as_ref();
+    let git_repo = GitRepo::open(repo_path)?;
+    let files = compare_sessions(&git_repo, &SessionTree::open(repo_path, a).await?, &SessionTree::open(repo_path, b).await?)?;
+    let no_changes = format!("Sessions '{}' and '{}' have the same tree", a, b);
+    show(&git_repo, a, b, files, display, &no_changes)
+}
 
-    if dirty_paths.is_empty() {
-        println!("No changes in session '{}'", session);
+/// Print changed files (renames paired up) as JSON, or as text in the
+/// requested format. Both views come from the same list.
+fn show(
+    git: &GitRepo,
+    from: &str,
+    to: &str,
+    files: Vec<FileDiff>,
+    display: DiffDisplay,
+    no_changes: &str,
+) -> Result<()> {
+    let files = find_renames(git, files)?;
+
+    if display.json {
+        let output = DiffOutput::new(git, from, to, &files, display.options);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+    if files.is_empty() {
+        println!("{}", no_changes);
         return Ok(());
     }
 
-    // Build the diff output
-    let session_dir = spawn_info.session_dir;
-
-    let mut diff_output = String::new();
+    print_diff(&render(git, &files, display)?, display)
+}
 
-    for path in &dirty_paths {
-        let path_str = path.as_str();
+/// Pair deleted files with added files Git considers renames of them
+/// (`git diff -M`), keeping the list sorted by path
+pub fn find_renames(git: &GitRepo, files: Vec<FileDiff>) -> Result<Vec<FileDiff>> {
+    let has = |status| files.iter().any(|f| f.status == status);
+    if !has(FileStatus::Added) || !has(FileStatus::Deleted) {
+        return Ok(files);
+    }
 
-        // Get base content from spawn commit
-        let base_content = get_file_at_commit(&git_repo, &spawn_commit, &path_str);
+    let candidates: Vec<FileDiff> = files
+        .iter()
+        .filter(|f| matches!(f.status, FileStatus::Added | FileStatus::Deleted))
+        .cloned()
+        .collect();
+    let raw = git_diff(git, &candidates, &["-M", "--raw", "-z"])?;
 
-        // Get current content from session
-        let session_file = session_dir.join(path_str);
-        let current_content = if session_file.exists() {
-            std::fs::read(&session_file).ok()
-        } else {
-            None
+    // Records are ":<modes> <oids> R<score>\0<old path>\0<new path>\0"
+    let raw = String::from_utf8_lossy(&raw);
+    let mut fields = raw.split('\0');
+    let mut renames = Vec::new();
+    while let Some(header) = fields.next() {
+        let Some(status) = header.split(' ').nth(4) else {
+            continue;
         };
+        let paths = if status.starts_with('R') || status.starts_with('C') { 2 } else { 1 };
+        let paths: Vec<&str> = fields.by_ref().take(paths).collect();
+        if status.starts_with('R') && paths.len() == 2 {
+            renames.push((paths[0].to_string(), paths[1].to_string()));
+        }
+    }
+    if renames.is_empty() {
+        return Ok(files);
+    }
 
-        // Determine file status
-        let (status, a_content, b_content) = match (&base_content, &current_content) {
-            (None, Some(content)) => ("new file", Vec::new(), content.clone()),
-            (Some(content), None) => ("deleted", content.clone(), Vec::new()),
-            (Some(base), Some(curr)) => ("modified", base.clone(), curr.clone()),
-            (None, None) => continue, // File doesn't exist in either - skip
+    let mut files = files;
+    for (from, to) in renames {
+        let Some(deleted) = files.iter().position(|f| f.status == FileStatus::Deleted && f.path == from) else {
+            continue;
         };
+        let deleted = files.remove(deleted);
+        if let Some(added) = files.iter_mut().find(|f| f.status == FileStatus::Added && f.path == to) {
+            added.status = FileStatus::Renamed;
+            added.old_path = Some(deleted.path);
+            added.old = deleted.old;
+            added.old_mode = deleted.old_mode;
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Files that differ between two session trees, by path
+pub fn compare_sessions(git: &GitRepo, a: &SessionTree, b: &SessionTree) -> Result<Vec<FileDiff>> {
+    let mut paths: BTreeSet<String> = a.changed_paths().union(b.changed_paths()).cloned().collect();
+    if a.base_commit != b.base_commit {
+        paths.extend(git.diff_trees(&a.base_commit, &b.base_commit)?.into_iter().map(|c| c.path));
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        files.extend(FileDiff::new(path, a.read_entry(git, path)?, b.read_entry(git, path)?));
+    }
+    Ok(files)
+}
+
+/// Output text for a set of changed files in the requested format
+fn render(git: &GitRepo, files: &[FileDiff], display: DiffDisplay) -> Result<String> {
+    if !display.uses_git() {
+        return Ok(render_diff(files, display.stat_only, display.options));
+    }
+
+    let mut args = display.options.git_args();
+    args.push("-M".to_string());
+    args.push(
+        if display.name_status {
+            "--name-status"
+        } else if display.numstat {
+            "--numstat"
+        } else if display.stat_only {
+            "--stat"
+        } else {
+            "--binary"
+        }
+        .to_string(),
+    );
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Ok(String::from_utf8_lossy(&git_diff(git, files, &args)?).into_owned())
+}
+
+/// Run `git diff` over the changed files. Each side is written as a tree
+/// holding only those files, so Git sees real blobs and modes and can pair
+/// deletions with additions as renames. The blobs and trees go to a scratch
+/// object directory, so diffing leaves nothing behind in the repository.
+pub fn git_diff(git: &GitRepo, files: &[FileDiff], args: &[&str]) -> Result<Vec<u8>> {
+    let scratch = git.scratch_objects()?;
+    let old_files: Vec<&FileDiff> = files.iter().filter(|f| f.status != FileStatus::Added).collect();
+    let new_files: Vec<&FileDiff> = files.iter().filter(|f| f.status != FileStatus::Deleted).collect();
+    let old_oids = scratch.write_blobs(old_files.iter().map(|f| f.old.as_slice()))?;
+    let new_oids = scratch.write_blobs(new_files.iter().map(|f| f.new.as_slice()))?;
+
+    let old_entries: Vec<_> = old_files
+        .iter()
+        .zip(old_oids)
+        .map(|(file, oid)| (file.old_path().to_string(), file.old_mode, oid))
+        .collect();
+    let new_entries: Vec<_> = new_files
+        .iter()
+        .zip(new_oids)
+        .map(|(file, oid)| (file.path.clone(), file.new_mode, oid))
+        .collect();
+
+    let old_tree = scratch.write_tree(&old_entries)?;
+    let new_tree = scratch.write_tree(&new_entries)?;
+    scratch.diff_trees_output(&old_tree, &new_tree, args)
+}
+
+/// Patch (or diffstat) text for a set of changed files
+fn render_diff(files: &[FileDiff], stat_only: bool, options: DiffOptions) -> String {
+    let mut diff_output = String::new();
+    let (mut insertions, mut deletions) = (0, 0);
+
+    for file in files {
+        let path_str = file.path.as_str();
 
         // Check if binary
-        if is_binary(&a_content) || is_binary(&b_content) {
+        if file.is_binary() {
             diff_output.push_str(&format!(
                 "Binary file {} ({}).\n",
-                path_str, status
+                path_str, file.status.label()
             ));
             continue;
         }
 
-        // Generate unified diff
-        let a_text = String::from_utf8_lossy(&a_content);
-        let b_text = String::from_utf8_lossy(&b_content);
-
         if stat_only {
-            let additions = b_text.lines().count();
-            let deletions = a_text.lines().count();
+            let (added, removed) = file.line_counts(options);
+            insertions += added;
+            deletions += removed;
+            let (plus, minus) = stat_bar(added, removed);
+            let name = match &file.old_path {
+                Some(old_path) => format!("{} => {}", old_path, path_str),
+                None => path_str.to_string(),
+            };
             diff_output.push_str(&format!(
                 " {} | {} {}{}\n",
-                path_str,
-                additions + deletions,
-                "+".repeat(additions.min(40)),
-                "-".repeat(deletions.min(40))
+                name,
+                added + removed,
+                "+".repeat(plus),
+                "-".repeat(minus)
             ));
         } else {
-            diff_output.push_str(&format!("diff --vibe a/{} b/{}\n", path_str, path_str));
+            let old_path = file.old_path();
+            diff_output.push_str(&format!("diff --vibe a/{} b/{}\n", old_path, path_str));
 
-            if status == "new file" {
-                diff_output.push_str("new file mode 100644\n");
-            } else if status == "deleted" {
-                diff_output.push_str("deleted file mode 100644\n");
+            match file.status {
+                FileStatus::Added => diff_output.push_str(&format!("new file mode {:o}\n", file.new_mode)),
+                FileStatus::Deleted => diff_output.push_str(&format!("deleted file mode {:o}\n", file.old_mode)),
+                FileStatus::Modified | FileStatus::Renamed | FileStatus::ModeChanged => {
+                    if file.old_mode != file.new_mode {
+                        diff_output.push_str(&format!("old mode {:o}\nnew mode {:o}\n", file.old_mode, file.new_mode));
+                    }
+                    if file.status == FileStatus::Renamed {
+                        diff_output.push_str(&format!("rename from {}\nrename to {}\n", old_path, path_str));
+                    }
+                }
+            }
+            if file.status == FileStatus::ModeChanged {
+                diff_output.push('\n');
+                continue;
             }
 
-            diff_output.push_str(&format!("--- a/{}\n", path_str));
+            diff_output.push_str(&format!("--- a/{}\n", old_path));
             diff_output.push_str(&format!("+++ b/{}\n", path_str));
 
             // Generate unified diff hunks
-            let diff_lines = generate_unified_diff(&a_text, &b_text);
-            diff_output.push_str(&diff_lines);
+            let a_text = String::from_utf8_lossy(&file.old);
+            let b_text = String::from_utf8_lossy(&file.new);
+            diff_output.push_str(&LineDiff::new(&a_text, &b_text, options).unified());
             diff_output.push('\n');
         }
     }
 
     if stat_only {
         diff_output.push_str(&format!(
-            "\n {} files changed\n",
-            dirty_paths.len()
+            "\n {} files changed, {} insertions(+), {} deletions(-)\n",
+            files.len(),
+            insertions,
+            deletions
         ));
     }
 
+    diff_output
+}
+
+/// Widths of the `+` and `-` bars of a `--stat` line, scaled down
+/// proportionally when the file changed more lines than fit
+fn stat_bar(added: usize, removed: usize) -> (usize, usize) {
+    const WIDTH: usize = 50;
+    let total = added + removed;
+    if total <= WIDTH {
+        return (added, removed);
+    }
+    let scale = |n: usize| if n == 0 { 0 } else { (n * WIDTH / total).max(1) };
+    (scale(added), scale(removed))
+}
+
+/// Color and page diff text
+fn print_diff(diff_output: &str, display: DiffDisplay) -> Result<()> {
     // Apply coloring if needed
-    let should_color = match color {
+    let should_color = match display.color {
         ColorOption::Always => true,
         ColorOption::Never => false,
         ColorOption::Auto => std::io::stdout().is_terminal(),
     };
 
     let colored_output = if should_color {
-        colorize_diff(&diff_output)
+        colorize_diff(diff_output)
     } else {
-        diff_output
+        diff_output.to_string()
     };
 
     // Output via pager or directly
-    if !no_pager && std::io::stdout().is_terminal() && colored_output.lines().count() > 25 {
+    if !display.no_pager && std::io::stdout().is_terminal() && colored_output.lines().count() > 25 {
         output_with_pager(&colored_output)?;
     } else {
         print!("{}", colored_output);
@@ -166,112 +734,11 @@ pub async fn diff<P: AsRef<Path>>(
     Ok(())
 }
 
-/// Get file content at a specific commit
-fn get_file_at_commit(git_repo: &GitRepo, commit: &str, path: &str) -> Option<Vec<u8>> {
-    let output = Command::new("git")
-        .args(["show", &format!("{}:{}", commit, path)])
-        .current_dir(&git_repo.repo_path())
-        .output()
-        .ok()?;
-
-    if output.status.success() {
-        Some(output.stdout)
-    } else {
-        None
-    }
-}
-
 /// Check if content is binary (contains null bytes)
 fn is_binary(content: &[u8]) -> bool {
     content.iter().take(8000).any(|&b| b == 0)
 }
 
-/// Generate unified diff between two strings
-fn generate_unified_diff(a: &str, b: &str) -> String {
-    use std::fmt::Write;
-
-    let a_lines: Vec<&str> = a.lines().collect();
-    let b_lines: Vec<&str> = b.lines().collect();
-
-    // Simple line-by-line diff
-    let mut output = String::new();
-
-    // Find changed regions
-    let max_len = a_lines.len().max(b_lines.len());
-    let mut i = 0;
-
-    while i < max_len {
-        let a_line = a_lines.get(i).copied();
-        let b_line = b_lines.get(i).copied();
-
-        if a_line != b_line {
-            // Found a difference - emit hunk
-            let hunk_start = i.saturating_sub(3);
-            let mut hunk_end = i;
-
-            // Find end of changed region
-            while hunk_end < max_len {
-                let a_l = a_lines.get(hunk_end).copied();
-                let b_l = b_lines.get(hunk_end).copied();
-                if a_l == b_l {
-                    // Found matching line, check if we have 3+ context lines
-                    let mut context_count = 0;
-                    for j in hunk_end..max_len.min(hunk_end + 6) {
-                        if a_lines.get(j) == b_lines.get(j) {
-                            context_count += 1;
-                        } else {
-                            break;
-                        }
-                    }
-                    if context_count >= 3 {
-                        break;
-                    }
-                }
-                hunk_end += 1;
-            }
-
-            hunk_end = hunk_end.min(max_len);
-            let hunk_context_end = (hunk_end + 3).min(max_len);
-
-            // Emit hunk header
-            let a_start = hunk_start + 1;
-            let a_count = (hunk_end - hunk_start).min(a_lines.len().saturating_sub(hunk_start));
-            let b_count = (hunk_context_end - hunk_start).min(b_lines.len().saturating_sub(hunk_start));
-
-            writeln!(output, "@@ -{},{} +{},{} @@", a_start, a_count, a_start, b_count).ok();
-
-            // Emit lines
-            for j in hunk_start..hunk_context_end {
-                let a_l = a_lines.get(j).copied();
-                let b_l = b_lines.get(j).copied();
-
-                match (a_l, b_l) {
-                    (Some(a), Some(b)) if a == b => {
-                        writeln!(output, " {}", a).ok();
-                    }
-                    (Some(a), Some(b)) => {
-                        writeln!(output, "-{}", a).ok();
-                        writeln!(output, "+{}", b).ok();
-                    }
-                    (Some(a), None) => {
-                        writeln!(output, "-{}", a).ok();
-                    }
-                    (None, Some(b)) => {
-                        writeln!(output, "+{}", b).ok();
-                    }
-                    (None, None) => {}
-                }
-            }
-
-            i = hunk_context_end;
-        } else {
-            i += 1;
-        }
-    }
-
-    output
-}
-
 /// Apply ANSI colors to diff output
 fn colorize_diff(diff: &str) -> String {
     let mut output = String::new();
@@ -316,6 +783,25 @@ fn output_with_pager(text: &str) -> Result<()> {
     Ok(())
 }
 
+/// Patch format: VibeFS's own, or Git's (`git apply` compatible)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffFormat {
+    Vibe,
+    Git,
+}
+
+impl std::str::FromStr for DiffFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vibe" => Ok(DiffFormat::Vibe),
+            "git" => Ok(DiffFormat::Git),
+            _ => Err(format!("Invalid diff format: {}. Use vibe or git.", s)),
+        }
+    }
+}
+
 /// Color output option
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorOption {
@@ -340,6 +826,26 @@ impl std::str::FromStr for ColorOption {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commands::{init, spawn};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("README.md"), "# Test\n").unwrap();
+        fs::write(repo_path.join("lib.txt"), "one\n").unwrap();
+        fs::write(repo_path.join("notes.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "Initial commit"]);
+        temp_dir
+    }
 
     #[test]
     fn test_is_binary() {
@@ -360,12 +866,228 @@ mod tests {
     fn test_generate_unified_diff_simple() {
         let a = "line1\nline2\nline3\n";
         let b = "line1\nmodified\nline3\n";
-        let diff = generate_unified_diff(a, b);
+        let diff = LineDiff::new(a, b, DiffOptions::default()).unified();
         assert!(diff.contains("@@"));
         assert!(diff.contains("-line2"));
         assert!(diff.contains("+modified"));
     }
 
+    #[tokio::test]
+    async fn test_compare_sessions_resolves_clean_files_through_bases() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        init::init(repo_path).await.unwrap();
+
+        // b starts from a branch where README.md moved on
+        git(&["checkout", "-q", "-b", "other"]);
+        fs::write(repo_path.join("README.md"), "# Other\n").unwrap();
+        git(&["commit", "-q", "-am", "Other readme"]);
+        git(&["checkout", "-q", "-"]);
+
+        let edit = |session: &str, path: &str, content: &str| {
+            let session_dir = repo_path.join(".vibe/sessions").join(session);
+            fs::write(session_dir.join(path), content).unwrap();
+            MetadataStore::open(session_dir.join("metadata.db")).unwrap().mark_dirty(path).unwrap();
+        };
+        spawn::spawn_local_from(repo_path, "a", Some("HEAD")).await.unwrap();
+        spawn::spawn_local_from(repo_path, "b", Some("other")).await.unwrap();
+        edit("a", "lib.txt", "two\n");
+        edit("a", "a.txt", "a\n");
+        edit("b", "lib.txt", "two\n");
+        edit("b", "b.txt", "b\n");
+
+        let repo = GitRepo::open(repo_path).unwrap();
+        let a = SessionTree::open(repo_path, "a").await.unwrap();
+        let b = SessionTree::open(repo_path, "b").await.unwrap();
+        let files = compare_sessions(&repo, &a, &b).unwrap();
+        let summary: Vec<(&str, FileStatus)> = files.iter().map(|f| (f.path.as_str(), f.status)).collect();
+        assert_eq!(summary, vec![
+            ("README.md", FileStatus::Modified),
+            ("a.txt", FileStatus::Deleted),
+            ("b.txt", FileStatus::Added),
+        ]);
+        assert_eq!(files[0].new, b"# Other\n");
+
+        let output = DiffOutput::new(&repo, "a", "b", &files, DiffOptions::default());
+        assert_eq!(output.files_changed, 3);
+        assert_eq!(serde_json::to_value(&output).unwrap()["files"][2]["status"], "added");
+    }
+
+    #[test]
+    fn test_json_output_lists_renames_and_hunks() {
+        let temp_dir = TempDir::new().unwrap();
+        Command::new("git").args(["init", "-q"]).current_dir(temp_dir.path()).output().unwrap();
+        let repo = GitRepo::open(temp_dir.path()).unwrap();
+
+        let old: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 5\n", "five\n");
+        let files: Vec<FileDiff> = [
+            FileDiff::new("docs/new.txt", None, Some((new.into_bytes(), 0o100644))),
+            FileDiff::new("old.txt", Some((old.clone().into_bytes(), 0o100644)), None),
+            FileDiff::new("run.sh", Some((b"echo\n".to_vec(), 0o100644)), Some((b"echo\n".to_vec(), 0o100755))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let files = find_renames(&repo, files).unwrap();
+        let output = serde_json::to_value(DiffOutput::new(&repo, "base", "s", &files, DiffOptions::default())).unwrap();
+        let renamed = &output["files"][0];
+        assert_eq!(renamed["status"], "renamed");
+        assert_eq!(renamed["old_path"], "old.txt");
+        assert_eq!(renamed["old_oid"], repo.hash_blob(old.as_bytes()));
+        assert_eq!((renamed["insertions"].as_u64(), renamed["deletions"].as_u64()), (Some(1), Some(1)));
+
+        let hunk = &renamed["hunks"][0];
+        assert_eq!(hunk["header"], "@@ -2,7 +2,7 @@");
+        let removed = &hunk["lines"][3];
+        assert_eq!((removed["kind"].as_str(), removed["old_line"].as_u64()), (Some("removed"), Some(5)));
+        assert_eq!(removed["text"], "line 5");
+        assert!(removed["new_line"].is_null());
+
+        let chmod = &output["files"][1];
+        assert_eq!(chmod["status"], "mode-changed");
+        assert_eq!((chmod["old_mode"].as_str(), chmod["new_mode"].as_str()), (Some("100644"), Some("100755")));
+        assert_eq!(chmod["hunks"].as_array().unwrap().len(), 0);
+        assert_eq!(output["files_changed"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_diff_against_head_and_checkpoint() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local_from(repo_path, "s", Some("HEAD")).await.unwrap();
+
+        let session_dir = repo_path.join(".vibe/sessions/s");
+        let edit = |path: &str, content: &str| {
+            fs::write(session_dir.join(path), content).unwrap();
+            MetadataStore::open(session_dir.join("metadata.db")).unwrap().mark_dirty(path).unwrap();
+        };
+        edit("a.txt", "v1\n");
+
+        // A checkpoint is a copy of the session directory
+        let status = Command::new("cp")
+            .args(["-r", "s", "s_snapshot_first"])
+            .current_dir(repo_path.join(".vibe/sessions"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        crate::commands::snapshot::record_checkpoint_base(repo_path, "s", "first").unwrap();
+        edit("a.txt", "v2\n");
+        edit("b.txt", "b\n");
+
+        let repo = GitRepo::open(repo_path).unwrap();
+        let tree = SessionTree::open(repo_path, "s").await.unwrap();
+        let summary = |files: &[FileDiff]| -> Vec<(String, FileStatus)> {
+            files.iter().map(|f| (f.path.clone(), f.status)).collect()
+        };
+
+        let checkpoint = resolve_against(repo_path, &repo, "s", "first").unwrap();
+        let files = compare_sessions(&repo, &checkpoint, &tree).unwrap();
+        assert_eq!(summary(&files), vec![
+            ("a.txt".to_string(), FileStatus::Modified),
+            ("b.txt".to_string(), FileStatus::Added),
+        ]);
+        assert_eq!(files[0].old, b"v1\n");
+
+        // HEAD moved on: a commit from the session would revert README.md
+        fs::write(repo_path.join("README.md"), "# Moved\n").unwrap();
+        git(&["commit", "-q", "-am", "Move README"]);
+        let head = resolve_against(repo_path, &repo, "s", "HEAD").unwrap();
+        let files = compare_sessions(&repo, &head, &tree).unwrap();
+        assert_eq!(summary(&files), vec![
+            ("README.md".to_string(), FileStatus::Modified),
+            ("a.txt".to_string(), FileStatus::Added),
+            ("b.txt".to_string(), FileStatus::Added),
+        ]);
+        assert_eq!(files[0].old, b"# Moved\n");
+
+        let first = resolve_against(repo_path, &repo, "s", "HEAD~1").unwrap();
+        assert_eq!(compare_sessions(&repo, &first, &tree).unwrap().len(), 2);
+        assert!(resolve_against(repo_path, &repo, "s", "nope").is_err());
+
+        // After a rebase the checkpoint still compares against its own base
+        let mut spawn_info = SpawnInfo::load(repo_path, "s").unwrap();
+        spawn_info.spawn_commit = Some(repo.head_commit().unwrap());
+        spawn_info.save(repo_path).unwrap();
+        let checkpoint = resolve_against(repo_path, &repo, "s", "first").unwrap();
+        assert_eq!(checkpoint.base_commit, first.base_commit);
+    }
+
+    #[tokio::test]
+    async fn test_git_format_patch_applies() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap()
+        };
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local_from(repo_path, "s", Some("HEAD")).await.unwrap();
+
+        // Move notes.txt, make README.md executable, add a binary file
+        let session_dir = repo_path.join(".vibe/sessions/s");
+        {
+            let store = MetadataStore::open(session_dir.join("metadata.db")).unwrap();
+            let inode = store.get_inode_by_path("notes.txt").unwrap().unwrap();
+            let notes = store.get_inode(inode).unwrap().unwrap();
+            store.delete_inode(inode).unwrap();
+            store.put_whiteout(inode, &notes).unwrap();
+            fs::create_dir_all(session_dir.join("docs")).unwrap();
+            fs::write(session_dir.join("docs/notes.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+            store.mark_dirty("docs/notes.txt").unwrap();
+
+            let inode = store.get_inode_by_path("README.md").unwrap().unwrap();
+            let mut readme = store.get_inode(inode).unwrap().unwrap();
+            readme.mode = 0o755;
+            store.put_inode(inode, &readme).unwrap();
+            fs::write(session_dir.join("README.md"), "#!/bin/sh\n").unwrap();
+            store.mark_dirty("README.md").unwrap();
+
+            fs::write(session_dir.join("logo.bin"), b"\x89PNG\x00\x01\x02").unwrap();
+            store.mark_dirty("logo.bin").unwrap();
+        }
+
+        let repo = GitRepo::open(repo_path).unwrap();
+        let tree = SessionTree::open(repo_path, "s").await.unwrap();
+        let files: Vec<FileDiff> = tree
+            .changed_paths()
+            .iter()
+            .filter_map(|path| {
+                let base = repo.read_entry_at_commit(&tree.base_commit, path).unwrap();
+                FileDiff::new(path, base, tree.read_entry(&repo, path).unwrap())
+            })
+            .collect();
+        let loose_objects = || String::from_utf8(git(&["count-objects"]).stdout).unwrap();
+        let objects_before = loose_objects();
+
+        let name_status = String::from_utf8(git_diff(&repo, &files, &["-M", "--name-status"]).unwrap()).unwrap();
+        assert!(name_status.contains("R100\tnotes.txt\tdocs/notes.txt"), "{}", name_status);
+
+        let patch = git_diff(&repo, &files, &["-M", "--binary"]).unwrap();
+        let text = String::from_utf8_lossy(&patch);
+        assert!(text.contains("diff --git a/README.md b/README.md"));
+        assert!(text.contains("new mode 100755"));
+        assert!(text.contains("GIT binary patch"));
+        // Diffing wrote nothing into the repository
+        assert_eq!(loose_objects(), objects_before);
+
+        // The patch applies to the base checkout
+        let patch_file = temp_dir.path().join("session.patch");
+        fs::write(&patch_file, &patch).unwrap();
+        let applied = git(&["apply", patch_file.to_str().unwrap()]);
+        assert!(applied.status.success(), "{}", String::from_utf8_lossy(&applied.stderr));
+        assert!(!repo_path.join("notes.txt").exists());
+        assert_eq!(fs::read(repo_path.join("logo.bin")).unwrap(), b"\x89PNG\x00\x01\x02");
+        assert_eq!(fs::read_to_string(repo_path.join("docs/notes.txt")).unwrap(), "one\ntwo\nthree\nfour\n");
+    }
+
     #[test]
     fn test_color_option_parse() {
         assert_eq!("auto".parse::<ColorOption>().unwrap(), ColorOption::Auto);
@@ -0,0 +1,181 @@
+//! `vibe fork <src> <dst>` command - Start a new session from another session's current state
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::commands::snapshot::copy_session_delta;
+use crate::commands::spawn::{export_and_mount, SpawnInfo};
+use crate::cwd_validation;
+use crate::daemon_client::{ensure_daemon_running, DaemonClient};
+use crate::daemon_ipc::DaemonResponse;
+use crate::db::MetadataStore;
+
+/// Copy a session's delta and metadata store (dirty markers and whiteouts
+/// included) into a new session directory. The caller keeps `store` from
+/// changing while this runs. Returns the number of changed paths copied.
+pub fn copy_session(source_dir: &Path, store: &MetadataStore, dest_dir: &Path) -> Result<usize> {
+    if dest_dir.exists() {
+        anyhow::bail!("Session directory {} already exists", dest_dir.display());
+    }
+
+    let copied = copy_session_delta(source_dir, dest_dir)
+        .and_then(|_| store.checkpoint_to(dest_dir.join("metadata.db")))
+        .and_then(|_| store.get_dirty_paths());
+    if copied.is_err() {
+        let _ = std::fs::remove_dir_all(dest_dir);
+    }
+    Ok(copied?.len())
+}
+
+/// Spawn info for `vibe_id` forked from `source`: same base, same
+/// unresolved conflicts and unmerged files
+fn forked_spawn_info(source: &SpawnInfo, vibe_id: &str, session_dir: PathBuf) -> SpawnInfo {
+    SpawnInfo {
+        vibe_id: vibe_id.to_string(),
+        session_dir,
+        mount_point: PathBuf::new(),
+        port: 0,
+        spawn_commit: source.spawn_commit.clone(),
+        created_at: Some(Utc::now().to_rfc3339()),
+        conflicts: source.conflicts.clone(),
+        unmerged: source.unmerged.clone(),
+        // Checkpoints stay with the source session
+        checkpoints: BTreeMap::new(),
+        base_ref: source.base_ref.clone(),
+        parent_session: source.parent_session.clone(),
+    }
+}
+
+/// Check that `source` exists and `vibe_id` is free, and load the source's spawn info
+fn check_fork(repo_path: &Path, source: &str, vibe_id: &str) -> Result<SpawnInfo> {
+    let sessions_dir = repo_path.join(".vibe/sessions");
+    if !repo_path.join(".vibe").exists() {
+        anyhow::bail!("VibeFS not initialized. Run 'vibe init' first.");
+    }
+    if source == vibe_id {
+        anyhow::bail!("Cannot fork session '{}' into itself", source);
+    }
+    if sessions_dir.join(vibe_id).exists() || sessions_dir.join(format!("{}.json", vibe_id)).exists() {
+        anyhow::bail!("Session '{}' already exists", vibe_id);
+    }
+    SpawnInfo::load(repo_path, source)
+        .with_context(|| format!("Session '{}' does not exist", source))
+}
+
+/// Fork a session: the new session starts with the source's changes and
+/// base, and is exported and mounted by the daemon. The daemon holds off
+/// writes to a live source while it copies, so the fork is consistent.
+pub async fn fork<P: AsRef<Path>>(repo_path: P, source: &str, vibe_id: &str) -> Result<()> {
+    // Validate that we're running from the correct directory
+    let _validated_root = cwd_validation::validate_cwd().context("Cannot fork session")?;
+
+    let repo_path = repo_path.as_ref();
+    let source_info = check_fork(repo_path, source, vibe_id)?;
+
+    println!("Forking session '{}' into '{}'", source, vibe_id);
+
+    println!("  Ensuring daemon is running...");
+    ensure_daemon_running(repo_path).await?;
+    let mut client = DaemonClient::connect(repo_path).await?;
+
+    match client.fork_session(source, vibe_id).await? {
+        DaemonResponse::SessionForked { dirty_count, .. } => {
+            println!("  ✓ Copied {} changed file(s)", dirty_count);
+        }
+        DaemonResponse::Error { message } => anyhow::bail!("Daemon error: {}", message),
+        other => anyhow::bail!("Unexpected daemon response: {:?}", other),
+    }
+
+    let session_dir = repo_path.join(".vibe/sessions").join(vibe_id);
+    export_and_mount(repo_path, &mut client, forked_spawn_info(&source_info, vibe_id, session_dir)).await?;
+
+    println!("\n✓ Forked '{}' into '{}'", source, vibe_id);
+
+    Ok(())
+}
+
+/// Local fork without daemon (for testing and simple use cases). The source
+/// must not be mounted.
+pub async fn fork_local<P: AsRef<Path>>(repo_path: P, source: &str, vibe_id: &str) -> Result<usize> {
+    let repo_path = repo_path.as_ref();
+    let source_info = check_fork(repo_path, source, vibe_id)?;
+
+    let sessions_dir = repo_path.join(".vibe/sessions");
+    let session_dir = sessions_dir.join(vibe_id);
+
+    // Sessions spawned without their own store keep their changes in the base store
+    let source_dir = sessions_dir.join(source);
+    let source_db = source_dir.join("metadata.db");
+    let store = if source_db.exists() {
+        MetadataStore::open(&source_db)?
+    } else {
+        MetadataStore::open(repo_path.join(".vibe/metadata.db"))?
+    };
+    let dirty_count = copy_session(&source_dir, &store, &session_dir)?;
+
+    let mut spawn_info = forked_spawn_info(&source_info, vibe_id, session_dir);
+    spawn_info.mount_point = PathBuf::from("/tmp/vibe").join(vibe_id);
+    spawn_info.save(repo_path)?;
+
+    Ok(dirty_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{init, promote, spawn};
+    use crate::git::GitRepo;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("README.md"), "# Test").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_fork_copies_changes_and_base() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        init::init(repo_path).await.unwrap();
+        let head = GitRepo::open(repo_path).unwrap().head_commit().unwrap();
+
+        spawn::spawn_local_from(repo_path, "src", Some(&head)).await.unwrap();
+        let src_dir = repo_path.join(".vibe/sessions/src");
+        fs::create_dir_all(src_dir.join("lib")).unwrap();
+        fs::write(src_dir.join("lib/a.txt"), "a").unwrap();
+        MetadataStore::open(src_dir.join("metadata.db")).unwrap().mark_dirty("lib/a.txt").unwrap();
+
+        assert_eq!(fork_local(repo_path, "src", "dst").await.unwrap(), 1);
+        assert!(fork_local(repo_path, "src", "dst").await.is_err());
+
+        let dst_dir = repo_path.join(".vibe/sessions/dst");
+        assert_eq!(fs::read_to_string(dst_dir.join("lib/a.txt")).unwrap(), "a");
+        let info = SpawnInfo::load(repo_path, "dst").unwrap();
+        assert_eq!(info.spawn_commit.as_deref(), Some(head.as_str()));
+
+        // The two sessions diverge from here
+        fs::write(dst_dir.join("b.txt"), "b").unwrap();
+        MetadataStore::open(dst_dir.join("metadata.db")).unwrap().mark_dirty("b.txt").unwrap();
+        assert!(!MetadataStore::open(src_dir.join("metadata.db")).unwrap().is_dirty("b.txt").unwrap());
+
+        promote::promote(repo_path, "dst", None, Some("Fork"), true, false).await.unwrap();
+        let git = GitRepo::open(repo_path).unwrap();
+        let commit = git.get_ref("refs/vibes/dst").unwrap().unwrap();
+        assert!(git.read_file_at_commit(&commit, "lib/a.txt").unwrap().is_some());
+        assert!(git.read_file_at_commit(&commit, "b.txt").unwrap().is_some());
+    }
+}
@@ -2,6 +2,7 @@ use anyhow::{Context, Result};
 use std::collections::BTreeSet;
 use std::path::Path;
 
+use crate::config::{Config, UntrackedFiles};
 use crate::db::{InodeMetadata, MetadataStore};
 use crate::git::GitRepo;
 use crate::gitignore::is_commonly_ignored;
@@ -61,6 +62,29 @@ When you're inside a VibeFS mount (your session workspace):
 7. **Cleanup**: `vibe close` or exit shell
 "#;
 
+/// Inode metadata for a file in the base tree, from its `ls-tree` entry
+pub fn tracked_file_metadata(git: &GitRepo, path: String, oid: String, git_mode: u32) -> InodeMetadata {
+    let size = git.blob_size(&oid).unwrap_or(0);
+
+    // Symlink blobs (mode 120000) hold the link target
+    let symlink_target = if git_mode & 0o170000 == 0o120000 {
+        git.read_blob(&oid)
+            .ok()
+            .map(|content| String::from_utf8_lossy(&content).to_string())
+    } else {
+        None
+    };
+
+    InodeMetadata {
+        path,
+        git_oid: Some(oid),
+        size,
+        mode: git_mode & 0o777,
+        symlink_target,
+        ..Default::default()
+    }
+}
+
 /// Initialize VibeFS for a Git repository
 pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
     // Validate that we're running from the correct directory
@@ -114,7 +138,7 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
     // Git only stores files (blobs), so we need to create directory inodes
     // for all parent directories
     let mut directories: BTreeSet<String> = BTreeSet::new();
-    for (path, _) in &entries {
+    for (path, _, _) in &entries {
         let mut current = path.as_path();
         while let Some(parent) = current.parent() {
             let parent_str = parent.to_string_lossy().to_string();
@@ -133,9 +157,7 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
         path: "".to_string(),
         git_oid: Some(head_oid),
         is_dir: true,
-        size: 0,
-        volatile: false,
-        mtime: 0,
+        ..Default::default()
     };
     metadata.put_inode(1, &root_metadata)?;
 
@@ -149,11 +171,8 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
 
         let dir_metadata = InodeMetadata {
             path: dir_path.clone(),
-            git_oid: None,  // Directories don't have a git oid
             is_dir: true,
-            size: 0,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
 
         metadata.put_inode(inode_id, &dir_metadata)?;
@@ -161,30 +180,22 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
 
     // Populate metadata for all file entries (Git-tracked)
     let mut tracked_paths: BTreeSet<String> = BTreeSet::new();
-    for (path, oid) in entries {
+    for (path, oid, git_mode) in entries {
         let inode_id = metadata.next_inode_id()?;
         let path_str = path.to_string_lossy().to_string();
         tracked_paths.insert(path_str.clone());
 
-        let size = git.read_blob(&oid)
-            .map(|data| data.len() as u64)
-            .unwrap_or(0);
-
-        let inode_metadata = InodeMetadata {
-            path: path_str,
-            git_oid: Some(oid),
-            is_dir: false,
-            size,
-            volatile: false,
-            mtime: 0,
-        };
-
+        let inode_metadata = tracked_file_metadata(&git, path_str, oid, git_mode);
         metadata.put_inode(inode_id, &inode_metadata)?;
     }
 
     // Also scan for untracked files in the repo (for passthrough access)
     // This allows tools like cargo to access Cargo.lock, node_modules, etc.
-    let untracked_files = scan_untracked_files(repo_path, &tracked_paths, &directories)?;
+    // `init.untracked = "skip"` leaves them out
+    let untracked_files = match Config::load(repo_path)?.init.untracked {
+        UntrackedFiles::Scan => scan_untracked_files(repo_path, &tracked_paths, &directories)?,
+        UntrackedFiles::Skip => Vec::new(),
+    };
     if !untracked_files.is_empty() {
         println!("Found {} untracked files for passthrough", untracked_files.len());
 
@@ -208,11 +219,9 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
             let inode_id = metadata.next_inode_id()?;
             let dir_metadata = InodeMetadata {
                 path: dir_path.clone(),
-                git_oid: None,
                 is_dir: true,
-                size: 0,
                 volatile: true,  // Mark as volatile since untracked
-                mtime: 0,
+                ..Default::default()
             };
             metadata.put_inode(inode_id, &dir_metadata)?;
         }
@@ -221,11 +230,9 @@ pub async fn init<P: AsRef<Path>>(repo_path: P) -> Result<()> {
             let inode_id = metadata.next_inode_id()?;
             let inode_metadata = InodeMetadata {
                 path: path.to_string_lossy().to_string(),
-                git_oid: None,  // No git oid - will use passthrough
-                is_dir: false,
                 size,
                 volatile: true,  // Mark as volatile since untracked
-                mtime: 0,
+                ..Default::default()
             };
             metadata.put_inode(inode_id, &inode_metadata)?;
         }
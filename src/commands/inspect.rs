@@ -5,9 +5,9 @@ use serde::Serialize;
 use std::path::Path;
 
 use crate::commands::spawn::SpawnInfo;
-use crate::db::MetadataStore;
 use crate::git::GitRepo;
 use crate::gitignore::PromoteFilter;
+use crate::session_state::SessionState;
 
 /// Get HEAD commit for comparison
 fn get_head_commit(repo_path: &Path) -> Option<String> {
@@ -29,34 +29,14 @@ pub async fn inspect<P: AsRef<Path>>(
     let spawn_info = SpawnInfo::load(repo_path, session)
         .with_context(|| format!("Session '{}' not found. Run 'vibe status' to see active sessions.", session))?;
 
-    // Get dirty files from per-session store (fallback to base), filtered by .gitignore
-    let db_path = {
-        let session_db = vibe_dir.join("sessions").join(session).join("metadata.db");
-        if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") }
+    // Get dirty files, filtered by .gitignore, and delta size
+    let mut state = SessionState::open(repo_path, session).await?;
+    let dirty_paths = state.changes().await?.dirty;
+    let dirty_files: Vec<String> = match PromoteFilter::new(repo_path, Some(&spawn_info.session_dir)) {
+        Ok(filter) => filter.filter_promotable(&dirty_paths).into_iter().cloned().collect(),
+        Err(_) => dirty_paths,
     };
-    let dirty_files = if db_path.exists() {
-        match MetadataStore::open_readonly(&db_path) {
-            Ok(store) => {
-                let paths = store.get_dirty_paths()?;
-                let session_dir = vibe_dir.join("sessions").join(session);
-                if let Ok(filter) = PromoteFilter::new(repo_path, Some(&session_dir)) {
-                    filter.filter_promotable(&paths).into_iter().cloned().collect()
-                } else {
-                    paths
-                }
-            }
-            Err(_) => {
-                // Fallback: if read-only fails, return empty list
-                Vec::new()
-            }
-        }
-    } else {
-        Vec::new()
-    };
-
-    // Calculate delta size
-    let delta_size = calculate_dir_size(&spawn_info.session_dir)?;
-    let delta_file_count = count_files(&spawn_info.session_dir)?;
+    let stats = state.stats().await?;
 
     // Find snapshots
     let snapshots = find_snapshots(&vibe_dir.join("sessions"), session)?;
@@ -84,8 +64,8 @@ pub async fn inspect<P: AsRef<Path>>(
         behind_head,
         phantom_ref: if phantom_exists { Some(phantom_ref) } else { None },
         delta_path: spawn_info.session_dir.to_string_lossy().to_string(),
-        delta_size_bytes: delta_size,
-        delta_file_count,
+        delta_size_bytes: stats.delta_size_bytes,
+        delta_file_count: stats.delta_file_count,
         snapshots,
         dirty_files: dirty_files
             .iter()
@@ -219,48 +199,6 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
-fn calculate_dir_size(path: &Path) -> Result<u64> {
-    let mut size = 0u64;
-
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    for entry in std::fs::read_dir(path)? {
-        let entry = entry?;
-        let meta = entry.metadata()?;
-
-        if meta.is_file() {
-            size += meta.len();
-        } else if meta.is_dir() {
-            size += calculate_dir_size(&entry.path())?;
-        }
-    }
-
-    Ok(size)
-}
-
-fn count_files(path: &Path) -> Result<usize> {
-    let mut count = 0;
-
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    for entry in std::fs::read_dir(path)? {
-        let entry = entry?;
-        let meta = entry.metadata()?;
-
-        if meta.is_file() {
-            count += 1;
-        } else if meta.is_dir() {
-            count += count_files(&entry.path())?;
-        }
-    }
-
-    Ok(count)
-}
-
 fn find_snapshots(sessions_dir: &Path, session: &str) -> Result<Vec<String>> {
     let prefix = format!("{}_snapshot_", session);
     let mut snapshots = Vec::new();
@@ -315,21 +253,4 @@ mod tests {
         assert_eq!(format_size(1024 * 1024), "1.0 MB");
         assert_eq!(format_size(1024 * 1024 * 1024), "1.0 GB");
     }
-
-    #[test]
-    fn test_calculate_dir_size_empty() {
-        let temp_dir = tempfile::TempDir::new().unwrap();
-        let size = calculate_dir_size(temp_dir.path()).unwrap();
-        assert_eq!(size, 0);
-    }
-
-    #[test]
-    fn test_calculate_dir_size_with_files() {
-        let temp_dir = tempfile::TempDir::new().unwrap();
-        std::fs::write(temp_dir.path().join("file1.txt"), "hello").unwrap();
-        std::fs::write(temp_dir.path().join("file2.txt"), "world!").unwrap();
-
-        let size = calculate_dir_size(temp_dir.path()).unwrap();
-        assert_eq!(size, 11); // 5 + 6 bytes
-    }
 }
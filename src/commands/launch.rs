@@ -5,6 +5,7 @@ use std::os::unix::process::CommandExt;
 use std::path::Path;
 
 use crate::commands::spawn::{self, SpawnInfo};
+use crate::config::Config;
 use crate::names;
 
 /// Known agent binaries for shortcuts and "did you mean" suggestions
@@ -14,24 +15,38 @@ pub const KNOWN_AGENTS: &[&str] = &[
     "mock-agent",  // For testing
 ];
 
-/// Check if a string is a known agent name
-pub fn is_known_agent(name: &str) -> bool {
-    KNOWN_AGENTS.contains(&name)
+/// Check if a string is a known agent name, built in or defined under
+/// `[agents]` in the config
+pub fn is_known_agent(repo_path: &Path, name: &str) -> Result<bool> {
+    Ok(KNOWN_AGENTS.contains(&name) || Config::load(repo_path)?.agents.contains_key(name))
 }
 
-/// Launch an agent in a vibe session
+/// Built-in agents and the ones defined in `config`
+fn known_agents(config: &Config) -> Vec<String> {
+    let mut agents: Vec<String> = KNOWN_AGENTS.iter().map(|a| a.to_string()).collect();
+    agents.extend(config.agents.keys().filter(|name| !KNOWN_AGENTS.contains(&name.as_str())).cloned());
+    agents
+}
+
+/// Launch an agent in a vibe session, based on HEAD or the `from` revision
 pub async fn launch<P: AsRef<Path>>(
     repo_path: P,
     agent: &str,
     session_name: Option<&str>,
     agent_args: &[String],
+    from: Option<&str>,
 ) -> Result<()> {
     let repo_path = repo_path.as_ref();
     let vibe_dir = repo_path.join(".vibe");
     let sessions_dir = vibe_dir.join("sessions");
 
+    // Agents defined in the config may name another binary and extra args
+    let config = Config::load(repo_path)?;
+    let agent_config = config.agents.get(agent).cloned().unwrap_or_default();
+    let agent_args: Vec<String> = agent_config.args.iter().chain(agent_args).cloned().collect();
+
     // Verify agent binary exists in PATH
-    let agent_path = which_agent(agent)?;
+    let agent_path = which_agent(agent_config.command.as_deref().unwrap_or(agent), &config)?;
 
     // Generate session name if not provided
     let session = match session_name {
@@ -42,7 +57,7 @@ pub async fn launch<P: AsRef<Path>>(
     println!("Launching {} in session '{}'...", agent, session);
 
     // Spawn the session
-    spawn::spawn(repo_path, &session).await?;
+    spawn::spawn(repo_path, &session, from).await?;
 
     // Load spawn info to get the actual mount point
     let spawn_info = SpawnInfo::load(repo_path, &session)
@@ -58,7 +73,7 @@ pub async fn launch<P: AsRef<Path>>(
 
     // exec the agent - this replaces the current process
     let err = std::process::Command::new(&agent_path)
-        .args(agent_args)
+        .args(&agent_args)
         .current_dir(&mount_point)
         .exec();
 
@@ -67,14 +82,14 @@ pub async fn launch<P: AsRef<Path>>(
 }
 
 /// Find agent binary in PATH, with helpful error messages
-fn which_agent(agent: &str) -> Result<String> {
+fn which_agent(agent: &str, config: &Config) -> Result<String> {
     // Check if binary exists in PATH
     if let Ok(path) = which::which(agent) {
         return Ok(path.to_string_lossy().to_string());
     }
 
     // Binary not found - generate helpful error message
-    let suggestions = find_similar_agents(agent);
+    let suggestions = find_similar_agents(agent, config);
 
     let mut msg = format!("Binary '{}' not found in PATH.", agent);
 
@@ -84,21 +99,21 @@ fn which_agent(agent: &str) -> Result<String> {
         msg.push('?');
     } else {
         msg.push_str("\nKnown agents: ");
-        msg.push_str(&KNOWN_AGENTS.join(", "));
+        msg.push_str(&known_agents(config).join(", "));
     }
 
     Err(anyhow::anyhow!(msg))
 }
 
 /// Find similar agent names using edit distance
-fn find_similar_agents(input: &str) -> Vec<String> {
-    let mut suggestions: Vec<(String, usize)> = KNOWN_AGENTS
-        .iter()
-        .filter_map(|&known| {
-            let dist = edit_distance(input, known);
+fn find_similar_agents(input: &str, config: &Config) -> Vec<String> {
+    let mut suggestions: Vec<(String, usize)> = known_agents(config)
+        .into_iter()
+        .filter_map(|known| {
+            let dist = edit_distance(input, &known);
             // Only suggest if distance is reasonable (max 3 edits)
             if dist <= 3 {
-                Some((known.to_string(), dist))
+                Some((known, dist))
             } else {
                 None
             }
@@ -165,14 +180,19 @@ mod tests {
 
     #[test]
     fn test_find_similar_agents() {
-        let suggestions = find_similar_agents("cluade");
+        let config = Config::default();
+        let suggestions = find_similar_agents("cluade", &config);
         assert!(suggestions.contains(&"claude".to_string()));
 
-        let suggestions = find_similar_agents("codr");
+        let suggestions = find_similar_agents("codr", &config);
         assert!(suggestions.contains(&"code".to_string()));
 
-        let suggestions = find_similar_agents("xyz123");
+        let suggestions = find_similar_agents("xyz123", &config);
         assert!(suggestions.is_empty());
+
+        let mut config = Config::default();
+        config.agents.insert("reviewer".to_string(), Default::default());
+        assert!(find_similar_agents("reveiwer", &config).contains(&"reviewer".to_string()));
     }
 
     #[test]
@@ -0,0 +1,124 @@
+//! `vibe log [session]` command - List the commits made from a session
+
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use std::path::Path;
+
+use crate::git::{CommitSummary, GitRepo};
+
+/// Commits made from a session, newest first.
+///
+/// Walks first parents from `refs/vibes/<session>` while the commits carry the
+/// session's `Vibe-Session` trailer; the base history below them is not listed.
+pub fn session_commits(git: &GitRepo, vibe_id: &str) -> Result<Vec<CommitSummary>> {
+    let mut commits = Vec::new();
+    let mut next = git.get_ref(&format!("refs/vibes/{}", vibe_id))?;
+
+    while let Some(oid) = next {
+        let commit = git.commit_summary(&oid)?;
+        if commit.session.as_deref() != Some(vibe_id) {
+            break;
+        }
+        next = commit.parents.first().cloned();
+        commits.push(commit);
+    }
+
+    Ok(commits)
+}
+
+/// Print a session's commit history
+pub fn log<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
+    let repo_path = repo_path.as_ref();
+    let git = GitRepo::open(repo_path).context("Failed to open Git repository")?;
+
+    let commits = session_commits(&git, vibe_id)?;
+    if commits.is_empty() {
+        println!("No commits from session '{}' yet. Run 'vibe commit' to create one.", vibe_id);
+        return Ok(());
+    }
+
+    let head = git.head_commit().ok();
+    println!("Commits from session '{}' (newest first):\n", vibe_id);
+    for commit in &commits {
+        let date = Local
+            .timestamp_opt(commit.time, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let merged = head
+            .as_ref()
+            .map(|head| git.is_ancestor(&commit.oid, head).unwrap_or(false))
+            .unwrap_or(false);
+
+        println!(
+            "  {}  {}  {}{}",
+            &commit.oid[..12.min(commit.oid.len())],
+            date,
+            commit.subject,
+            if merged { "  (in HEAD)" } else { "" }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{init, promote, spawn};
+    use crate::db::MetadataStore;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("README.md"), "# Test").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_session_commits_are_chained() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+        let session_dir = repo_path.join(".vibe/sessions/test-vibe");
+        let edit = |path: &str, content: &str| {
+            fs::write(session_dir.join(path), content).unwrap();
+            let metadata = MetadataStore::open(repo_path.join(".vibe/metadata.db")).unwrap();
+            metadata.mark_dirty(path).unwrap();
+        };
+
+        edit("a.txt", "a");
+        promote::promote(repo_path, "test-vibe", None, Some("Add a"), true, false).await.unwrap();
+        edit("b.txt", "b");
+        promote::promote(repo_path, "test-vibe", None, Some("Add b"), true, false).await.unwrap();
+
+        let git = GitRepo::open(repo_path).unwrap();
+        let commits = session_commits(&git, "test-vibe").unwrap();
+        let subjects: Vec<&str> = commits.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["Add b", "Add a"]);
+        assert_eq!(commits[0].parents, vec![commits[1].oid.clone()]);
+        assert_eq!(commits[1].parents, vec![git.head_commit().unwrap()]);
+
+        // --amend replaces the tip and keeps its parent
+        edit("c.txt", "c");
+        promote::promote(repo_path, "test-vibe", None, None, true, true).await.unwrap();
+        let amended = session_commits(&git, "test-vibe").unwrap();
+        assert_eq!(amended.len(), 2);
+        assert_eq!(amended[0].subject, "Add b");
+        assert_eq!(amended[0].parents, vec![commits[1].oid.clone()]);
+        assert!(git.read_file_at_commit(&amended[0].oid, "c.txt").unwrap().is_some());
+    }
+}
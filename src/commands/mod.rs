@@ -10,10 +10,32 @@ pub mod inspect;
 pub mod status;
 pub mod launch;
 pub mod rebase;
+pub mod log;
+pub mod restack;
+pub mod fork;
+pub mod transplant;
+pub mod config;
 
 use anyhow::{Context, Result};
 use std::path::Path;
 
+/// Make the daemon's buffered writes for a session durable before reading
+/// the session from disk. Nothing to do when no daemon is running or the
+/// session isn't mounted.
+pub async fn sync_session(repo_path: &Path, vibe_id: &str) -> Result<()> {
+    use crate::daemon_client::DaemonClient;
+    use crate::daemon_ipc::DaemonResponse;
+
+    let Ok(mut client) = DaemonClient::connect(repo_path).await else {
+        return Ok(());
+    };
+    match client.sync_session(vibe_id).await? {
+        DaemonResponse::Error { message } if message.contains("not found") => Ok(()),
+        DaemonResponse::Error { message } => anyhow::bail!("{}", message),
+        _ => Ok(()),
+    }
+}
+
 /// Detection source for session auto-detection
 #[derive(Debug, Clone, PartialEq)]
 pub enum DetectionSource {
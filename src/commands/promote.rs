@@ -4,10 +4,13 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
 
+use crate::commands::rebase::{blocking_conflicts, unresolved_conflicts};
 use crate::commands::spawn::SpawnInfo;
-use crate::db::MetadataStore;
-use crate::git::GitRepo;
+use crate::config::Config;
+use crate::db::InodeMetadata;
+use crate::git::{GitRepo, SESSION_TRAILER};
 use crate::gitignore::PromoteFilter;
+use crate::session_state::{SessionChanges, SessionState};
 use crate::cwd_validation;
 
 /// Promote a vibe session into a Git commit
@@ -17,6 +20,7 @@ pub async fn promote<P: AsRef<Path>>(
     only_paths: Option<Vec<String>>,
     message: Option<&str>,
     ref_only: bool,
+    amend: bool,
 ) -> Result<()> {
     // Validate that we're running from the correct directory
     let _validated_root = cwd_validation::validate_cwd()
@@ -30,45 +34,55 @@ pub async fn promote<P: AsRef<Path>>(
         anyhow::bail!("Vibe session '{}' does not exist", vibe_id);
     }
 
-    // Check if session is behind HEAD
-    if let Ok(spawn_info) = SpawnInfo::load(repo_path, vibe_id) {
-        if let Some(ref base_commit) = spawn_info.spawn_commit {
-            let git = GitRepo::open(repo_path)?;
-            if let Ok(head_commit) = git.head_commit() {
-                if base_commit != &head_commit {
-                    eprintln!("⚠ WARNING: Session '{}' is based on {} but HEAD is at {}",
-                        vibe_id, &base_commit[..7.min(base_commit.len())], &head_commit[..7.min(head_commit.len())]);
-                    eprintln!("  The promoted commit will be based on HEAD, not your session's base commit.");
-                    eprintln!("  Consider running 'vibe rebase {}' first to update.\n", vibe_id);
-                }
+    // Buffered NFS writes must be on disk before the session is read
+    crate::commands::sync_session(repo_path, vibe_id).await?;
+
+    // Refuse to commit conflict markers or unmerged files left by `vibe rebase`
+    if let Ok(mut spawn_info) = SpawnInfo::load(repo_path, vibe_id) {
+        if !spawn_info.conflicts.is_empty() || !spawn_info.unmerged.is_empty() {
+            let unresolved = unresolved_conflicts(&session_dir, &spawn_info.conflicts);
+            if unresolved.len() != spawn_info.conflicts.len() {
+                spawn_info.conflicts = unresolved;
+                spawn_info.save(repo_path)?;
+            }
+            let blocking = blocking_conflicts(&session_dir, &spawn_info);
+            if !blocking.is_empty() {
+                anyhow::bail!(
+                    "Session '{}' has unresolved rebase conflicts in:\n  {}\nRemove the conflict markers (or run 'vibe resolve' for unmerged files) and run 'vibe commit' again.",
+                    vibe_id,
+                    blocking.join("\n  ")
+                );
             }
         }
     }
 
-    println!("Promoting vibe session: {}", vibe_id);
+    let config = Config::load(repo_path)?;
+    let ref_only = ref_only || config.commit.ref_only;
 
-    // Open per-session metadata store (fallback to base for backward compat)
-    let metadata_path = {
-        let session_db = vibe_dir.join("sessions").join(vibe_id).join("metadata.db");
-        if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") }
-    };
-    let metadata = MetadataStore::open_readonly(&metadata_path)
-        .context("Failed to open metadata store. Is the daemon running?")?;
+    // The session's changes are relative to the commit it was spawned from
+    let session_base = SpawnInfo::load(repo_path, vibe_id)
+        .ok()
+        .and_then(|info| info.spawn_commit);
 
-    // Open Git repository
+    // The last commit made from this session, which the next one builds on
     let git = GitRepo::open(repo_path)
         .context("Failed to open Git repository")?;
+    let ref_name = format!("refs/vibes/{}", vibe_id);
+    let previous = match git.get_ref(&ref_name)? {
+        Some(oid) => Some(git.commit_summary(&oid)?)
+            .filter(|commit| commit.session.as_deref() == Some(vibe_id)),
+        None => None,
+    };
+    if amend && previous.is_none() {
+        anyhow::bail!("Session '{}' has no commit to amend", vibe_id);
+    }
 
-    // Get dirty paths (modified files)
-    // Try to get from metadata first, but if empty, scan session directory
-    let mut all_dirty_paths = metadata.get_dirty_paths()
-        .context("Failed to get dirty paths")?;
+    println!("Promoting vibe session: {}", vibe_id);
 
-    // If no dirty paths found in metadata, scan session directory for changes
-    // This handles the case where the daemon has the DB locked
-    if all_dirty_paths.is_empty() {
-        all_dirty_paths = scan_session_directory(&session_dir)?;
-    }
+    // Dirty paths (modified files) and base files deleted in the session,
+    // from the daemon's live store when it is running
+    let mut metadata = SessionState::open(repo_path, vibe_id).await?;
+    let SessionChanges { dirty: all_dirty_paths, deleted: all_deleted_paths } = metadata.changes().await?;
 
     // Create gitignore filter
     let filter = PromoteFilter::new(repo_path, Some(&session_dir))
@@ -92,22 +106,24 @@ pub async fn promote<P: AsRef<Path>>(
         println!();
     }
 
-    // Filter by --only patterns if provided
-    let dirty_paths: Vec<String> = if let Some(ref patterns) = only_paths {
+    // Filter by --only patterns if provided. Deletions are never gitignore-filtered:
+    // they remove files that are already tracked.
+    let (dirty_paths, deleted_paths): (Vec<String>, Vec<String>) = if let Some(ref patterns) = only_paths {
         let globs: Vec<Pattern> = patterns
             .iter()
             .filter_map(|p| Pattern::new(p).ok())
             .collect();
+        let matches = |path: &String| globs.iter().any(|g| g.matches(path));
 
-        promotable_paths
-            .into_iter()
-            .filter(|path| globs.iter().any(|g| g.matches(path)))
-            .collect()
+        (
+            promotable_paths.into_iter().filter(matches).collect(),
+            all_deleted_paths.into_iter().filter(matches).collect(),
+        )
     } else {
-        promotable_paths
+        (promotable_paths, all_deleted_paths)
     };
 
-    if dirty_paths.is_empty() {
+    if dirty_paths.is_empty() && deleted_paths.is_empty() && !amend {
         if only_paths.is_some() {
             println!("No changes matching the specified patterns to promote");
         } else if !ignored_paths.is_empty() {
@@ -118,14 +134,34 @@ pub async fn promote<P: AsRef<Path>>(
         return Ok(());
     }
 
-    println!("Promoting {} files:", dirty_paths.len());
+    println!("Promoting {} files:", dirty_paths.len() + deleted_paths.len());
     for path in &dirty_paths {
         println!("  - {}", path);
     }
+    for path in &deleted_paths {
+        println!("  - {} (deleted)", path);
+    }
 
     // Hash new blobs for modified files
     let mut new_blobs = HashMap::new();
     for path in &dirty_paths {
+        let inode = metadata.inode(path).await?;
+
+        // Symlinks are committed as a blob holding the target. Artifact
+        // symlinks (volatile) are local build storage and never committed.
+        if let Some(inode) = inode.as_ref().filter(|m| m.is_symlink()) {
+            if inode.volatile {
+                continue;
+            }
+            let target = inode.link_target().unwrap_or_default();
+            let oid = git.write_blob(target.as_bytes())
+                .with_context(|| format!("Failed to hash symlink {}", path))?;
+
+            println!("  Hashed {} -> {} (symlink)", path, &oid);
+            new_blobs.insert(path.clone(), (oid, "120000"));
+            continue;
+        }
+
         let file_path = session_dir.join(path);
         if file_path.exists() && file_path.is_file() {
             let content = std::fs::read(&file_path)
@@ -135,7 +171,7 @@ pub async fn promote<P: AsRef<Path>>(
                 .with_context(|| format!("Failed to hash blob for {}", path))?;
 
             println!("  Hashed {} -> {}", path, &oid);
-            new_blobs.insert(path.clone(), oid);
+            new_blobs.insert(path.clone(), (oid, file_mode(inode.as_ref(), &file_path)));
         }
     }
 
@@ -144,26 +180,47 @@ pub async fn promote<P: AsRef<Path>>(
 
     let head_oid = git.head_commit()
         .context("Failed to get HEAD commit")?;
+    let base_oid = session_base.unwrap_or_else(|| head_oid.clone());
 
-    // Create a temporary index based on HEAD
+    // Create a temporary index based on the session's base
     let temp_index = session_dir.parent().unwrap().join(format!("{}_index", vibe_id));
 
-    // Read HEAD tree into temporary index
+    // Read the base tree into temporary index
     let output = Command::new("git")
-        .args(&["read-tree", &head_oid])
+        .args(["read-tree", &base_oid])
         .env("GIT_INDEX_FILE", &temp_index)
         .current_dir(repo_path)
         .output()
-        .context("Failed to read HEAD tree")?;
+        .with_context(|| format!("Failed to read tree of {}", base_oid))?;
 
     if !output.status.success() {
-        anyhow::bail!("Failed to read tree");
+        anyhow::bail!("Failed to read tree of {}", base_oid);
+    }
+
+    // Remove deleted files first, so a path deleted and then recreated in the
+    // session ends up with its new content. --force-remove is needed because the
+    // path usually still exists in the working tree.
+    for path in &deleted_paths {
+        let output = Command::new("git")
+            .args(["update-index", "--force-remove", "--", path])
+            .env("GIT_INDEX_FILE", &temp_index)
+            .current_dir(repo_path)
+            .output()
+            .context("Failed to update index")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to remove {} from index: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
     }
 
     // Update index with modified files
-    for (path, oid) in &new_blobs {
+    for (path, (oid, mode)) in &new_blobs {
         let output = Command::new("git")
-            .args(&["update-index", "--add", "--cacheinfo", &format!("100644,{},{}", oid, path)])
+            .args(["update-index", "--add", "--cacheinfo", &format!("{},{},{}", mode, oid, path)])
             .env("GIT_INDEX_FILE", &temp_index)
             .current_dir(repo_path)
             .output()
@@ -192,17 +249,47 @@ pub async fn promote<P: AsRef<Path>>(
     // Clean up temporary index
     let _ = std::fs::remove_file(&temp_index);
 
-    // Create commit with HEAD as parent
-    let commit_message = message
-        .map(|m| m.to_string())
-        .unwrap_or_else(|| format!("Vibe promotion: {}\n\nPromoted changes from vibe session", vibe_id));
+    // Chain on the previous session commit (--amend replaces it), and on the
+    // session's base unless the chain already contains it
+    let mut parents: Vec<String> = match &previous {
+        Some(prev) if amend => prev.parents.iter()
+            .filter(|p| is_session_commit(&git, p, vibe_id))
+            .cloned()
+            .collect(),
+        Some(prev) => vec![prev.oid.clone()],
+        None => Vec::new(),
+    };
+    if !parents.iter().any(|p| git.is_ancestor(&base_oid, p).unwrap_or(false)) {
+        parents.push(base_oid.clone());
+    }
 
-    let commit_oid = git.create_commit(&tree_oid, &head_oid, &commit_message)
-        .context("Failed to create commit")?;
-    println!("  Created commit: {}", commit_oid);
+    let commit_message = match (message, &previous) {
+        (Some(m), _) => m.to_string(),
+        (None, Some(prev)) if amend => strip_session_trailer(&prev.message),
+        (None, _) => match &config.commit.message {
+            Some(template) => template.replace("{session}", vibe_id),
+            None => format!("Vibe promotion: {}\n\nPromoted changes from vibe session", vibe_id),
+        },
+    };
+    let commit_message = with_session_trailer(&commit_message, vibe_id);
+
+    let unchanged = previous.as_ref().filter(|prev| {
+        !amend
+            && parents == [prev.oid.clone()]
+            && git.get_ref(&format!("{}^{{tree}}", prev.oid)).ok().flatten().as_deref() == Some(tree_oid.as_str())
+    });
+    let commit_oid = if let Some(prev) = unchanged {
+        println!("  No changes since the last session commit {}", &prev.oid[..12.min(prev.oid.len())]);
+        prev.oid.clone()
+    } else {
+        let parent_refs: Vec<&str> = parents.iter().map(String::as_str).collect();
+        let oid = git.create_commit_with_parents(&tree_oid, &parent_refs, &commit_message)
+            .context("Failed to create commit")?;
+        println!("  {} commit: {}", if amend { "Amended" } else { "Created" }, oid);
+        oid
+    };
 
     // Update refs/vibes/<vibe_id> reference
-    let ref_name = format!("refs/vibes/{}", vibe_id);
     git.update_ref(&ref_name, &commit_oid)
         .context("Failed to update reference")?;
 
@@ -210,11 +297,46 @@ pub async fn promote<P: AsRef<Path>>(
     println!("  Reference: {}", ref_name);
     println!("  Commit: {}", commit_oid);
 
+    // HEAD moved since the session's base: three-way merge the session
+    // commit with it instead of overlaying session files on HEAD's tree
+    let mut merge_target = commit_oid.clone();
+    if !git.is_ancestor(&head_oid, &commit_oid)? {
+        println!("  Session base {} is behind HEAD {}, merging",
+            &base_oid[..7.min(base_oid.len())], &head_oid[..7.min(head_oid.len())]);
+        let merge = git.merge_trees(&head_oid, &commit_oid)
+            .context("Failed to merge session commit with HEAD")?;
+
+        if !merge.conflicts.is_empty() {
+            println!("\n⚠ CONFLICTS with HEAD:");
+            for path in &merge.conflicts {
+                println!("  - {}", path);
+            }
+            if ref_only {
+                println!("\nTo merge into main, run: git merge {} (and resolve the conflicts)", ref_name);
+                return Ok(());
+            }
+            anyhow::bail!(
+                "Session '{}' conflicts with HEAD in {} file(s). Run 'vibe rebase {}', resolve the conflicts and commit again.",
+                vibe_id,
+                merge.conflicts.len(),
+                vibe_id
+            );
+        }
+
+        merge_target = git.create_commit_with_parents(
+            &merge.tree,
+            &[&head_oid, &commit_oid],
+            &format!("Merge vibe session '{}'", vibe_id),
+        )
+        .context("Failed to create merge commit")?;
+        println!("  Merge commit: {}", merge_target);
+    }
+
     if ref_only {
         println!("\nTo merge into main, run: git merge {}", ref_name);
     } else {
         // Auto-merge into HEAD
-        match git.merge_ff(&ref_name) {
+        match git.merge_ff(&merge_target) {
             Ok(new_head) => {
                 println!("  Merged into HEAD: {}", &new_head[..12.min(new_head.len())]);
 
@@ -228,7 +350,7 @@ pub async fn promote<P: AsRef<Path>>(
             }
             Err(e) => {
                 eprintln!("  Warning: auto-merge failed: {}", e);
-                println!("  To merge manually, run: git merge {}", ref_name);
+                println!("  To merge manually, run: git merge {}", merge_target);
             }
         }
     }
@@ -236,54 +358,40 @@ pub async fn promote<P: AsRef<Path>>(
     Ok(())
 }
 
-/// Scan session directory for files (when daemon has DB locked)
-fn scan_session_directory(session_dir: &Path) -> Result<Vec<String>> {
-    let mut files = Vec::new();
-    scan_directory_recursive(session_dir, session_dir, &mut files)?;
-    Ok(files)
+/// Whether `oid` was committed from session `vibe_id`
+fn is_session_commit(git: &GitRepo, oid: &str, vibe_id: &str) -> bool {
+    git.commit_summary(oid)
+        .map(|commit| commit.session.as_deref() == Some(vibe_id))
+        .unwrap_or(false)
 }
 
-fn scan_directory_recursive(base: &Path, current: &Path, files: &mut Vec<String>) -> Result<()> {
-    if !current.exists() {
-        return Ok(());
-    }
-
-    for entry in std::fs::read_dir(current)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // Skip symlinks (artifact directories like target/, node_modules/ are
-        // symlinked to local storage and are outside the CoW system)
-        if path.is_symlink() {
-            continue;
-        }
-
-        // Skip hidden files and macOS metadata files
-        if let Some(name) = path.file_name() {
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with('.') || name_str.starts_with("._") || name_str == ".DS_Store" {
-                continue;
-            }
-        }
+/// Commit message with its `Vibe-Session` trailer set to `vibe_id`
+fn with_session_trailer(message: &str, vibe_id: &str) -> String {
+    format!("{}\n\n{}: {}", strip_session_trailer(message), SESSION_TRAILER, vibe_id)
+}
 
-        // Skip metadata.db (per-session RocksDB store lives inside session dir)
-        if let Some(name) = path.file_name() {
-            if name == "metadata.db" {
-                continue;
-            }
-        }
+fn strip_session_trailer(message: &str) -> String {
+    let prefix = format!("{}:", SESSION_TRAILER);
+    let mut lines: Vec<&str> = message.trim_end().lines().collect();
+    while lines.last().is_some_and(|line| line.starts_with(&prefix) || line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
 
-        if path.is_dir() {
-            scan_directory_recursive(base, &path, files)?;
-        } else if path.is_file() {
-            // Get relative path from session dir
-            if let Ok(rel) = path.strip_prefix(base) {
-                files.push(rel.display().to_string());
-            }
-        }
+/// Git mode to commit a session file with. The inode's recorded mode wins;
+/// files unknown to the metadata store fall back to the session file's
+/// execute bit.
+fn file_mode(inode: Option<&InodeMetadata>, file_path: &Path) -> &'static str {
+    if let Some(inode) = inode {
+        return inode.git_file_mode();
     }
 
-    Ok(())
+    use std::os::unix::fs::PermissionsExt;
+    match std::fs::metadata(file_path) {
+        Ok(m) if m.permissions().mode() & 0o111 != 0 => "100755",
+        _ => "100644",
+    }
 }
 
 /// Promote all sessions with dirty files
@@ -323,7 +431,7 @@ pub async fn promote_all<P: AsRef<Path>>(
     for session in &sessions {
         print!("  {}: ", session);
 
-        match promote(repo_path, session, None, message, ref_only).await {
+        match promote(repo_path, session, None, message, ref_only, false).await {
             Ok(_) => {
                 // Check if anything was actually promoted
                 let git = GitRepo::open(repo_path)?;
@@ -351,6 +459,7 @@ pub async fn promote_all<P: AsRef<Path>>(
 mod tests {
     use super::*;
     use crate::commands::{init, spawn};
+    use crate::db::MetadataStore;
     use std::fs;
     use tempfile::TempDir;
 
@@ -417,11 +526,203 @@ mod tests {
         } // metadata is dropped here, releasing the lock
 
         // Promote (ref-only mode for test since we don't have a daemon)
-        promote(repo_path, "test-vibe", None, None, true).await.unwrap();
+        promote(repo_path, "test-vibe", None, None, true, false).await.unwrap();
 
         // Verify reference was created
         let git = GitRepo::open(repo_path).unwrap();
         let ref_oid = git.get_ref("refs/vibes/test-vibe").unwrap();
         assert!(ref_oid.is_some());
     }
+
+    #[tokio::test]
+    async fn test_promote_deleted_file() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+
+        // Delete README.md in the session
+        {
+            let metadata_path = repo_path.join(".vibe/metadata.db");
+            let metadata = MetadataStore::open(&metadata_path).unwrap();
+            let inode = metadata.get_inode_by_path("README.md").unwrap().unwrap();
+            let readme = metadata.get_inode(inode).unwrap().unwrap();
+            metadata.delete_inode(inode).unwrap();
+            metadata.put_whiteout(inode, &readme).unwrap();
+        }
+
+        promote(repo_path, "test-vibe", None, None, true, false).await.unwrap();
+
+        // The promoted commit no longer contains README.md
+        let git = GitRepo::open(repo_path).unwrap();
+        let ref_oid = git.get_ref("refs/vibes/test-vibe").unwrap().unwrap();
+        assert!(git.read_file_at_commit(&ref_oid, "README.md").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_promote_keeps_executable_mode() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+
+        // chmod +x README.md in the session
+        let session_dir = repo_path.join(".vibe/sessions/test-vibe");
+        fs::write(session_dir.join("README.md"), "# Test").unwrap();
+        {
+            let metadata_path = repo_path.join(".vibe/metadata.db");
+            let metadata = MetadataStore::open(&metadata_path).unwrap();
+            let inode = metadata.get_inode_by_path("README.md").unwrap().unwrap();
+            let mut readme = metadata.get_inode(inode).unwrap().unwrap();
+            assert_eq!(readme.mode, 0o644);
+            readme.mode = 0o755;
+            metadata.put_inode(inode, &readme).unwrap();
+            metadata.mark_dirty("README.md").unwrap();
+        }
+
+        promote(repo_path, "test-vibe", None, None, true, false).await.unwrap();
+
+        let output = Command::new("git")
+            .args(["ls-tree", "refs/vibes/test-vibe", "README.md"])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).starts_with("100755 "));
+    }
+
+    #[tokio::test]
+    async fn test_promote_symlink() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+
+        // ln -s README.md docs.md in the session
+        {
+            let metadata_path = repo_path.join(".vibe/metadata.db");
+            let metadata = MetadataStore::open(&metadata_path).unwrap();
+            let inode = metadata.next_inode_id().unwrap();
+            metadata.put_inode(inode, &InodeMetadata {
+                path: "docs.md".to_string(),
+                size: 9,
+                symlink_target: Some("README.md".to_string()),
+                ..Default::default()
+            }).unwrap();
+            metadata.mark_dirty("docs.md").unwrap();
+        }
+
+        promote(repo_path, "test-vibe", None, None, true, false).await.unwrap();
+
+        let output = Command::new("git")
+            .args(["ls-tree", "refs/vibes/test-vibe", "docs.md"])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).starts_with("120000 "));
+
+        let git = GitRepo::open(repo_path).unwrap();
+        let ref_oid = git.get_ref("refs/vibes/test-vibe").unwrap().unwrap();
+        assert_eq!(git.read_file_at_commit(&ref_oid, "docs.md").unwrap().unwrap(), b"README.md");
+    }
+
+    #[tokio::test]
+    async fn test_promote_refuses_unresolved_conflicts() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+
+        // A rebase left README.md conflicted
+        let session_dir = repo_path.join(".vibe/sessions/test-vibe");
+        fs::write(
+            session_dir.join("README.md"),
+            "<<<<<<< session test-vibe\n# Mine\n=======\n# Theirs\n>>>>>>> HEAD\n",
+        )
+        .unwrap();
+        {
+            let metadata = MetadataStore::open(repo_path.join(".vibe/metadata.db")).unwrap();
+            metadata.mark_dirty("README.md").unwrap();
+        }
+        let mut spawn_info = SpawnInfo::load(repo_path, "test-vibe").unwrap();
+        spawn_info.conflicts = vec!["README.md".to_string()];
+        spawn_info.save(repo_path).unwrap();
+
+        let err = promote(repo_path, "test-vibe", None, None, true, false).await.unwrap_err();
+        assert!(err.to_string().contains("README.md"));
+
+        // Resolving the markers clears the conflict, but an unmerged file
+        // blocks until it is marked resolved
+        fs::write(session_dir.join("README.md"), "# Both\n").unwrap();
+        let mut spawn_info = SpawnInfo::load(repo_path, "test-vibe").unwrap();
+        spawn_info.unmerged = vec!["README.md".to_string()];
+        spawn_info.save(repo_path).unwrap();
+        assert!(promote(repo_path, "test-vibe", None, None, true, false).await.is_err());
+        assert!(SpawnInfo::load(repo_path, "test-vibe").unwrap().conflicts.is_empty());
+
+        crate::commands::rebase::resolve(repo_path, "test-vibe", &["README.md".to_string()]).unwrap();
+        promote(repo_path, "test-vibe", None, None, true, false).await.unwrap();
+        assert!(SpawnInfo::load(repo_path, "test-vibe").unwrap().unmerged.is_empty());
+    }
+
+    /// Session with README.md edited, spawned before HEAD gained `upstream_file`
+    async fn setup_moved_head(upstream_file: &str, upstream_content: &str) -> (TempDir, String) {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+
+        init::init(repo_path).await.unwrap();
+        spawn::spawn_local(repo_path, "test-vibe").await.unwrap();
+        let base = GitRepo::open(repo_path).unwrap().head_commit().unwrap();
+
+        fs::write(repo_path.join(".vibe/sessions/test-vibe/README.md"), "# Session").unwrap();
+        {
+            let metadata = MetadataStore::open(repo_path.join(".vibe/metadata.db")).unwrap();
+            metadata.mark_dirty("README.md").unwrap();
+        }
+
+        fs::write(repo_path.join(upstream_file), upstream_content).unwrap();
+        Command::new("git").args(["add", upstream_file]).current_dir(repo_path).output().unwrap();
+        Command::new("git").args(["commit", "-m", "upstream"]).current_dir(repo_path).output().unwrap();
+
+        (temp_dir, base)
+    }
+
+    #[tokio::test]
+    async fn test_promote_merges_with_moved_head() {
+        let (temp_dir, base) = setup_moved_head("upstream.txt", "upstream").await;
+        let repo_path = temp_dir.path();
+        let git = GitRepo::open(repo_path).unwrap();
+        let upstream = git.head_commit().unwrap();
+
+        promote(repo_path, "test-vibe", None, None, false, false).await.unwrap();
+
+        // The session commit sits on the session's base
+        let session_commit = git.get_ref("refs/vibes/test-vibe").unwrap().unwrap();
+        assert_eq!(git.get_ref(&format!("{}^", session_commit)).unwrap().unwrap(), base);
+
+        // HEAD is a merge that keeps both sides
+        let head = git.head_commit().unwrap();
+        assert_eq!(git.get_ref(&format!("{}^1", head)).unwrap().unwrap(), upstream);
+        assert_eq!(git.get_ref(&format!("{}^2", head)).unwrap().unwrap(), session_commit);
+        assert_eq!(git.read_file_at_commit(&head, "README.md").unwrap().unwrap(), b"# Session");
+        assert_eq!(git.read_file_at_commit(&head, "upstream.txt").unwrap().unwrap(), b"upstream");
+    }
+
+    #[tokio::test]
+    async fn test_promote_reports_conflicts_with_head() {
+        let (temp_dir, _) = setup_moved_head("README.md", "# Upstream").await;
+        let repo_path = temp_dir.path();
+        let git = GitRepo::open(repo_path).unwrap();
+        let upstream = git.head_commit().unwrap();
+
+        let err = promote(repo_path, "test-vibe", None, None, false, false).await.unwrap_err();
+        assert!(err.to_string().contains("conflicts with HEAD"));
+
+        // Upstream's README.md was not clobbered
+        assert_eq!(git.head_commit().unwrap(), upstream);
+        assert_eq!(git.read_file_at_commit(&upstream, "README.md").unwrap().unwrap(), b"# Upstream");
+    }
 }
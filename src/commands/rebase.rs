@@ -1,12 +1,14 @@
 //! `vibe rebase <session>` command - Update session base to current HEAD
 
 use anyhow::{Context, Result};
+use std::collections::BTreeSet;
 use std::path::Path;
 
+use crate::commands::init::tracked_file_metadata;
 use crate::commands::spawn::SpawnInfo;
 use crate::daemon_client::DaemonClient;
 use crate::daemon_ipc::DaemonResponse;
-use crate::db::MetadataStore;
+use crate::db::{InodeMetadata, MetadataStore};
 use crate::git::GitRepo;
 use crate::platform;
 
@@ -18,14 +20,20 @@ fn is_cwd_inside_mount(mount_point: &str) -> bool {
         .unwrap_or(false)
 }
 
-/// Rebase a session to the current HEAD
+/// Rebase a session to the current HEAD (or the tip of the ref it was
+/// started from with `vibe new --from`)
 ///
-/// This updates the session's spawn_commit to the current HEAD, effectively
-/// moving the base forward. The session's delta files are preserved.
+/// This updates the session's spawn_commit to that commit, effectively
+/// moving the base forward, and applies the base tree diff to the session's
+/// inode map (see `apply_base_changes`). The session's delta files are preserved.
 ///
-/// Note: This is a simple rebase that doesn't check for conflicts between
-/// the session deltas and changes in HEAD..spawn_commit. For safety, we
-/// warn but allow the user to proceed.
+/// Session files that also changed upstream are three-way merged against the
+/// old base (see `merge_session_changes`). Files that don't merge cleanly get
+/// conflict markers and are recorded in the session's conflict list. Files
+/// that can't be merged keep the session version and are recorded as
+/// unmerged. Both block `vibe commit` until they are resolved. If the
+/// merge or the store update fails, the session keeps its old base and its
+/// own files.
 pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) -> Result<()> {
     let repo_path = repo_path.as_ref();
     let vibe_dir = repo_path.join(".vibe");
@@ -34,62 +42,41 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
     let mut spawn_info = SpawnInfo::load(repo_path, session)
         .with_context(|| format!("Session '{}' not found", session))?;
 
-    // Get current HEAD
+    // Get current HEAD (or the tip of the session's base ref)
     let git = GitRepo::open(repo_path)?;
-    let head_commit = git.head_commit()
-        .context("Failed to get HEAD commit")?;
+    let upstream = spawn_info.upstream_name().to_string();
+    let head_commit = spawn_info.upstream_commit(&git)
+        .with_context(|| format!("Failed to resolve {}", upstream))?;
 
     // Check if already at HEAD
     if spawn_info.spawn_commit.as_ref() == Some(&head_commit) {
-        println!("Session '{}' is already at HEAD ({})", session, &head_commit[..7]);
+        println!("Session '{}' is already at {} ({})", session, upstream, &head_commit[..7]);
         return Ok(());
     }
 
     let old_base = spawn_info.spawn_commit.clone().unwrap_or_else(|| "unknown".to_string());
 
     // Show what we're doing
-    println!("Rebasing session '{}' to HEAD", session);
+    println!("Rebasing session '{}' to {}", session, upstream);
     println!("  Old base: {}", &old_base[..12.min(old_base.len())]);
     println!("  New base: {}", &head_commit[..12.min(head_commit.len())]);
 
-    // Check for potential conflicts by looking at what files changed in HEAD
+    // Files still carrying markers from an earlier rebase would be merged
+    // again, and unmerged files would be overwritten by the new base
     let session_dir = vibe_dir.join("sessions").join(session);
-    let session_files = list_session_files(&session_dir)?;
-
-    if !session_files.is_empty() {
-        // Get files changed between old base and HEAD
-        let changed_in_git = get_changed_files(&git, &old_base, &head_commit)?;
-
-        // Find conflicts
-        let conflicts: Vec<_> = session_files.iter()
-            .filter(|f| changed_in_git.contains(*f))
-            .collect();
-
-        if !conflicts.is_empty() {
-            println!("\n⚠ WARNING: The following files were modified in both the session and Git:");
-            for file in &conflicts {
-                println!("  - {}", file);
-            }
-            println!("\nRebasing will keep your session changes, but you may need to manually");
-            println!("reconcile with the Git changes when you promote.");
-
-            if !force {
-                println!("\nUse 'vibe rebase {} --force' to proceed anyway.", session);
-                return Ok(());
-            }
-            println!("\nProceeding with --force...");
+    let unresolved = blocking_conflicts(&session_dir, &spawn_info);
+    if !unresolved.is_empty() {
+        println!("\n⚠ Session '{}' has unresolved conflicts from a previous rebase:", session);
+        for file in &unresolved {
+            println!("  - {}", file);
         }
+        if !force {
+            println!("\nResolve them, or use 'vibe rebase {} --force' to proceed anyway.", session);
+            return Ok(());
+        }
+        println!("\nProceeding with --force...");
     }
-
-    // Update spawn_commit
-    spawn_info.spawn_commit = Some(head_commit.clone());
-
-    // Save updated spawn info
-    let info_path = vibe_dir.join("sessions").join(format!("{}.json", session));
-    let info_json = serde_json::to_string_pretty(&spawn_info)?;
-    std::fs::write(&info_path, info_json)?;
-
-    println!("\n✓ Session '{}' rebased to {}", session, &head_commit[..7]);
+    spawn_info.conflicts = unresolved_conflicts(&session_dir, &spawn_info.conflicts);
 
     // If daemon is running, try RPC rebase (keeps NFS alive, no bricked shells)
     if DaemonClient::is_running(repo_path).await {
@@ -100,7 +87,12 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
         .await;
 
         match rpc_result {
-            Ok(DaemonResponse::SessionRebased { reconciled_count, .. }) => {
+            Ok(DaemonResponse::SessionRebased { reconciled_count, base_changes, merged, conflicts, unmerged, .. }) => {
+                println!("\n✓ Session '{}' rebased to {}", session, &head_commit[..7]);
+                print_merge_outcome(session, merged, &conflicts, &unmerged);
+                if base_changes > 0 {
+                    println!("  Updated {} path(s) from the new base", base_changes);
+                }
                 if reconciled_count > 0 {
                     println!("  Cleaned up {} stale file(s) that match HEAD", reconciled_count);
                 }
@@ -117,7 +109,7 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
             }
         }
 
-        // Legacy fallback: unmount, reconcile, re-export
+        // Legacy fallback: unmount, rebase offline, re-export
         print!("  Restarting NFS mount...");
 
         let mut client = DaemonClient::connect(repo_path).await?;
@@ -132,12 +124,8 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
         let old_mount = spawn_info.mount_point.to_string_lossy().to_string();
         platform::unmount_nfs_sync(&old_mount).ok();
 
-        let session_metadata_db = session_dir.join("metadata.db");
-        match reconcile_session_files(&git, &session_dir, &head_commit, Some(&session_metadata_db)) {
-            Ok(0) => {}
-            Ok(n) => println!("\n  Cleaned up {} stale file(s) that match HEAD", n),
-            Err(e) => eprintln!("\n  Warning: reconciliation error: {}", e),
-        }
+        // The session is exported again whether or not the rebase worked
+        let rebased = rebase_offline(&git, repo_path, &mut spawn_info, &session_dir, &old_base, &head_commit);
 
         let mut client = DaemonClient::connect(repo_path).await?;
         match client.export_session(session).await? {
@@ -148,8 +136,7 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
                         println!("  NFS mounted at: {}", mount_point);
 
                         spawn_info.port = nfs_port;
-                        let info_json = serde_json::to_string_pretty(&spawn_info)?;
-                        std::fs::write(&info_path, info_json)?;
+                        spawn_info.save(repo_path)?;
 
                         if let Err(e) = platform::register_mount(&mount_point, repo_path) {
                             eprintln!("  Warning: Failed to register mount: {}", e);
@@ -171,14 +158,9 @@ pub async fn rebase<P: AsRef<Path>>(repo_path: P, session: &str, force: bool) ->
             }
             _ => {}
         }
+        rebased?;
     } else {
-        // Daemon not running — still reconcile stale files
-        let session_metadata_db = session_dir.join("metadata.db");
-        match reconcile_session_files(&git, &session_dir, &head_commit, Some(&session_metadata_db)) {
-            Ok(0) => {}
-            Ok(n) => println!("  Cleaned up {} stale file(s) that match HEAD", n),
-            Err(e) => eprintln!("  Warning: reconciliation error: {}", e),
-        }
+        rebase_offline(&git, repo_path, &mut spawn_info, &session_dir, &old_base, &head_commit)?;
         println!("  Note: Daemon not running. Start a session with 'vibe new {}' to apply.", session);
     }
 
@@ -197,10 +179,6 @@ fn reconcile_session_files(
     metadata_db_path: Option<&Path>,
 ) -> Result<usize> {
     let session_files = list_session_files(session_dir)?;
-    if session_files.is_empty() {
-        return Ok(0);
-    }
-
     let mut reconciled = 0;
 
     // Try to open per-session metadata.db to clear dirty markers
@@ -251,9 +229,475 @@ fn reconcile_session_files(
         }
     }
 
+    // Deletions that HEAD now also has are no longer session changes
+    if let Some(ref s) = store {
+        for path in s.get_whiteout_paths().unwrap_or_default() {
+            if let Ok(None) = git.read_file_at_commit(head_commit, &path) {
+                let _ = s.clear_whiteout(&path);
+                reconciled += 1;
+            }
+        }
+    }
+
     Ok(reconciled)
 }
 
+/// Inode map updates made by `apply_base_changes`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BaseChanges {
+    pub added: usize,
+    pub removed: usize,
+    pub updated: usize,
+}
+
+impl BaseChanges {
+    pub fn total(&self) -> usize {
+        self.added + self.removed + self.updated
+    }
+}
+
+/// Move a session's inode map from `old_base` to `new_base`.
+///
+/// The store describes the base tree overlaid with the session's changes, so
+/// files added upstream get inodes (and parent directories), files deleted
+/// upstream lose theirs, and modified files point at their new blobs. Paths
+/// the session changed keep the session's version: a dirty file only has its
+/// base oid moved, and a whiteout stays deleted but restores the new version
+/// on reset.
+pub fn apply_base_changes(
+    git: &GitRepo,
+    store: &MetadataStore,
+    old_base: &str,
+    new_base: &str,
+) -> Result<BaseChanges> {
+    let changes = git.diff_trees(old_base, new_base)?;
+    let mut result = BaseChanges::default();
+    let mut touched_dirs: BTreeSet<String> = BTreeSet::new();
+
+    for change in changes {
+        let path = change.path;
+
+        if let (Some((oid, mode)), Some(mut whiteout)) = (&change.new, store.get_whiteout(&path)?) {
+            whiteout.metadata = tracked_file_metadata(git, path.clone(), oid.clone(), *mode);
+            store.clear_whiteout(&path)?;
+            store.put_whiteout(whiteout.inode_id, &whiteout.metadata)?;
+        }
+
+        let existing = match store.get_inode_by_path(&path)? {
+            Some(id) => store.get_inode(id)?.map(|metadata| (id, metadata)),
+            None => None,
+        };
+        let dirty = store.is_dirty(&path)?;
+
+        match (change.new, existing) {
+            (Some((_, _)), Some((_, metadata))) if metadata.is_dir => {
+                eprintln!("  Warning: {} is a directory in the session, keeping it", path);
+            }
+            (Some((oid, mode)), Some((id, metadata))) => {
+                let updated = if dirty {
+                    // Session content wins, only the base it is compared against moves
+                    InodeMetadata { git_oid: Some(oid), volatile: false, ..metadata }
+                } else {
+                    tracked_file_metadata(git, path, oid, mode)
+                };
+                store.put_inode(id, &updated)?;
+                result.updated += 1;
+            }
+            (Some((oid, mode)), None) => {
+                if store.is_whiteout(&path)? {
+                    continue;
+                }
+                ensure_dir_inodes(store, &path)?;
+                let id = store.next_inode_id()?;
+                store.put_inode(id, &tracked_file_metadata(git, path, oid, mode))?;
+                result.added += 1;
+            }
+            (None, Some((id, metadata))) if !metadata.is_dir => {
+                if dirty {
+                    // Modified in the session: it stays, as a file the session adds
+                    store.put_inode(id, &InodeMetadata { git_oid: None, ..metadata })?;
+                    result.updated += 1;
+                } else {
+                    store.delete_inode(id)?;
+                    if let Some(parent) = Path::new(&path).parent() {
+                        touched_dirs.insert(parent.to_string_lossy().to_string());
+                    }
+                    result.removed += 1;
+                }
+            }
+            // Already gone from the session (whiteouts are settled by the
+            // reconciliation step)
+            _ => {}
+        }
+    }
+
+    // Drop directories that only held files deleted upstream, deepest first
+    for dir in touched_dirs.iter().rev() {
+        let mut current = Some(Path::new(dir.as_str()));
+        while let Some(dir_path) = current.filter(|p| !p.as_os_str().is_empty()) {
+            let dir_str = dir_path.to_string_lossy();
+            let subtree = store.get_subtree(&dir_str)?;
+            match subtree.as_slice() {
+                [(id, metadata)] if metadata.is_dir && !store.is_dirty(&dir_str)? => {
+                    store.delete_inode(*id)?;
+                }
+                _ => break,
+            }
+            current = dir_path.parent();
+        }
+    }
+
+    Ok(result)
+}
+
+/// Move a freshly cloned store from the tree it was built from (recorded as
+/// the root inode's `git_oid`, the init-time HEAD) to `commit`'s tree, and
+/// record `commit` on the root
+pub fn move_store_to_commit(git: &GitRepo, store: &MetadataStore, commit: &str) -> Result<BaseChanges> {
+    let Some(mut root) = store.get_inode(1)? else {
+        return Ok(BaseChanges::default());
+    };
+    let Some(built_from) = root.git_oid.clone() else {
+        return Ok(BaseChanges::default());
+    };
+    if built_from == commit {
+        return Ok(BaseChanges::default());
+    }
+
+    let changes = apply_base_changes(git, store, &built_from, commit)?;
+    root.git_oid = Some(commit.to_string());
+    store.put_inode(1, &root)?;
+    Ok(changes)
+}
+
+/// Session files updated by a `SessionMerge`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeOutcome {
+    /// Merged cleanly with the upstream change
+    pub merged: Vec<String>,
+    /// Written with conflict markers
+    pub conflicts: Vec<String>,
+    /// Changed upstream but impossible to merge (binary, deleted upstream, or
+    /// rejected by `git merge-file`); the session version is kept
+    pub unmerged: Vec<String>,
+}
+
+/// Session file merges computed by `merge_session_changes`, not yet written
+#[derive(Default)]
+pub struct SessionMerge {
+    files: Vec<FileMerge>,
+    unmerged: Vec<String>,
+}
+
+/// A session file merged by `merge_session_changes`
+struct FileMerge {
+    path: String,
+    /// The session's version, put back if writing the merge fails
+    ours: Vec<u8>,
+    merged: Vec<u8>,
+    conflicted: bool,
+}
+
+/// Three-way merge session files that also changed between `old_base` and
+/// `new_base`.
+///
+/// The old base (the session's spawn commit) is the merge base, the session
+/// file is "ours" and the new base, the tip of `upstream`, is "theirs". The
+/// merge has conflict markers where both sides changed the same lines. Files
+/// that can't be merged keep the session version and are reported as
+/// unmerged. Nothing is written: `SessionMerge::write` replaces the session
+/// files once the rest of the rebase is ready to go ahead.
+pub fn merge_session_changes(
+    git: &GitRepo,
+    session_dir: &Path,
+    vibe_id: &str,
+    upstream: &str,
+    old_base: &str,
+    new_base: &str,
+) -> Result<SessionMerge> {
+    let mut merge = SessionMerge::default();
+    let ours_label = format!("session {}", vibe_id);
+    let base_label = old_base[..12.min(old_base.len())].to_string();
+    let theirs_label = format!("{} {}", upstream, &new_base[..12.min(new_base.len())]);
+
+    for change in git.diff_trees(old_base, new_base)? {
+        let session_file = session_dir.join(&change.path);
+        match std::fs::symlink_metadata(&session_file) {
+            Ok(meta) if meta.is_file() => {}
+            _ => continue,
+        }
+
+        let Some((new_oid, _)) = change.new else {
+            eprintln!("  Warning: {} was deleted upstream but changed in the session", change.path);
+            merge.unmerged.push(change.path);
+            continue;
+        };
+
+        let ours = std::fs::read(&session_file)
+            .with_context(|| format!("Failed to read {}", session_file.display()))?;
+        let theirs = git.read_blob(&new_oid)?;
+        if ours == theirs {
+            // Same change on both sides, reconciliation drops the copy
+            continue;
+        }
+        let base = match &change.old {
+            Some((oid, _)) => git.read_blob(oid)?,
+            None => Vec::new(),
+        };
+
+        match git.merge_file(&ours, &base, &theirs, [&ours_label, &base_label, &theirs_label]) {
+            Ok((merged, conflicted)) => {
+                merge.files.push(FileMerge { path: change.path, ours, merged, conflicted })
+            }
+            Err(e) => {
+                eprintln!("  Warning: cannot merge {} ({})", change.path, e);
+                merge.unmerged.push(change.path);
+            }
+        }
+    }
+
+    Ok(merge)
+}
+
+impl SessionMerge {
+    /// The files this merge writes, and those it leaves unmerged
+    pub fn outcome(&self) -> MergeOutcome {
+        let (conflicts, merged): (Vec<_>, Vec<_>) = self.files.iter().partition(|file| file.conflicted);
+        MergeOutcome {
+            merged: merged.into_iter().map(|file| file.path.clone()).collect(),
+            conflicts: conflicts.into_iter().map(|file| file.path.clone()).collect(),
+            unmerged: self.unmerged.clone(),
+        }
+    }
+
+    /// Replace the session files with their merged versions. `store`, when
+    /// given, gets the new file sizes. If a write fails, the files already
+    /// written are restored, so an error leaves the session untouched.
+    pub fn write(&self, store: Option<&MetadataStore>, session_dir: &Path) -> Result<()> {
+        for (i, file) in self.files.iter().enumerate() {
+            if let Err(e) = write_session_file(store, session_dir, &file.path, &file.merged) {
+                restore_session_files(store, session_dir, &self.files[..=i]);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Put back the session's own version of every merged file, for a
+    /// rebase that failed after `write`
+    pub fn restore(&self, store: Option<&MetadataStore>, session_dir: &Path) {
+        restore_session_files(store, session_dir, &self.files);
+    }
+}
+
+/// Put back the session's own version of merged files
+fn restore_session_files(store: Option<&MetadataStore>, session_dir: &Path, files: &[FileMerge]) {
+    for file in files {
+        if let Err(e) = write_session_file(store, session_dir, &file.path, &file.ours) {
+            eprintln!("  Warning: failed to restore {}: {:#}", file.path, e);
+        }
+    }
+}
+
+/// Replace a session file's content and record its new size in `store`
+fn write_session_file(
+    store: Option<&MetadataStore>,
+    session_dir: &Path,
+    path: &str,
+    content: &[u8],
+) -> Result<()> {
+    let session_file = session_dir.join(path);
+    std::fs::write(&session_file, content)
+        .with_context(|| format!("Failed to write {}", session_file.display()))?;
+
+    if let Some(store) = store {
+        if let Some(id) = store.get_inode_by_path(path)? {
+            if let Some(mut metadata) = store.get_inode(id)? {
+                metadata.size = content.len() as u64;
+                metadata.touch_modified();
+                store.put_inode(id, &metadata)?;
+            }
+        }
+        store.mark_dirty(path)?;
+    }
+    Ok(())
+}
+
+/// The recorded conflicts whose session file still has conflict markers
+pub fn unresolved_conflicts(session_dir: &Path, conflicts: &[String]) -> Vec<String> {
+    conflicts
+        .iter()
+        .filter(|path| {
+            std::fs::read(session_dir.join(path))
+                .map(|content| has_conflict_markers(&content))
+                .unwrap_or(false)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Everything that blocks `vibe commit` for a session: recorded conflicts
+/// that still have markers, and unmerged files not yet marked resolved
+pub fn blocking_conflicts(session_dir: &Path, spawn_info: &SpawnInfo) -> Vec<String> {
+    let mut blocking = unresolved_conflicts(session_dir, &spawn_info.conflicts);
+    for path in &spawn_info.unmerged {
+        if !blocking.contains(path) {
+            blocking.push(path.clone());
+        }
+    }
+    blocking
+}
+
+/// Record a merge's conflicts and unmerged files in `spawn_info`
+pub fn record_merge_outcome(spawn_info: &mut SpawnInfo, outcome: &MergeOutcome) {
+    for path in &outcome.conflicts {
+        if !spawn_info.conflicts.contains(path) {
+            spawn_info.conflicts.push(path.clone());
+        }
+    }
+    for path in &outcome.unmerged {
+        if !spawn_info.unmerged.contains(path) {
+            spawn_info.unmerged.push(path.clone());
+        }
+    }
+}
+
+/// `vibe resolve`: mark conflicted or unmerged files as resolved, keeping
+/// the session's current version of each
+pub fn resolve(repo_path: &Path, session: &str, paths: &[String]) -> Result<()> {
+    let mut spawn_info = SpawnInfo::load(repo_path, session)
+        .with_context(|| format!("Session '{}' not found", session))?;
+    let session_dir = repo_path.join(".vibe/sessions").join(session);
+
+    for path in paths {
+        if !spawn_info.conflicts.contains(path) && !spawn_info.unmerged.contains(path) {
+            anyhow::bail!("'{}' is not a conflict in session '{}'", path, session);
+        }
+        if !unresolved_conflicts(&session_dir, std::slice::from_ref(path)).is_empty() {
+            anyhow::bail!("'{}' still has conflict markers", path);
+        }
+    }
+
+    spawn_info.conflicts.retain(|path| !paths.contains(path));
+    spawn_info.unmerged.retain(|path| !paths.contains(path));
+    spawn_info.save(repo_path)?;
+    println!("Marked {} file(s) resolved in session '{}'", paths.len(), session);
+    Ok(())
+}
+
+fn has_conflict_markers(content: &[u8]) -> bool {
+    let mut lines = content.split(|&b| b == b'\n');
+    lines.any(|line| line.starts_with(b"<<<<<<< ") || line == b"<<<<<<<")
+        && lines.any(|line| line.starts_with(b">>>>>>> ") || line == b">>>>>>>")
+}
+
+/// Rebase a session no daemon has open: merge its files, move its store to
+/// the new base and drop stale copies. The merged files are written only
+/// once every merge is computed, and put back if the store can't be moved,
+/// so on failure the session keeps its old base and its own files.
+fn rebase_offline(
+    git: &GitRepo,
+    repo_path: &Path,
+    spawn_info: &mut SpawnInfo,
+    session_dir: &Path,
+    old_base: &str,
+    new_base: &str,
+) -> Result<()> {
+    let merge = merge_session_changes(
+        git,
+        session_dir,
+        &spawn_info.vibe_id,
+        spawn_info.upstream_name(),
+        old_base,
+        new_base,
+    )
+    .context("Failed to merge session changes")?;
+
+    let metadata_db_path = session_dir.join("metadata.db");
+    let changes = {
+        let store = if metadata_db_path.exists() {
+            Some(MetadataStore::open(&metadata_db_path).context("Failed to open session metadata")?)
+        } else {
+            None
+        };
+        merge.write(store.as_ref(), session_dir).context("Failed to write merged session files")?;
+        let changes = match &store {
+            Some(store) => apply_base_changes(git, store, old_base, new_base),
+            None => Ok(BaseChanges::default()),
+        };
+        match changes {
+            Ok(changes) => changes,
+            Err(e) => {
+                merge.restore(store.as_ref(), session_dir);
+                return Err(e.context("Failed to update session to the new base"));
+            }
+        }
+    };
+
+    // The new base and the conflicts it brought are saved together
+    let outcome = merge.outcome();
+    spawn_info.spawn_commit = Some(new_base.to_string());
+    record_merge_outcome(spawn_info, &outcome);
+    spawn_info.save(repo_path)?;
+
+    print_merge_outcome(&spawn_info.vibe_id, outcome.merged.len(), &outcome.conflicts, &outcome.unmerged);
+    if changes.total() > 0 {
+        println!("  Updated {} path(s) from the new base", changes.total());
+    }
+    match reconcile_session_files(git, session_dir, new_base, Some(&metadata_db_path)) {
+        Ok(0) => {}
+        Ok(n) => println!("  Cleaned up {} stale file(s) that match HEAD", n),
+        Err(e) => eprintln!("  Warning: reconciliation error: {}", e),
+    }
+
+    println!("\n✓ Session '{}' rebased to {}", spawn_info.vibe_id, &new_base[..7]);
+    Ok(())
+}
+
+fn print_merge_outcome(session: &str, merged: usize, conflicts: &[String], unmerged: &[String]) {
+    if merged > 0 {
+        println!("\n  Merged upstream changes into {} session file(s)", merged);
+    }
+    if !conflicts.is_empty() {
+        println!("\n⚠ CONFLICTS: the following files were left with conflict markers:");
+        for file in conflicts {
+            println!("  - {}", file);
+        }
+        println!("\nResolve them in the session before running 'vibe commit -s {}'.", session);
+    }
+    if !unmerged.is_empty() {
+        println!("\n⚠ UNMERGED: the following files changed upstream but could not be merged:");
+        for file in unmerged {
+            println!("  - {}", file);
+        }
+        println!("\nThe session kept its version. Apply the upstream change by hand, then run 'vibe resolve -s {} <file>'.", session);
+    }
+}
+
+/// Create inodes for the missing parent directories of `path`
+pub fn ensure_dir_inodes(store: &MetadataStore, path: &str) -> Result<()> {
+    let mut missing = Vec::new();
+    let mut current = Path::new(path).parent();
+    while let Some(dir) = current.filter(|p| !p.as_os_str().is_empty()) {
+        let dir_str = dir.to_string_lossy().to_string();
+        if store.get_inode_by_path(&dir_str)?.is_some() {
+            break;
+        }
+        missing.push(dir_str);
+        current = dir.parent();
+    }
+
+    for dir in missing.into_iter().rev() {
+        let id = store.next_inode_id()?;
+        store.put_inode(id, &InodeMetadata {
+            path: dir,
+            is_dir: true,
+            ..Default::default()
+        })?;
+    }
+    Ok(())
+}
+
 /// Remove empty parent directories up to (but not including) the base directory
 fn remove_empty_parents(dir: &Path, base: &Path) -> Result<()> {
     let mut current = dir;
@@ -315,29 +759,6 @@ fn list_session_files(session_dir: &Path) -> Result<Vec<String>> {
     Ok(files)
 }
 
-/// Get files changed between two commits
-fn get_changed_files(git: &GitRepo, from: &str, to: &str) -> Result<Vec<String>> {
-    use std::process::Command;
-
-    let output = Command::new("git")
-        .args(["diff", "--name-only", from, to])
-        .current_dir(git.repo_path())
-        .output()
-        .context("Failed to run git diff")?;
-
-    if !output.status.success() {
-        // If git diff fails (e.g., invalid commit), return empty list
-        return Ok(Vec::new());
-    }
-
-    let files: Vec<String> = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .map(|s| s.to_string())
-        .collect();
-
-    Ok(files)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,4 +900,133 @@ mod tests {
         let store = MetadataStore::open(&db_path).unwrap();
         assert!(!store.is_dirty("file.txt").unwrap(), "dirty marker should be cleared after reconciliation");
     }
+
+    #[test]
+    fn test_apply_base_changes() {
+        use std::fs;
+
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let commit = |message: &str| {
+            std::process::Command::new("git").args(["add", "-A"]).current_dir(repo_path).output().unwrap();
+            std::process::Command::new("git").args(["commit", "-m", message]).current_dir(repo_path).output().unwrap();
+        };
+
+        fs::create_dir_all(repo_path.join("docs")).unwrap();
+        fs::write(repo_path.join("docs/guide.md"), "guide").unwrap();
+        fs::write(repo_path.join("config.toml"), "v1").unwrap();
+        fs::write(repo_path.join("edited.rs"), "base").unwrap();
+        commit("old base");
+        let git = GitRepo::open(repo_path).unwrap();
+        let old_base = git.head_commit().unwrap();
+
+        // Session store as init builds it, with one file edited in the session
+        let db_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(db_dir.path().join("metadata.db")).unwrap();
+        for (path, oid, mode) in git.list_tree_files().unwrap() {
+            let path = path.to_string_lossy().to_string();
+            ensure_dir_inodes(&store, &path).unwrap();
+            let id = store.next_inode_id().unwrap();
+            store.put_inode(id, &tracked_file_metadata(&git, path, oid, mode)).unwrap();
+        }
+        store.mark_dirty("edited.rs").unwrap();
+
+        fs::remove_file(repo_path.join("docs/guide.md")).unwrap();
+        fs::write(repo_path.join("config.toml"), "v2, longer").unwrap();
+        fs::write(repo_path.join("edited.rs"), "upstream").unwrap();
+        fs::create_dir_all(repo_path.join("src/bin")).unwrap();
+        fs::write(repo_path.join("src/bin/tool.rs"), "fn main() {}").unwrap();
+        commit("new base");
+        let new_base = git.head_commit().unwrap();
+
+        let changes = apply_base_changes(&git, &store, &old_base, &new_base).unwrap();
+        assert_eq!(changes, BaseChanges { added: 1, removed: 1, updated: 2 });
+
+        let lookup = |path: &str| {
+            store.get_inode_by_path(path).unwrap().and_then(|id| store.get_inode(id).unwrap())
+        };
+
+        // Upstream addition, with its new parent directory
+        let tool = lookup("src/bin/tool.rs").unwrap();
+        assert_eq!(git.read_blob(tool.git_oid.as_ref().unwrap()).unwrap(), b"fn main() {}");
+        assert!(lookup("src/bin").unwrap().is_dir);
+
+        // Upstream deletion removes the file and its now-empty directory
+        assert!(lookup("docs/guide.md").is_none());
+        assert!(lookup("docs").is_none());
+
+        // Clean file follows the new blob
+        let config = lookup("config.toml").unwrap();
+        assert_eq!(git.read_blob(config.git_oid.as_ref().unwrap()).unwrap(), b"v2, longer");
+        assert_eq!(config.size, 10);
+
+        // Session edit stays dirty, compared against the new base
+        let edited = lookup("edited.rs").unwrap();
+        assert!(store.is_dirty("edited.rs").unwrap());
+        assert_eq!(git.read_blob(edited.git_oid.as_ref().unwrap()).unwrap(), b"upstream");
+    }
+
+    #[test]
+    fn test_merge_session_changes() {
+        use std::fs;
+
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let commit = |message: &str| {
+            std::process::Command::new("git").args(["add", "-A"]).current_dir(repo_path).output().unwrap();
+            std::process::Command::new("git").args(["commit", "-m", message]).current_dir(repo_path).output().unwrap();
+        };
+
+        fs::write(repo_path.join("clean.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(repo_path.join("clash.txt"), "base\n").unwrap();
+        fs::write(repo_path.join("image.bin"), b"\0base").unwrap();
+        fs::write(repo_path.join("gone.txt"), "base\n").unwrap();
+        commit("old base");
+        let git = GitRepo::open(repo_path).unwrap();
+        let old_base = git.head_commit().unwrap();
+
+        let session_dir = TempDir::new().unwrap();
+        let session_dir = session_dir.path();
+        fs::write(session_dir.join("clean.txt"), "one\ntwo\nthree!\n").unwrap();
+        fs::write(session_dir.join("clash.txt"), "mine\n").unwrap();
+        fs::write(session_dir.join("image.bin"), b"\0mine").unwrap();
+        fs::write(session_dir.join("gone.txt"), "mine\n").unwrap();
+
+        fs::write(repo_path.join("clean.txt"), "one!\ntwo\nthree\n").unwrap();
+        fs::write(repo_path.join("clash.txt"), "theirs\n").unwrap();
+        fs::write(repo_path.join("image.bin"), b"\0theirs").unwrap();
+        fs::remove_file(repo_path.join("gone.txt")).unwrap();
+        commit("new base");
+        let new_base = git.head_commit().unwrap();
+
+        let merge = merge_session_changes(&git, session_dir, "s1", "main", &old_base, &new_base).unwrap();
+        let outcome = merge.outcome();
+        assert_eq!(outcome.merged, vec!["clean.txt".to_string()]);
+        assert_eq!(outcome.conflicts, vec!["clash.txt".to_string()]);
+
+        // Binary files and files deleted upstream keep the session version
+        let mut unmerged = outcome.unmerged.clone();
+        unmerged.sort();
+        assert_eq!(unmerged, vec!["gone.txt".to_string(), "image.bin".to_string()]);
+        assert_eq!(fs::read(session_dir.join("image.bin")).unwrap(), b"\0mine");
+
+        // Nothing is written until the merge is
+        assert_eq!(fs::read(session_dir.join("clean.txt")).unwrap(), b"one\ntwo\nthree!\n");
+        merge.write(None, session_dir).unwrap();
+
+        // The upstream edit is kept alongside the session's
+        assert_eq!(fs::read(session_dir.join("clean.txt")).unwrap(), b"one!\ntwo\nthree!\n");
+        let clash = fs::read_to_string(session_dir.join("clash.txt")).unwrap();
+        assert!(clash.starts_with("<<<<<<< session s1\nmine\n=======\ntheirs\n>>>>>>> main "));
+
+        // A conflict is resolved once its markers are gone
+        assert_eq!(unresolved_conflicts(session_dir, &outcome.conflicts), outcome.conflicts);
+        fs::write(session_dir.join("clash.txt"), "both\n").unwrap();
+        assert!(unresolved_conflicts(session_dir, &outcome.conflicts).is_empty());
+
+        // A failed rebase puts the session's versions back
+        merge.restore(None, session_dir);
+        assert_eq!(fs::read(session_dir.join("clean.txt")).unwrap(), b"one\ntwo\nthree!\n");
+        assert_eq!(fs::read(session_dir.join("clash.txt")).unwrap(), b"mine\n");
+    }
 }
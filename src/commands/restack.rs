@@ -0,0 +1,197 @@
+//! `vibe restack [session]` command - Rebase stacked sessions onto their parents
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::commands::rebase::{blocking_conflicts, rebase};
+use crate::commands::spawn::SpawnInfo;
+use crate::git::GitRepo;
+
+/// Parent of every stacked session (child -> parent), read from the
+/// sessions' spawn info
+pub fn session_parents(repo_path: &Path) -> Result<BTreeMap<String, String>> {
+    let sessions_dir = repo_path.join(".vibe/sessions");
+    let mut parents = BTreeMap::new();
+    if !sessions_dir.exists() {
+        return Ok(parents);
+    }
+
+    for entry in std::fs::read_dir(&sessions_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(vibe_id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if let Ok(SpawnInfo { parent_session: Some(parent), .. }) = SpawnInfo::load(repo_path, &vibe_id) {
+            parents.insert(vibe_id, parent);
+        }
+    }
+
+    Ok(parents)
+}
+
+/// Sessions stacked on `root` (on any session when `None`), each listed
+/// after its parent
+pub fn stack_order(parents: &BTreeMap<String, String>, root: Option<&str>) -> Vec<String> {
+    let mut children: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (child, parent) in parents {
+        children.entry(parent.as_str()).or_default().push(child.as_str());
+    }
+
+    // Bottom of each stack: parents that aren't stacked themselves
+    let roots: Vec<&str> = match root {
+        Some(root) => vec![root],
+        None => children
+            .keys()
+            .copied()
+            .filter(|parent| !parents.contains_key(*parent))
+            .collect(),
+    };
+
+    let mut order = Vec::new();
+    let mut seen: BTreeSet<&str> = roots.iter().copied().collect();
+    let mut pending: Vec<&str> = roots.into_iter().rev().collect();
+    while let Some(session) = pending.pop() {
+        if parents.contains_key(session) && root != Some(session) {
+            order.push(session.to_string());
+        }
+        for child in children.get(session).into_iter().flatten().rev() {
+            if seen.insert(child) {
+                pending.push(child);
+            }
+        }
+    }
+
+    order
+}
+
+/// Rebase every session stacked on `root` (or every stacked session) onto
+/// its parent's latest `refs/vibes/<parent>`, parents first. Stops at the
+/// first session left with conflicts.
+pub async fn restack<P: AsRef<Path>>(repo_path: P, root: Option<&str>) -> Result<()> {
+    let repo_path = repo_path.as_ref();
+    let git = GitRepo::open(repo_path).context("Failed to open Git repository")?;
+
+    let parents = session_parents(repo_path)?;
+    let order = stack_order(&parents, root);
+    if order.is_empty() {
+        match root {
+            Some(root) => println!("No sessions are stacked on '{}'", root),
+            None => println!("No stacked sessions"),
+        }
+        return Ok(());
+    }
+
+    for session in &order {
+        let parent = &parents[session];
+        let parent_ref = format!("refs/vibes/{}", parent);
+        let parent_commit = git.get_ref(&parent_ref)?.with_context(|| {
+            format!("Session '{}' is stacked on '{}', which has no commits", session, parent)
+        })?;
+
+        println!("\n== Restacking '{}' onto '{}' ==", session, parent);
+        rebase(repo_path, session, false).await?;
+
+        let info = SpawnInfo::load(repo_path, session)?;
+        let session_dir = repo_path.join(".vibe/sessions").join(session);
+        let conflicts = blocking_conflicts(&session_dir, &info);
+        if !conflicts.is_empty() {
+            anyhow::bail!(
+                "Stopped restacking at '{}': resolve the conflicts in {} and run 'vibe restack' again",
+                session,
+                conflicts.join(", ")
+            );
+        }
+
+        // Dependents follow this session's ref, which only moves when it commits
+        let has_dependents = parents.values().any(|p| p == session);
+        if let Some(own_commit) = git.get_ref(&format!("refs/vibes/{}", session))? {
+            if has_dependents && !git.is_ancestor(&parent_commit, &own_commit)? {
+                println!(
+                    "  Note: '{}' has commits on the old '{}'. Commit it again to carry the update to its dependents.",
+                    session, parent
+                );
+            }
+        }
+    }
+
+    println!("\n✓ Restacked {} session(s)", order.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{init, promote, spawn};
+    use crate::db::MetadataStore;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn parents(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(c, p)| (c.to_string(), p.to_string())).collect()
+    }
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("README.md"), "# Test").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[test]
+    fn test_stack_order_puts_parents_first() {
+        let parents = parents(&[("c", "b"), ("b", "a"), ("d", "a"), ("y", "x")]);
+
+        assert_eq!(stack_order(&parents, None), vec!["b", "c", "d", "y"]);
+        assert_eq!(stack_order(&parents, Some("a")), vec!["b", "c", "d"]);
+        assert_eq!(stack_order(&parents, Some("b")), vec!["c"]);
+        assert!(stack_order(&parents, Some("c")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restack_follows_parent_commits() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        init::init(repo_path).await.unwrap();
+
+        let edit = |session: &str, db: &str, path: &str, content: &str| {
+            fs::write(repo_path.join(".vibe/sessions").join(session).join(path), content).unwrap();
+            MetadataStore::open(repo_path.join(db)).unwrap().mark_dirty(path).unwrap();
+        };
+
+        spawn::spawn_local(repo_path, "base").await.unwrap();
+        edit("base", ".vibe/metadata.db", "a.txt", "a");
+        promote::promote(repo_path, "base", None, Some("Add a"), true, false).await.unwrap();
+
+        spawn::spawn_local_from(repo_path, "top", Some("base")).await.unwrap();
+        edit("top", ".vibe/sessions/top/metadata.db", "b.txt", "b");
+        assert_eq!(session_parents(repo_path).unwrap(), parents(&[("top", "base")]));
+
+        edit("base", ".vibe/metadata.db", "c.txt", "c");
+        promote::promote(repo_path, "base", None, Some("Add c"), true, false).await.unwrap();
+        restack(repo_path, Some("base")).await.unwrap();
+
+        let git = GitRepo::open(repo_path).unwrap();
+        let base_tip = git.get_ref("refs/vibes/base").unwrap().unwrap();
+        let info = SpawnInfo::load(repo_path, "top").unwrap();
+        assert_eq!(info.spawn_commit.as_deref(), Some(base_tip.as_str()));
+
+        // The parent's new file comes from Git; the session's own edit stays
+        let top_dir = repo_path.join(".vibe/sessions/top");
+        let store = MetadataStore::open(top_dir.join("metadata.db")).unwrap();
+        assert!(store.get_inode_by_path("c.txt").unwrap().is_some());
+        assert_eq!(fs::read_to_string(top_dir.join("b.txt")).unwrap(), "b");
+    }
+}
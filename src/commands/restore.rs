@@ -4,6 +4,7 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use std::path::Path;
 
+use crate::commands::snapshot::record_checkpoint_base;
 use crate::cwd_validation;
 use crate::daemon_client::DaemonClient;
 use crate::daemon_ipc::DaemonResponse;
@@ -60,6 +61,7 @@ pub async fn restore<P: AsRef<Path>>(
         {
             copy_recursive(&session_dir, &backup_dir)?;
         }
+        record_checkpoint_base(repo_path, session, &backup_name)?;
 
         println!("  Backed up current state to snapshot '{}'", backup_name);
     }
@@ -148,6 +150,7 @@ pub async fn reset_hard<P: AsRef<Path>>(
         {
             copy_recursive(&session_dir, &backup_dir)?;
         }
+        record_checkpoint_base(repo_path, session, &backup_name)?;
     }
 
     // If daemon is running, try RPC reset (keeps NFS alive, no bricked shells)
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
+use crate::commands::spawn::SpawnInfo;
 use crate::cwd_validation;
 
 /// Create a zero-cost snapshot of a vibe session
@@ -17,6 +18,9 @@ pub async fn snapshot<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()>
         anyhow::bail!("Vibe session '{}' does not exist", vibe_id);
     }
 
+    // Copy what the mount has acknowledged, not just what reached the disk
+    crate::commands::sync_session(repo_path, vibe_id).await?;
+
     // Create snapshot directory
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
     let snapshot_name = format!("{}_snapshot_{}", vibe_id, timestamp);
@@ -43,6 +47,8 @@ pub async fn snapshot<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()>
         copy_recursive(&session_dir, &snapshot_dir)?;
     }
 
+    record_checkpoint_base(repo_path, vibe_id, &timestamp.to_string())?;
+
     println!("✓ Snapshot created successfully: {}", snapshot_name);
 
     Ok(())
@@ -67,6 +73,8 @@ pub async fn snapshot_with_name<P: AsRef<Path>>(repo_path: P, vibe_id: &str, nam
         anyhow::bail!("Session '{}' does not exist", vibe_id);
     }
 
+    crate::commands::sync_session(repo_path, vibe_id).await?;
+
     // Create snapshot with custom name
     let snapshot_dir_name = format!("{}_snapshot_{}", vibe_id, snapshot_name);
     let snapshot_dir = vibe_dir.join("sessions").join(&snapshot_dir_name);
@@ -93,11 +101,26 @@ pub async fn snapshot_with_name<P: AsRef<Path>>(repo_path: P, vibe_id: &str, nam
         copy_recursive(&session_dir, &snapshot_dir)?;
     }
 
+    record_checkpoint_base(repo_path, vibe_id, snapshot_name)?;
+
     println!("✓ Checkpoint saved: {}", snapshot_name);
 
     Ok(())
 }
 
+/// Record the base commit `session` has now as the base of its checkpoint
+/// `name`. Sessions without spawn info have no base to record.
+pub fn record_checkpoint_base(repo_path: &Path, session: &str, name: &str) -> Result<()> {
+    let Ok(mut spawn_info) = SpawnInfo::load(repo_path, session) else {
+        return Ok(());
+    };
+    if let Some(base) = spawn_info.spawn_commit.clone() {
+        spawn_info.checkpoints.insert(name.to_string(), base);
+        spawn_info.save(repo_path)?;
+    }
+    Ok(())
+}
+
 /// List available snapshots for a session
 pub async fn list_snapshots<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
     let repo_path = repo_path.as_ref();
@@ -136,6 +159,50 @@ pub async fn list_snapshots<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Resu
     Ok(())
 }
 
+/// Copy a session's delta files to a new session directory with the same
+/// CoW copies snapshots use. The metadata store and the artifact directory
+/// symlinks are left out: the store needs a consistent copy of its own, and
+/// each session links its own artifact storage.
+pub fn copy_session_delta(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst)
+        .with_context(|| format!("Failed to create {}", dst.display()))?;
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(src)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|name| name == "metadata.db") {
+            continue;
+        }
+        // Artifact links point at <artifacts_root>/<session>/<name>
+        let artifact_link = src.file_name().zip(path.file_name()).map(|(session, name)| Path::new(session).join(name));
+        if std::fs::read_link(&path).is_ok_and(|target| artifact_link.is_some_and(|link| target.is_absolute() && target.ends_with(link))) {
+            continue;
+        }
+        entries.push(path);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        for path in &entries {
+            copy_with_clonefile(path, &dst.join(path.file_name().unwrap_or_default()))?;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        copy_entries_with_reflink(&entries, dst)?;
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        for path in &entries {
+            copy_entry(path, &dst.join(path.file_name().unwrap_or_default()))?;
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 fn copy_with_clonefile(src: &Path, dst: &Path) -> Result<()> {
     use std::ffi::CString;
@@ -183,6 +250,45 @@ fn copy_with_reflink(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+/// `copy_with_reflink` for several entries into one directory
+#[cfg(target_os = "linux")]
+fn copy_entries_with_reflink(entries: &[std::path::PathBuf], dst_dir: &Path) -> Result<()> {
+    use std::process::Command;
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let output = Command::new("cp")
+        .arg("-r")
+        .arg("--reflink=always")
+        .arg("-t")
+        .arg(dst_dir)
+        .args(entries)
+        .output()
+        .context("Failed to execute cp with reflink")?;
+
+    if !output.status.success() {
+        eprintln!("Warning: reflink not supported on this filesystem, using regular copy");
+        for path in entries {
+            copy_entry(path, &dst_dir.join(path.file_name().unwrap_or_default()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy a file or directory without CoW
+#[cfg(not(target_os = "macos"))]
+fn copy_entry(src: &Path, dst: &Path) -> Result<()> {
+    if src.is_dir() {
+        copy_recursive(src, dst)
+    } else {
+        std::fs::copy(src, dst)?;
+        Ok(())
+    }
+}
+
 #[cfg(not(target_os = "macos"))]
 fn copy_recursive(src: &Path, dst: &Path) -> Result<()> {
     std::fs::create_dir_all(dst)?;
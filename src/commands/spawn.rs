@@ -1,28 +1,55 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::config::Config;
 use crate::cwd_validation;
 use crate::daemon_client::{ensure_daemon_running, DaemonClient};
 use crate::daemon_ipc::DaemonResponse;
+use crate::commands::rebase::move_store_to_commit;
+use crate::db::MetadataStore;
 use crate::git::GitRepo;
 use crate::platform;
 
-/// Directories that should be symlinked to local storage for performance
-/// and to avoid macOS NFS xattr issues with build tools.
-const ARTIFACT_DIRS: &[&str] = &[
-    "target",           // Rust/Cargo
-    "node_modules",     // Node.js/npm
-    ".venv",            // Python virtualenv
-    "__pycache__",      // Python cache
-    ".next",            // Next.js
-    ".nuxt",            // Nuxt.js
-    "dist",             // Common build output
-    "build",            // Common build output
-];
-
-/// Spawn a new vibe workspace
-pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
+/// Resolve `vibe new --from <rev>`: a branch, tag, commit or ref, or the name
+/// of another session (its `refs/vibes/<session>`). Returns the commit and,
+/// when `rev` names a ref, the full ref name.
+pub fn resolve_from(git: &GitRepo, rev: &str) -> Result<(String, Option<String>)> {
+    if let Ok(commit) = git.resolve_commit(rev) {
+        return Ok((commit, git.symbolic_full_name(rev)));
+    }
+
+    let session_ref = format!("refs/vibes/{}", rev);
+    match git.resolve_commit(&session_ref) {
+        Ok(commit) => Ok((commit, Some(session_ref))),
+        Err(_) => anyhow::bail!("Unknown revision '{}' (not a branch, tag, commit or session)", rev),
+    }
+}
+
+/// Session a `refs/vibes/<session>` base ref belongs to
+fn parent_session(base_ref: Option<&str>) -> Option<String> {
+    base_ref
+        .and_then(|r| r.strip_prefix("refs/vibes/"))
+        .map(str::to_string)
+}
+
+/// Commit a new session starts from: `from` resolved, or HEAD
+fn spawn_base(repo_path: &Path, vibe_id: &str, from: Option<&str>) -> Result<(Option<String>, Option<String>)> {
+    let git_repo = GitRepo::open(repo_path)?;
+    let Some(rev) = from else {
+        return Ok((git_repo.head_commit().ok(), None));
+    };
+
+    if repo_path.join(".vibe/sessions").join(format!("{}.json", vibe_id)).exists() {
+        anyhow::bail!("Session '{}' already exists; --from only applies to new sessions", vibe_id);
+    }
+    let (commit, base_ref) = resolve_from(&git_repo, rev)?;
+    Ok((Some(commit), base_ref))
+}
+
+/// Spawn a new vibe workspace, based on HEAD or on the `from` revision
+pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str, from: Option<&str>) -> Result<()> {
     // Validate that we're running from the correct directory
     let _validated_root = cwd_validation::validate_cwd().context("Cannot spawn vibe workspace")?;
 
@@ -36,9 +63,11 @@ pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
         anyhow::bail!("VibeFS not initialized. Run 'vibe init' first.");
     }
 
-    // Capture HEAD commit at spawn time
-    let git_repo = GitRepo::open(repo_path)?;
-    let spawn_commit = git_repo.head_commit().ok();
+    // Capture the base commit (HEAD unless --from) at spawn time
+    let (spawn_commit, base_ref) = spawn_base(repo_path, vibe_id, from)?;
+    if let (Some(rev), Some(commit)) = (from, &spawn_commit) {
+        println!("  From: {} ({})", rev, &commit[..12.min(commit.len())]);
+    }
 
     // Ensure daemon is running
     println!("  Ensuring daemon is running...");
@@ -46,8 +75,33 @@ pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
 
     // Connect to daemon and export session
     let mut client = DaemonClient::connect(repo_path).await?;
+    let spawn_info = SpawnInfo {
+        vibe_id: vibe_id.to_string(),
+        session_dir: vibe_dir.join("sessions").join(vibe_id),
+        mount_point: PathBuf::new(),
+        port: 0,
+        spawn_commit,
+        created_at: Some(Utc::now().to_rfc3339()),
+        conflicts: Vec::new(),
+        unmerged: Vec::new(),
+        checkpoints: BTreeMap::new(),
+        parent_session: parent_session(base_ref.as_deref()),
+        base_ref,
+    };
+    export_and_mount(repo_path, &mut client, spawn_info).await?;
+
+    println!("\n✓ Vibe workspace spawned successfully");
+
+    Ok(())
+}
+
+/// Export a session through the daemon, save its spawn info, and mount it.
+/// `spawn_info` describes the session's base; the daemon fills in its port
+/// and mount point.
+pub async fn export_and_mount(repo_path: &Path, client: &mut DaemonClient, mut spawn_info: SpawnInfo) -> Result<()> {
+    let vibe_dir = repo_path.join(".vibe");
 
-    match client.export_session(vibe_id).await? {
+    match client.export_session_at(&spawn_info.vibe_id, spawn_info.spawn_commit.as_deref()).await? {
         DaemonResponse::SessionExported {
             vibe_id,
             nfs_port,
@@ -58,28 +112,9 @@ pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
             println!("  Mount point: {}", mount_point);
 
             // Save spawn info for other commands
-            let spawn_info = SpawnInfo {
-                vibe_id: vibe_id.clone(),
-                session_dir: vibe_dir.join("sessions").join(&vibe_id),
-                mount_point: PathBuf::from(&mount_point),
-                port: nfs_port,
-                spawn_commit: spawn_commit.clone(),
-                created_at: Some(Utc::now().to_rfc3339()),
-            };
-
-            let info_path = vibe_dir.join("sessions").join(format!("{}.json", vibe_id));
-            let info_json = serde_json::to_string_pretty(&spawn_info)?;
-            std::fs::write(&info_path, info_json)?;
-
-            // Create symlinks for build artifact directories
-            // These point to local storage to avoid NFS xattr issues with build tools
-            let session_dir = vibe_dir.join("sessions").join(&vibe_id);
-            if let Err(e) = setup_artifact_symlinks(&session_dir, &vibe_id) {
-                eprintln!("  Warning: Failed to setup artifact symlinks: {}", e);
-                // Non-fatal - continue with spawn
-            } else {
-                println!("  ✓ Build artifact directories linked to local storage");
-            }
+            spawn_info.mount_point = PathBuf::from(&mount_point);
+            spawn_info.port = nfs_port;
+            spawn_info.save(repo_path)?;
 
             // Try to mount NFS (works automatically on macOS, requires manual step on Linux)
             println!("\n  NFS server running on port {}", nfs_port);
@@ -100,8 +135,6 @@ pub async fn spawn<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
                     println!("    {}", vibe_dir.join("sessions").join(&vibe_id).display());
                 }
             }
-
-            println!("\n✓ Vibe workspace spawned successfully");
         }
         DaemonResponse::Error { message } => {
             anyhow::bail!("Daemon error: {}", message);
@@ -139,6 +172,25 @@ pub struct SpawnInfo {
     /// Timestamp when session was created
     #[serde(default)]
     pub created_at: Option<String>,
+    /// Files left with conflict markers by `vibe rebase`, until resolved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+    /// Files `vibe rebase` could not merge (binary files, or files deleted
+    /// upstream), until `vibe resolve` clears them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmerged: Vec<String>,
+    /// Base commit of each checkpoint, by checkpoint name, as it was when
+    /// the checkpoint was saved. `vibe diff --against <checkpoint>` compares
+    /// against that base, not the one the session has since been rebased to.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checkpoints: BTreeMap<String, String>,
+    /// Ref given to `vibe new --from` (a branch, tag or `refs/vibes/<session>`).
+    /// `vibe rebase` follows it instead of HEAD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_ref: Option<String>,
+    /// Session this one is stacked on, when `base_ref` is its `refs/vibes/<session>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session: Option<String>,
 }
 
 impl SpawnInfo {
@@ -152,41 +204,36 @@ impl SpawnInfo {
         let info: SpawnInfo = serde_json::from_str(&json)?;
         Ok(info)
     }
-}
-
-/// Set up symlinks for build artifact directories.
-/// These directories are symlinked to local storage (/tmp/vibe-artifacts/<session>/)
-/// to avoid macOS NFS xattr issues and improve build performance.
-fn setup_artifact_symlinks(session_dir: &Path, vibe_id: &str) -> Result<()> {
-    // Base directory for local artifacts
-    let artifacts_base = PathBuf::from("/tmp/vibe-artifacts").join(vibe_id);
 
-    for dir_name in ARTIFACT_DIRS {
-        let local_path = artifacts_base.join(dir_name);
-        let symlink_path = session_dir.join(dir_name);
-
-        // Skip if symlink already exists
-        if symlink_path.exists() || symlink_path.is_symlink() {
-            continue;
+    /// Commit `vibe rebase` moves the session onto: the tip of `base_ref`,
+    /// or HEAD for sessions started from HEAD or a plain commit
+    pub fn upstream_commit(&self, git: &GitRepo) -> Result<String> {
+        match &self.base_ref {
+            Some(base_ref) => git.resolve_commit(base_ref),
+            None => git.head_commit(),
         }
+    }
 
-        // Create the local directory
-        std::fs::create_dir_all(&local_path)
-            .with_context(|| format!("Failed to create local artifact dir: {}", local_path.display()))?;
-
-        // Create symlink in session directory
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(&local_path, &symlink_path)
-            .with_context(|| format!("Failed to create symlink: {} -> {}", symlink_path.display(), local_path.display()))?;
+    /// Name of what `upstream_commit` follows, for messages
+    pub fn upstream_name(&self) -> &str {
+        self.base_ref.as_deref().unwrap_or("HEAD")
     }
 
-    Ok(())
+    /// Write spawn info back to `.vibe/sessions/<id>.json`
+    pub fn save(&self, repo_path: &Path) -> Result<()> {
+        let info_path = repo_path
+            .join(".vibe/sessions")
+            .join(format!("{}.json", self.vibe_id));
+        std::fs::write(&info_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", info_path.display()))?;
+        Ok(())
+    }
 }
 
 /// Clean up artifact symlinks and their local directories.
 /// Called when closing a session.
-pub fn cleanup_artifact_symlinks(vibe_id: &str) -> Result<()> {
-    let artifacts_base = PathBuf::from("/tmp/vibe-artifacts").join(vibe_id);
+pub fn cleanup_artifact_symlinks(repo_path: &Path, vibe_id: &str) -> Result<()> {
+    let artifacts_base = Config::load(repo_path)?.artifacts_dir(vibe_id);
 
     if artifacts_base.exists() {
         std::fs::remove_dir_all(&artifacts_base)
@@ -199,6 +246,12 @@ pub fn cleanup_artifact_symlinks(vibe_id: &str) -> Result<()> {
 /// Local spawn without daemon (for testing and simple use cases)
 /// This creates the session directory structure without NFS mounting
 pub async fn spawn_local<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<()> {
+    spawn_local_from(repo_path, vibe_id, None).await
+}
+
+/// `spawn_local` based on the `from` revision. The session gets its own
+/// metadata store, moved from the init-time tree to that revision's.
+pub async fn spawn_local_from<P: AsRef<Path>>(repo_path: P, vibe_id: &str, from: Option<&str>) -> Result<()> {
     let repo_path = repo_path.as_ref();
     let vibe_dir = repo_path.join(".vibe");
 
@@ -207,15 +260,20 @@ pub async fn spawn_local<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<
         anyhow::bail!("VibeFS not initialized. Run 'vibe init' first.");
     }
 
-    // Capture HEAD commit at spawn time
-    let git_repo = GitRepo::open(repo_path)?;
-    let spawn_commit = git_repo.head_commit().ok();
+    // Capture the base commit (HEAD unless --from) at spawn time
+    let (spawn_commit, base_ref) = spawn_base(repo_path, vibe_id, from)?;
 
     // Create session directory
     let session_dir = vibe_dir.join("sessions").join(vibe_id);
     std::fs::create_dir_all(&session_dir)
         .context("Failed to create session directory")?;
 
+    if let (Some(_), Some(commit)) = (from, &spawn_commit) {
+        let base_store = MetadataStore::open(vibe_dir.join("metadata.db"))?;
+        let store = base_store.clone_to(session_dir.join("metadata.db"))?;
+        move_store_to_commit(&GitRepo::open(repo_path)?, &store, commit)?;
+    }
+
     // Create mount point (for compatibility)
     let mount_point = PathBuf::from("/tmp/vibe").join(vibe_id);
     std::fs::create_dir_all(&mount_point)
@@ -229,6 +287,11 @@ pub async fn spawn_local<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<
         port: 0,
         spawn_commit,
         created_at: Some(Utc::now().to_rfc3339()),
+        conflicts: Vec::new(),
+        unmerged: Vec::new(),
+        checkpoints: BTreeMap::new(),
+        parent_session: parent_session(base_ref.as_deref()),
+        base_ref,
     };
 
     let info_path = vibe_dir.join("sessions").join(format!("{}.json", vibe_id));
@@ -242,7 +305,6 @@ pub async fn spawn_local<P: AsRef<Path>>(repo_path: P, vibe_id: &str) -> Result<
 mod tests {
     use super::*;
 
-    #[allow(dead_code)]
     fn setup_test_repo() -> tempfile::TempDir {
         use std::fs;
         use tempfile::TempDir;
@@ -296,6 +358,11 @@ mod tests {
             port: 12345,
             spawn_commit: Some("abc123def456".to_string()),
             created_at: Some("2026-01-13T10:00:00Z".to_string()),
+            conflicts: Vec::new(),
+            unmerged: Vec::new(),
+            checkpoints: BTreeMap::new(),
+            base_ref: None,
+            parent_session: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -322,4 +389,59 @@ mod tests {
         assert_eq!(parsed.spawn_commit, None);
         assert_eq!(parsed.created_at, None);
     }
+
+    #[tokio::test]
+    async fn test_spawn_from_revision() {
+        use crate::commands::{init, promote};
+        use std::fs;
+        use std::process::Command;
+
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        init::init(repo_path).await.unwrap();
+
+        // A release branch that HEAD doesn't have
+        git(&["checkout", "-q", "-b", "release"]);
+        fs::write(repo_path.join("release.txt"), "1.0").unwrap();
+        git(&["add", "release.txt"]);
+        git(&["commit", "-q", "-m", "Release"]);
+        git(&["checkout", "-q", "-"]);
+        let repo = GitRepo::open(repo_path).unwrap();
+        let release = repo.resolve_commit("release").unwrap();
+
+        spawn_local_from(repo_path, "rel", Some("release")).await.unwrap();
+        let info = SpawnInfo::load(repo_path, "rel").unwrap();
+        assert_eq!(info.spawn_commit.as_deref(), Some(release.as_str()));
+        assert_eq!(info.base_ref.as_deref(), Some("refs/heads/release"));
+        assert_eq!(info.upstream_commit(&repo).unwrap(), release);
+
+        // The session's inode map is the release tree
+        let session_dir = repo_path.join(".vibe/sessions/rel");
+        {
+            let store = MetadataStore::open(session_dir.join("metadata.db")).unwrap();
+            assert!(store.get_inode_by_path("release.txt").unwrap().is_some());
+            assert_eq!(store.get_inode(1).unwrap().unwrap().git_oid.as_deref(), Some(release.as_str()));
+
+            fs::write(session_dir.join("notes.txt"), "notes").unwrap();
+            store.mark_dirty("notes.txt").unwrap();
+        }
+
+        // Commits build on the release commit
+        promote::promote(repo_path, "rel", None, None, true, false).await.unwrap();
+        let commit = repo.get_ref("refs/vibes/rel").unwrap().unwrap();
+        assert_eq!(repo.commit_summary(&commit).unwrap().parents, vec![release]);
+
+        // Another session can stack on it by name
+        spawn_local_from(repo_path, "stacked", Some("rel")).await.unwrap();
+        let stacked = SpawnInfo::load(repo_path, "stacked").unwrap();
+        assert_eq!(stacked.spawn_commit.as_deref(), Some(commit.as_str()));
+        assert_eq!(stacked.base_ref.as_deref(), Some("refs/vibes/rel"));
+        assert_eq!(stacked.parent_session.as_deref(), Some("rel"));
+        assert_eq!(info.parent_session, None);
+
+        assert!(spawn_local_from(repo_path, "bad", Some("no-such-rev")).await.is_err());
+    }
 }
@@ -2,15 +2,16 @@
 
 use anyhow::Result;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 
+use crate::commands::rebase::blocking_conflicts;
+use crate::commands::restack::session_parents;
 use crate::commands::spawn::SpawnInfo;
 use crate::daemon_client::DaemonClient;
 use crate::daemon_ipc::{self, DaemonResponse};
-use crate::db::MetadataStore;
 use crate::git::GitRepo;
-use crate::gitignore::PromoteFilter;
+use crate::session_state::promotable_dirty_paths;
 
 /// Show status - overview, per-session details, or conflicts
 pub async fn status<P: AsRef<Path>>(
@@ -60,6 +61,7 @@ async fn show_overview<P: AsRef<Path>>(repo_path: P, json_output: bool) -> Resul
         head_commit: head_commit.clone(),
         active_sessions: Vec::new(),
         offline_sessions: Vec::new(),
+        stacked_on: session_parents(repo_path).unwrap_or_default(),
     };
 
     // Check daemon status
@@ -82,36 +84,31 @@ async fn show_overview<P: AsRef<Path>>(repo_path: P, json_output: bool) -> Resul
             // Get active sessions
             if let Ok(DaemonResponse::Sessions { sessions }) = client.list_sessions().await {
                 for sess in sessions {
-                    // Get dirty count from per-session metadata store (filtered by .gitignore)
-                    let dirty_count = {
-                        let session_db = vibe_dir.join("sessions").join(&sess.vibe_id).join("metadata.db");
-                        let session_dir = vibe_dir.join("sessions").join(&sess.vibe_id);
-                        let db_path = if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") };
-                        if let Ok(store) = MetadataStore::open_readonly(&db_path) {
-                            if let Ok(paths) = store.get_dirty_paths() {
-                                if let Ok(filter) = PromoteFilter::new(repo_path, Some(&session_dir)) {
-                                    filter.filter_promotable(&paths).len()
-                                } else {
-                                    paths.len()
-                                }
-                            } else {
-                                0
-                            }
-                        } else {
-                            0
-                        }
-                    };
+                    // Dirty count, filtered by .gitignore
+                    let dirty_count = promotable_dirty_paths(repo_path, &sess.vibe_id)
+                        .await
+                        .map_or(0, |paths| paths.len());
 
                     let spawn_info = SpawnInfo::load(repo_path, &sess.vibe_id).ok();
                     let base_commit = spawn_info.as_ref().and_then(|s| s.spawn_commit.clone());
-                    let behind_head = match (&base_commit, &head_commit) {
+                    // Sessions started with --from follow their base ref, not HEAD
+                    let upstream = match spawn_info.as_ref().filter(|s| s.base_ref.is_some()) {
+                        Some(info) => GitRepo::open(repo_path).ok().and_then(|git| info.upstream_commit(&git).ok()),
+                        None => head_commit.clone(),
+                    };
+                    let behind_head = match (&base_commit, &upstream) {
                         (Some(base), Some(head)) => Some(base != head),
                         _ => None,
                     };
+                    let conflict_count = spawn_info.as_ref().map_or(0, |s| {
+                        blocking_conflicts(&vibe_dir.join("sessions").join(&sess.vibe_id), s).len()
+                    });
 
                     output.active_sessions.push(SessionSummary {
                         id: sess.vibe_id.clone(),
+                        parent: spawn_info.as_ref().and_then(|s| s.parent_session.clone()),
                         dirty_count,
+                        conflict_count,
                         uptime_secs: sess.uptime_secs,
                         mount_point: sess.mount_point,
                         base_commit,
@@ -163,38 +160,22 @@ async fn show_session_details<P: AsRef<Path>>(
     // Load session info
     let spawn_info = SpawnInfo::load(repo_path, session_id)?;
 
-    // Get current HEAD
+    // Get current HEAD (or the tip of the session's base ref)
     let head_commit = GitRepo::open(repo_path)
         .ok()
-        .and_then(|git| git.head_commit().ok());
+        .and_then(|git| spawn_info.upstream_commit(&git).ok());
 
-    // Check if behind HEAD
+    // Check if behind HEAD (or the ref the session was started from)
     let behind_head = match (&spawn_info.spawn_commit, &head_commit) {
         (Some(base), Some(head)) => Some(base != head),
         _ => None,
     };
 
-    // Get dirty files from per-session store (fallback to base), filtered by .gitignore
-    let db_path = {
-        let session_db = vibe_dir.join("sessions").join(session_id).join("metadata.db");
-        if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") }
-    };
-    let dirty_files = if db_path.exists() {
-        match MetadataStore::open_readonly(&db_path) {
-            Ok(store) => {
-                let paths = store.get_dirty_paths()?;
-                let session_dir = vibe_dir.join("sessions").join(session_id);
-                if let Ok(filter) = PromoteFilter::new(repo_path, Some(&session_dir)) {
-                    filter.filter_promotable(&paths).into_iter().cloned().collect()
-                } else {
-                    paths
-                }
-            }
-            Err(_) => Vec::new(),
-        }
-    } else {
-        Vec::new()
-    };
+    // Dirty files, filtered by .gitignore
+    let dirty_files = promotable_dirty_paths(repo_path, session_id).await.unwrap_or_default();
+
+    // Files left with conflict markers, or unmerged, by a rebase
+    let conflicts = blocking_conflicts(&vibe_dir.join("sessions").join(session_id), &spawn_info);
 
     // Find snapshots
     let snapshots = find_snapshots(&vibe_dir.join("sessions"), session_id)?;
@@ -222,11 +203,20 @@ async fn show_session_details<P: AsRef<Path>>(
         mount_point: spawn_info.mount_point.to_string_lossy().to_string(),
         uptime_secs,
         spawn_commit: spawn_info.spawn_commit.clone(),
+        base_ref: spawn_info.base_ref.clone(),
+        parent: spawn_info.parent_session.clone(),
+        stacked: session_parents(repo_path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, parent)| parent == session_id)
+            .map(|(child, _)| child)
+            .collect(),
         head_commit,
         behind_head,
         created_at: spawn_info.created_at.clone(),
         dirty_count: dirty_files.len(),
         dirty_files: dirty_files.clone(),
+        conflicts,
         snapshots,
     };
 
@@ -257,26 +247,16 @@ async fn show_conflicts_status<P: AsRef<Path>>(repo_path: P, json_output: bool)
         Vec::new()
     };
 
-    // Collect dirty paths per session from per-session metadata stores (filtered by .gitignore)
+    // Collect dirty paths per session (filtered by .gitignore)
     let mut file_sessions: HashMap<String, Vec<String>> = HashMap::new();
 
     for session in &sessions {
-        let session_db = sessions_dir.join(session).join("metadata.db");
-        let session_dir = sessions_dir.join(session);
-        let db_path = if session_db.exists() { session_db } else { vibe_dir.join("metadata.db") };
-        if let Ok(store) = MetadataStore::open_readonly(&db_path) {
-            if let Ok(dirty_paths) = store.get_dirty_paths() {
-                let filtered = if let Ok(filter) = PromoteFilter::new(repo_path, Some(&session_dir)) {
-                    filter.filter_promotable(&dirty_paths).into_iter().cloned().collect::<Vec<_>>()
-                } else {
-                    dirty_paths
-                };
-                for path in filtered {
-                    file_sessions
-                        .entry(path)
-                        .or_default()
-                        .push(session.clone());
-                }
+        if let Ok(paths) = promotable_dirty_paths(repo_path, session).await {
+            for path in paths {
+                file_sessions
+                    .entry(path)
+                    .or_default()
+                    .push(session.clone());
             }
         }
     }
@@ -338,12 +318,18 @@ struct StatusOverview {
     head_commit: Option<String>,
     active_sessions: Vec<SessionSummary>,
     offline_sessions: Vec<String>,
+    /// Stacked sessions and the session each is stacked on
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    stacked_on: BTreeMap<String, String>,
 }
 
 #[derive(Serialize)]
 struct SessionSummary {
     id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
     dirty_count: usize,
+    conflict_count: usize,
     uptime_secs: u64,
     mount_point: String,
     base_commit: Option<String>,
@@ -357,12 +343,19 @@ struct SessionDetails {
     mount_point: String,
     uptime_secs: Option<u64>,
     spawn_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stacked: Vec<String>,
     head_commit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     behind_head: Option<bool>,
     created_at: Option<String>,
     dirty_count: usize,
     dirty_files: Vec<String>,
+    conflicts: Vec<String>,
     snapshots: Vec<String>,
 }
 
@@ -404,13 +397,19 @@ fn print_overview(output: &StatusOverview, repo_path: &Path) {
                 let base_short = sess.base_commit.as_ref()
                     .map(|c| &c[..7.min(c.len())])
                     .unwrap_or("unknown");
-                let status = match sess.behind_head {
-                    Some(true) => " ⚠ BEHIND",
-                    Some(false) => "",
-                    None => "",
+                let mut status = match sess.behind_head {
+                    Some(true) => " ⚠ BEHIND".to_string(),
+                    Some(false) => String::new(),
+                    None => String::new(),
                 };
+                if sess.conflict_count > 0 {
+                    status.push_str(&format!(" ⚠ {} CONFLICTED", sess.conflict_count));
+                }
+                let parent = sess.parent.as_ref()
+                    .map(|p| format!(" on:{}", p))
+                    .unwrap_or_default();
                 println!(
-                    "  {} [{}] base:{}{} → {}",
+                    "  {} [{}] base:{}{}{} → {}",
                     sess.id,
                     if sess.dirty_count > 0 {
                         format!("{} dirty", sess.dirty_count)
@@ -418,6 +417,7 @@ fn print_overview(output: &StatusOverview, repo_path: &Path) {
                         "clean".to_string()
                     },
                     base_short,
+                    parent,
                     status,
                     sess.mount_point
                 );
@@ -442,6 +442,15 @@ fn print_overview(output: &StatusOverview, repo_path: &Path) {
         }
     }
 
+    if !output.stacked_on.is_empty() {
+        println!("\nSTACKS:");
+        let parents: BTreeSet<&str> = output.stacked_on.values().map(String::as_str).collect();
+        for root in parents.iter().filter(|p| !output.stacked_on.contains_key(**p)) {
+            println!("  {}", root);
+            print_stack(&output.stacked_on, root, "  ");
+        }
+    }
+
     println!("================================================================================");
 }
 
@@ -461,6 +470,15 @@ fn print_session_details(output: &SessionDetails) {
         };
         println!("  Base:      {}{}", &commit[..12.min(commit.len())], status);
     }
+    if let Some(ref base_ref) = output.base_ref {
+        println!("  From:      {}", base_ref);
+    }
+    if let Some(ref parent) = output.parent {
+        println!("  Stacked on: {}", parent);
+    }
+    if !output.stacked.is_empty() {
+        println!("  Dependents: {} (run 'vibe restack {}' after committing)", output.stacked.join(", "), output.id);
+    }
     if let Some(ref head) = output.head_commit {
         if output.behind_head == Some(true) {
             println!("  HEAD:      {}", &head[..12.min(head.len())]);
@@ -469,6 +487,9 @@ fn print_session_details(output: &SessionDetails) {
     }
 
     println!("  Dirty:     {} files", output.dirty_count);
+    if !output.conflicts.is_empty() {
+        println!("  Conflicts: {} files", output.conflicts.len());
+    }
     if !output.snapshots.is_empty() {
         println!("  Snapshots: {}", output.snapshots.join(", "));
     }
@@ -476,9 +497,28 @@ fn print_session_details(output: &SessionDetails) {
     if !output.dirty_files.is_empty() {
         println!("\nDIRTY FILES:");
         for file in &output.dirty_files {
-            println!("  M {}", file);
+            let status = if output.conflicts.contains(file) { "U" } else { "M" };
+            println!("  {} {}", status, file);
         }
     }
+
+    if !output.conflicts.is_empty() {
+        println!("\n⚠ Unresolved rebase conflicts. Remove the conflict markers before 'vibe commit -s {}'.", output.id);
+    }
+}
+
+/// Print the sessions stacked on `parent` as a tree
+fn print_stack(stacked_on: &BTreeMap<String, String>, parent: &str, indent: &str) {
+    let children: Vec<&String> = stacked_on.iter()
+        .filter(|(_, p)| p.as_str() == parent)
+        .map(|(child, _)| child)
+        .collect();
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        println!("{}{} {}", indent, if last { "└─" } else { "├─" }, child);
+        let nested = format!("{}{}", indent, if last { "   " } else { "│  " });
+        print_stack(stacked_on, child, &nested);
+    }
 }
 
 fn format_uptime(secs: u64) -> String {
@@ -0,0 +1,492 @@
+//! `vibe transplant` command - Copy files or hunks from one session into another
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::commands::diff::SessionTree;
+use crate::commands::rebase::ensure_dir_inodes;
+use crate::commands::spawn::SpawnInfo;
+use crate::daemon_client::DaemonClient;
+use crate::daemon_ipc::DaemonResponse;
+use crate::db::{InodeMetadata, MetadataStore};
+use crate::git::{GitRepo, Hunk};
+
+/// One file to write into the target session. Contents travel in the
+/// request rather than as blobs, so a transplant adds no objects to the
+/// repository; `None` means the file is absent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransplantFile {
+    pub path: String,
+    /// The file in the source session's base: the merge base
+    pub base: Option<Vec<u8>>,
+    /// The selected source content
+    pub content: Option<Vec<u8>>,
+}
+
+/// What `apply_transplant` did to each file
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransplantOutcome {
+    /// Taken as is: the target had not changed the file
+    pub copied: Vec<String>,
+    /// Merged cleanly with the target's own changes
+    pub merged: Vec<String>,
+    /// Written with conflict markers
+    pub conflicts: Vec<String>,
+    /// Left alone because the target already has the same content
+    pub skipped: Vec<String>,
+    /// Not transplanted; the target keeps its version
+    pub failed: Vec<FailedFile>,
+}
+
+/// A file `apply_transplant` could not write, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+impl TransplantOutcome {
+    fn fail(&mut self, path: &str, reason: impl Into<String>) {
+        self.failed.push(FailedFile { path: path.to_string(), reason: reason.into() });
+    }
+}
+
+/// Write transplanted files into a session's delta and store.
+///
+/// The target's current file is "ours", the source's base is the merge base
+/// and the selected source content is "theirs". Where the target still has
+/// the base version the source content is taken as is; otherwise the two
+/// are three-way merged, with conflict markers where both changed the same
+/// lines. Written paths are marked dirty. Files that can't be merged (not
+/// regular files in the target, binary, or deleted on one side and changed
+/// on the other) are reported as failed and left alone.
+pub fn apply_transplant(
+    git: &GitRepo,
+    store: &MetadataStore,
+    session_dir: &Path,
+    source: &str,
+    target: &str,
+    files: &[TransplantFile],
+) -> Result<TransplantOutcome> {
+    let mut outcome = TransplantOutcome::default();
+    let labels = [format!("session {}", target), "base".to_string(), format!("session {}", source)];
+
+    for file in files {
+        let path = file.path.as_str();
+        let session_file = session_dir.join(path);
+        let existing = match store.get_inode_by_path(path)? {
+            Some(id) => store.get_inode(id)?.map(|metadata| (id, metadata)),
+            None => None,
+        };
+        if existing.as_ref().is_some_and(|(_, m)| m.is_dir || m.is_symlink()) {
+            outcome.fail(path, format!("not a regular file in session '{}'", target));
+            continue;
+        }
+
+        let ours = match &existing {
+            Some(_) if store.is_dirty(path)? => Some(
+                std::fs::read(&session_file)
+                    .with_context(|| format!("Failed to read {}", session_file.display()))?,
+            ),
+            Some((_, metadata)) => match &metadata.git_oid {
+                Some(oid) => Some(git.read_blob(oid)?),
+                None => std::fs::read(&session_file).ok(),
+            },
+            None => None,
+        };
+        let base = file.base.clone();
+        let theirs = file.content.clone();
+
+        let (result, conflicted) = if ours == theirs {
+            outcome.skipped.push(file.path.clone());
+            continue;
+        } else if ours == base {
+            (theirs, false)
+        } else {
+            let (Some(ours), Some(theirs)) = (&ours, &theirs) else {
+                outcome.fail(path, "deleted on one side and changed on the other");
+                continue;
+            };
+            let base = base.clone().unwrap_or_default();
+            match git.merge_file(ours, &base, theirs, [&labels[0], &labels[1], &labels[2]]) {
+                Ok((merged, conflicted)) => (Some(merged), conflicted),
+                Err(e) => {
+                    outcome.fail(path, format!("cannot merge: {}", e));
+                    continue;
+                }
+            }
+        };
+
+        match result {
+            Some(content) => write_file(store, &session_file, path, existing, &content)?,
+            None => remove_file(store, &session_file, existing)?,
+        }
+
+        if conflicted {
+            outcome.conflicts.push(file.path.clone());
+        } else if ours == base {
+            outcome.copied.push(file.path.clone());
+        } else {
+            outcome.merged.push(file.path.clone());
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Write `content` as the session's version of `path`
+fn write_file(
+    store: &MetadataStore,
+    session_file: &Path,
+    path: &str,
+    existing: Option<(u64, InodeMetadata)>,
+    content: &[u8],
+) -> Result<()> {
+    if let Some(parent) = session_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(session_file, content)
+        .with_context(|| format!("Failed to write {}", session_file.display()))?;
+
+    // A deleted base file comes back as a modified one
+    let (id, mut metadata) = match (existing, store.get_whiteout(path)?) {
+        (Some(existing), _) => existing,
+        (None, Some(whiteout)) => {
+            store.clear_whiteout(path)?;
+            (whiteout.inode_id, whiteout.metadata)
+        }
+        (None, None) => {
+            ensure_dir_inodes(store, path)?;
+            (store.next_inode_id()?, InodeMetadata {
+                path: path.to_string(),
+                ..Default::default()
+            })
+        }
+    };
+    metadata.size = content.len() as u64;
+    metadata.touch_modified();
+    store.put_inode(id, &metadata)?;
+    store.mark_dirty(path)
+}
+
+/// Delete the session's version of a file, leaving a whiteout for base files
+fn remove_file(store: &MetadataStore, session_file: &Path, existing: Option<(u64, InodeMetadata)>) -> Result<()> {
+    let Some((id, metadata)) = existing else {
+        return Ok(());
+    };
+    store.delete_inode(id)?;
+    if metadata.git_oid.is_some() {
+        store.put_whiteout(id, &metadata)?;
+    }
+    store.clear_dirty_path(&metadata.path)?;
+    if session_file.is_file() {
+        std::fs::remove_file(session_file)?;
+    }
+    Ok(())
+}
+
+/// Answer to a transplant prompt
+enum Choice {
+    Yes,
+    No,
+    Quit,
+}
+
+/// Ask a y/n/q question on the terminal
+fn ask(question: &str) -> Result<Choice> {
+    loop {
+        print!("{} [y,n,q]? ", question);
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(Choice::Quit);
+        }
+        match answer.trim() {
+            "y" | "Y" => return Ok(Choice::Yes),
+            "n" | "N" => return Ok(Choice::No),
+            "q" | "Q" => return Ok(Choice::Quit),
+            _ => println!("y - transplant it, n - skip it, q - stop here"),
+        }
+    }
+}
+
+fn print_hunk(path: &str, hunk: &Hunk) {
+    println!("\n{} @@ -{} +{} @@", path, hunk.old_start, hunk.new_start);
+    for line in &hunk.removed {
+        print!("\x1b[31m-{}\x1b[0m", String::from_utf8_lossy(line));
+    }
+    for line in &hunk.added {
+        print!("\x1b[32m+{}\x1b[0m", String::from_utf8_lossy(line));
+    }
+    if hunk.added.last().or(hunk.removed.last()).is_some_and(|l| !l.ends_with(b"\n")) {
+        println!();
+    }
+}
+
+/// Let the user pick hunks of the change from `base` to `new`. Returns the
+/// base with the chosen hunks applied (`None` if none were chosen), and
+/// whether the user quit.
+fn choose_hunks(git: &GitRepo, path: &str, base: &[u8], new: &[u8]) -> Result<(Option<Vec<u8>>, bool)> {
+    let hunks = match git.diff_hunks(base, new) {
+        Ok(hunks) => hunks,
+        Err(e) => {
+            println!("\n{}: {}", path, e);
+            return match ask("Transplant the whole file")? {
+                Choice::Yes => Ok((Some(new.to_vec()), false)),
+                Choice::No => Ok((None, false)),
+                Choice::Quit => Ok((None, true)),
+            };
+        }
+    };
+
+    let mut chosen = Vec::new();
+    let mut quit = false;
+    for hunk in &hunks {
+        print_hunk(path, hunk);
+        match ask("Transplant this hunk")? {
+            Choice::Yes => chosen.push(hunk),
+            Choice::No => {}
+            Choice::Quit => {
+                quit = true;
+                break;
+            }
+        }
+    }
+
+    let content = (!chosen.is_empty()).then(|| Hunk::apply(base, &chosen));
+    Ok((content, quit))
+}
+
+/// Whether `path` is one of `selected` or inside one of them
+fn is_selected(path: &str, selected: &[String]) -> bool {
+    selected.is_empty()
+        || selected.iter().any(|s| {
+            let s = s.trim_end_matches('/');
+            path == s || path.strip_prefix(s).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// Pick the source changes to transplant: whole files, or with `hunks`,
+/// the hunks chosen interactively.
+pub fn select_changes(
+    git: &GitRepo,
+    source: &SessionTree,
+    paths: &[String],
+    hunks: bool,
+) -> Result<Vec<TransplantFile>> {
+    for path in paths {
+        if !source.changed_paths().iter().any(|p| is_selected(p, std::slice::from_ref(path))) {
+            eprintln!("  Warning: {} has no changes in session '{}'", path, source.session);
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in source.changed_paths().iter().filter(|p| is_selected(p, paths)) {
+        let base = git.read_file_at_commit(&source.base_commit, path)?;
+        let content = source.read(git, path)?;
+        if base == content {
+            continue;
+        }
+
+        if hunks {
+            let (selected, quit) = match &content {
+                Some(new) => {
+                    let (chosen, quit) = choose_hunks(git, path, base.as_deref().unwrap_or_default(), new)?;
+                    (chosen.map(Some), quit)
+                }
+                // A deletion has no hunks: it is all or nothing
+                None => match ask(&format!("\nDelete {}", path))? {
+                    Choice::Yes => (Some(None), false),
+                    Choice::No => (None, false),
+                    Choice::Quit => (None, true),
+                },
+            };
+            if let Some(selected) = selected {
+                files.push(TransplantFile { path: path.clone(), base, content: selected });
+            }
+            if quit {
+                break;
+            }
+            continue;
+        }
+
+        files.push(TransplantFile { path: path.clone(), base, content });
+    }
+
+    Ok(files)
+}
+
+/// Copy files (or, with `hunks`, selected hunks) changed in session `from`
+/// into session `to`. A live target is updated through the daemon.
+pub async fn transplant<P: AsRef<Path>>(
+    repo_path: P,
+    from: &str,
+    to: &str,
+    paths: &[String],
+    hunks: bool,
+) -> Result<()> {
+    let repo_path = repo_path.as_ref();
+    let git = GitRepo::open(repo_path).context("Failed to open Git repository")?;
+    if from == to {
+        anyhow::bail!("Cannot transplant session '{}' into itself", from);
+    }
+    let mut target_info = SpawnInfo::load(repo_path, to)
+        .with_context(|| format!("Session '{}' does not exist", to))?;
+
+    // Read what the source's mount has acknowledged
+    crate::commands::sync_session(repo_path, from).await?;
+    let source = SessionTree::open(repo_path, from).await?;
+    let files = select_changes(&git, &source, paths, hunks)?;
+    if files.is_empty() {
+        println!("Nothing to transplant from '{}'", from);
+        return Ok(());
+    }
+
+    println!("Transplanting {} file(s) from '{}' into '{}'", files.len(), from, to);
+
+    let outcome = match transplant_live(repo_path, from, to, &files).await? {
+        Some(outcome) => outcome,
+        None => {
+            // Not mounted: update the session's store directly
+            let session_dir = repo_path.join(".vibe/sessions").join(to);
+            let session_db = session_dir.join("metadata.db");
+            let store = if session_db.exists() {
+                MetadataStore::open(&session_db)?
+            } else {
+                MetadataStore::open(repo_path.join(".vibe/metadata.db"))?
+            };
+            apply_transplant(&git, &store, &session_dir, from, to, &files)?
+        }
+    };
+
+    for path in &outcome.copied {
+        println!("  ✓ {}", path);
+    }
+    for path in &outcome.merged {
+        println!("  ✓ {} (merged)", path);
+    }
+    for path in &outcome.skipped {
+        println!("  - {} (unchanged)", path);
+    }
+    for file in &outcome.failed {
+        println!("  ✗ {} ({})", file.path, file.reason);
+    }
+    if !outcome.conflicts.is_empty() {
+        for path in &outcome.conflicts {
+            println!("  ⚠ {} (conflict)", path);
+            if !target_info.conflicts.contains(path) {
+                target_info.conflicts.push(path.clone());
+            }
+        }
+        target_info.save(repo_path)?;
+        println!("\nResolve the conflict markers in '{}' before running 'vibe commit -s {}'.", to, to);
+    }
+    if !outcome.failed.is_empty() {
+        anyhow::bail!(
+            "{} file(s) could not be transplanted into '{}'; it keeps its own version of them",
+            outcome.failed.len(),
+            to
+        );
+    }
+
+    Ok(())
+}
+
+/// Ask the daemon to apply the transplant; `None` if it isn't serving `to`
+async fn transplant_live(
+    repo_path: &Path,
+    from: &str,
+    to: &str,
+    files: &[TransplantFile],
+) -> Result<Option<TransplantOutcome>> {
+    let Ok(mut client) = DaemonClient::connect(repo_path).await else {
+        return Ok(None);
+    };
+    match client.transplant_files(from, to, files).await? {
+        DaemonResponse::FilesTransplanted { outcome, .. } => Ok(Some(outcome)),
+        DaemonResponse::SessionNotExported { .. } => Ok(None),
+        DaemonResponse::Error { message } => anyhow::bail!("Daemon error: {}", message),
+        other => anyhow::bail!("Unexpected daemon response: {:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{init, spawn};
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("parser.rs"), "fn a() {}\n\nfn b() {}\n\nfn c() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[test]
+    fn test_is_selected() {
+        let selected = vec!["src/".to_string(), "README.md".to_string()];
+        assert!(is_selected("src/parser.rs", &selected));
+        assert!(is_selected("README.md", &selected));
+        assert!(!is_selected("srcs/main.rs", &selected));
+        assert!(is_selected("anything", &[]));
+    }
+
+    #[tokio::test]
+    async fn test_transplant_merges_with_target_changes() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        init::init(repo_path).await.unwrap();
+
+        let edit = |session: &str, path: &str, content: &str| {
+            let session_dir = repo_path.join(".vibe/sessions").join(session);
+            fs::write(session_dir.join(path), content).unwrap();
+            MetadataStore::open(session_dir.join("metadata.db")).unwrap().mark_dirty(path).unwrap();
+        };
+        spawn::spawn_local_from(repo_path, "fixer", Some("HEAD")).await.unwrap();
+        spawn::spawn_local_from(repo_path, "other", Some("HEAD")).await.unwrap();
+        edit("fixer", "parser.rs", "fn a() { fixed }\n\nfn b() {}\n\nfn c() {}\n");
+        edit("fixer", "new.rs", "fn new() {}\n");
+        edit("other", "parser.rs", "fn a() {}\n\nfn b() {}\n\nfn c() { other }\n");
+
+        transplant(repo_path, "fixer", "other", &[], false).await.unwrap();
+
+        let other_dir = repo_path.join(".vibe/sessions/other");
+        assert_eq!(
+            fs::read_to_string(other_dir.join("parser.rs")).unwrap(),
+            "fn a() { fixed }\n\nfn b() {}\n\nfn c() { other }\n"
+        );
+        assert_eq!(fs::read_to_string(other_dir.join("new.rs")).unwrap(), "fn new() {}\n");
+        let store = MetadataStore::open(other_dir.join("metadata.db")).unwrap();
+        assert!(store.is_dirty("new.rs").unwrap());
+        let id = store.get_inode_by_path("new.rs").unwrap().unwrap();
+        assert_eq!(store.get_inode(id).unwrap().unwrap().size, 12);
+        drop(store);
+
+        // Both sides changing the same lines is a recorded conflict
+        edit("fixer", "parser.rs", "fn a() { fixed }\n\nfn b() { mine }\n\nfn c() {}\n");
+        edit("other", "parser.rs", "fn a() { fixed }\n\nfn b() { yours }\n\nfn c() { other }\n");
+        transplant(repo_path, "fixer", "other", &["parser.rs".to_string()], false).await.unwrap();
+        let content = fs::read_to_string(other_dir.join("parser.rs")).unwrap();
+        assert!(content.contains("<<<<<<< session other"));
+        assert_eq!(SpawnInfo::load(repo_path, "other").unwrap().conflicts, vec!["parser.rs"]);
+
+        // Files that can't be merged are reported and fail the command
+        edit("fixer", "parser.rs", "\0binary");
+        let err = transplant(repo_path, "fixer", "other", &["parser.rs".to_string()], false).await.unwrap_err();
+        assert!(err.to_string().contains("could not be transplanted"));
+        assert_eq!(fs::read_to_string(other_dir.join("parser.rs")).unwrap(), content);
+    }
+}
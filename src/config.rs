@@ -0,0 +1,429 @@
+//! VibeFS configuration
+//!
+//! Settings are read from the user's `~/.config/vibe/config.toml` (or
+//! `$XDG_CONFIG_HOME/vibe/config.toml`), then from the repository's
+//! `.vibe/config.toml`, which overrides it key by key. Both the CLI and the
+//! daemon load them; anything left unset uses the built-in defaults. A file
+//! that doesn't parse, or has an unknown key or a value of the wrong type,
+//! is an error naming the file and the key, wherever it is loaded.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::{Table, Value};
+
+/// Directories that should be symlinked to local storage for performance
+/// and to avoid macOS NFS xattr issues with build tools.
+pub const DEFAULT_ARTIFACT_DIRS: &[&str] = &[
+    "target",           // Rust/Cargo
+    "node_modules",     // Node.js/npm
+    ".venv",            // Python virtualenv
+    "__pycache__",      // Python cache
+    ".next",            // Next.js
+    ".nuxt",            // Nuxt.js
+    "dist",             // Common build output
+    "build",            // Common build output
+];
+
+/// Default daemon idle timeout: 20 minutes
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 20 * 60;
+
+/// Default local storage for artifact directories
+pub const DEFAULT_ARTIFACTS_ROOT: &str = "/tmp/vibe-artifacts";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub daemon: DaemonConfig,
+    pub paths: PathsConfig,
+    pub artifacts: ArtifactsConfig,
+    pub init: InitConfig,
+    pub commit: CommitConfig,
+    /// Agents for `vibe new --agent` and `vibe <agent>`, in addition to the
+    /// built-in ones
+    pub agents: BTreeMap<String, AgentConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Seconds without client requests before the daemon shuts down
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self { idle_timeout_secs: DEFAULT_IDLE_TIMEOUT_SECS }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Local storage for artifact directories, one subdirectory per session
+    pub artifacts_root: PathBuf,
+    /// Where sessions are mounted. Read from the user config only, since
+    /// mounts are located before the repository is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mounts_root: Option<PathBuf>,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            artifacts_root: PathBuf::from(DEFAULT_ARTIFACTS_ROOT),
+            mounts_root: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArtifactsConfig {
+    /// Directories linked to local storage: top-level names, or paths whose
+    /// parent part is a glob (`packages/*/node_modules`)
+    pub dirs: Vec<String>,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self { dirs: DEFAULT_ARTIFACT_DIRS.iter().map(|d| d.to_string()).collect() }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InitConfig {
+    pub untracked: UntrackedFiles,
+}
+
+/// What `vibe init` does with files Git doesn't track
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UntrackedFiles {
+    /// Serve them read-through from the working tree
+    #[default]
+    Scan,
+    /// Leave them out of sessions
+    Skip,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitConfig {
+    /// Message for `vibe commit` without `-m`; `{session}` is replaced by
+    /// the session name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Always commit as if `--ref-only` were given
+    pub ref_only: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    /// Binary to run (the agent's name if unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Arguments passed before any given on the command line
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// The user-level config file
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        if !dir.trim().is_empty() {
+            return Some(PathBuf::from(dir).join("vibe/config.toml"));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(".config/vibe/config.toml"))
+}
+
+/// The repository's config file
+pub fn repo_config_path(repo_path: &Path) -> PathBuf {
+    repo_path.join(".vibe/config.toml")
+}
+
+impl Config {
+    /// Settings for `repo_path`: the user config overridden by the repository's
+    pub fn load(repo_path: &Path) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = user_config_path().into_iter().collect();
+        paths.push(repo_config_path(repo_path));
+        Self::load_files(&paths)
+    }
+
+    /// User-level settings only
+    pub fn load_user() -> Result<Self> {
+        Self::load_files(&user_config_path().into_iter().collect::<Vec<_>>())
+    }
+
+    /// Merge the config files in `paths` (later ones win); missing files are skipped
+    pub fn load_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut merged = Table::new();
+        for path in paths {
+            merge(&mut merged, read_config_file(path)?);
+        }
+        Self::from_table(merged)
+    }
+
+    fn from_table(table: Table) -> Result<Self> {
+        Value::Table(table).try_into().map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    /// Every setting as a dotted key and its value, defaults included
+    pub fn entries(&self) -> Result<Vec<(String, Value)>> {
+        let mut entries = Vec::new();
+        flatten("", &Value::try_from(self)?, &mut entries);
+        Ok(entries)
+    }
+
+    /// The value of a dotted key (`daemon.idle_timeout_secs`), if set
+    pub fn get(&self, key: &str) -> Result<Option<Value>> {
+        let mut value = Value::try_from(self)?;
+        for part in key_parts(key)? {
+            match value {
+                Value::Table(mut table) => match table.remove(part) {
+                    Some(child) => value = child,
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.daemon.idle_timeout_secs)
+    }
+
+    /// Local storage for a session's artifact directories
+    pub fn artifacts_dir(&self, vibe_id: &str) -> PathBuf {
+        self.paths.artifacts_root.join(vibe_id)
+    }
+
+    /// Session paths to link to artifact storage. Plain names are top-level
+    /// directories; for `parent/name` patterns, `name` inside every directory
+    /// the `parent` glob matches (`**` includes the top level).
+    /// `directories` lists the session's directories and is only called when
+    /// a pattern needs it.
+    pub fn artifact_paths<F>(&self, directories: F) -> Result<Vec<String>>
+    where
+        F: FnOnce() -> Result<Vec<String>>,
+    {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let mut directories = Some(directories);
+        let mut known_dirs: Vec<String> = Vec::new();
+        let mut paths = BTreeSet::new();
+
+        for pattern in &self.artifacts.dirs {
+            let pattern = pattern.trim_matches('/');
+            let (parent, name) = match pattern.rsplit_once('/') {
+                Some((parent, name)) => (Some(parent), name),
+                None => (None, pattern),
+            };
+            if name.is_empty() || name.contains(['*', '?', '[']) {
+                anyhow::bail!("Artifact directory '{}' must end in a plain directory name", pattern);
+            }
+
+            let Some(parent) = parent else {
+                paths.insert(name.to_string());
+                continue;
+            };
+            if let Some(list) = directories.take() {
+                known_dirs = list()?;
+            }
+            let parent_pattern = glob::Pattern::new(parent)
+                .with_context(|| format!("Invalid artifact directory pattern '{}'", pattern))?;
+            if parent == "**" {
+                paths.insert(name.to_string());
+            }
+            for dir in known_dirs.iter().filter(|dir| parent_pattern.matches_with(dir, options)) {
+                paths.insert(format!("{}/{}", dir, name));
+            }
+        }
+
+        Ok(paths.into_iter().collect())
+    }
+}
+
+/// Set a dotted `key` in the config file at `path`, keeping its other
+/// settings. `value` is read as TOML (`30`, `true`, `["a", "b"]`), or as a
+/// plain string when it isn't valid TOML.
+pub fn set_value(path: &Path, key: &str, value: &str) -> Result<()> {
+    let mut table = read_table(path)?;
+    let parts = key_parts(key)?;
+    let (last, parents) = parts.split_last().expect("key has at least one part");
+
+    let mut current = &mut table;
+    for part in parents {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = match entry {
+            Value::Table(child) => child,
+            _ => anyhow::bail!("'{}' in '{}' is not a table", part, key),
+        };
+    }
+    current.insert(last.to_string(), parse_value(value));
+
+    Config::from_table(table.clone()).with_context(|| format!("Invalid setting '{}'", key))?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, toml::to_string_pretty(&table)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn parse_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+fn key_parts(key: &str) -> Result<Vec<&str>> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        anyhow::bail!("Invalid config key '{}'", key);
+    }
+    Ok(parts)
+}
+
+fn read_table(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// A config file's settings, checked on their own so that an invalid key
+/// is reported with the file it is in
+fn read_config_file(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    // The parse error points at the offending key
+    toml::from_str::<Config>(&content).map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Merge `overrides` into `base`, table by table
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_child)), Value::Table(child)) => merge(base_child, child),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, entries: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Table(table) => {
+            for (key, child) in table {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&key, child, entries);
+            }
+        }
+        _ => entries.push((prefix.to_string(), value.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_repo_config_overrides_user_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let user = temp_dir.path().join("user.toml");
+        let repo = temp_dir.path().join("repo.toml");
+        std::fs::write(&user, "[daemon]\nidle_timeout_secs = 60\n\n[commit]\nref_only = true\n").unwrap();
+        std::fs::write(&repo, "[daemon]\nidle_timeout_secs = 90\n").unwrap();
+
+        let config = Config::load_files(&[user.clone(), repo.clone()]).unwrap();
+        assert_eq!(config.idle_timeout(), Duration::from_secs(90));
+        assert!(config.commit.ref_only);
+        assert_eq!(config.artifacts, ArtifactsConfig::default());
+
+        set_value(&repo, "agents.review.command", "claude").unwrap();
+        set_value(&repo, "agents.review.args", r#"["--model", "opus"]"#).unwrap();
+        set_value(&repo, "paths.artifacts_root", "/var/tmp/artifacts").unwrap();
+        assert!(set_value(&repo, "daemon.idle_timeout", "5").is_err());
+        assert!(set_value(&repo, "daemon.idle_timeout_secs", "soon").is_err());
+
+        let config = Config::load_files(&[user, repo]).unwrap();
+        assert_eq!(config.agents["review"].args, vec!["--model", "opus"]);
+        assert_eq!(config.artifacts_dir("s1"), PathBuf::from("/var/tmp/artifacts/s1"));
+        assert_eq!(config.get("daemon.idle_timeout_secs").unwrap(), Some(Value::Integer(90)));
+        assert_eq!(config.get("commit.message").unwrap(), None);
+        assert!(config.entries().unwrap().iter().any(|(key, _)| key == "agents.review.command"));
+    }
+
+    #[test]
+    fn test_invalid_config_names_file_and_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let user = temp_dir.path().join("user.toml");
+        let repo = temp_dir.path().join("repo.toml");
+        std::fs::write(&user, "[commit]\nref_only = true\n").unwrap();
+        std::fs::write(&repo, "[daemon]\nidle_timeout = 5\n").unwrap();
+
+        let message = format!("{:#}", Config::load_files(&[user.clone(), repo.clone()]).unwrap_err());
+        assert!(message.contains(&repo.display().to_string()), "{}", message);
+        assert!(message.contains("idle_timeout"), "{}", message);
+
+        std::fs::write(&repo, "[daemon]\nidle_timeout_secs = \"soon\"\n").unwrap();
+        let message = format!("{:#}", Config::load_files(&[user, repo.clone()]).unwrap_err());
+        assert!(message.contains(&repo.display().to_string()), "{}", message);
+        assert!(message.contains("idle_timeout_secs"), "{}", message);
+    }
+
+    #[test]
+    fn test_artifact_paths_expand_nested_globs() {
+        let config = Config {
+            artifacts: ArtifactsConfig {
+                dirs: vec!["target".into(), "packages/*/node_modules".into(), "**/.venv".into()],
+            },
+            ..Default::default()
+        };
+        let dirs = || Ok(vec!["packages".to_string(), "packages/a".into(), "packages/a/src".into(), "tools".into()]);
+
+        assert_eq!(
+            config.artifact_paths(dirs).unwrap(),
+            vec![
+                ".venv",
+                "packages/.venv",
+                "packages/a/.venv",
+                "packages/a/node_modules",
+                "packages/a/src/.venv",
+                "target",
+                "tools/.venv",
+            ]
+        );
+
+        let config = Config {
+            artifacts: ArtifactsConfig { dirs: vec!["packages/*".into()] },
+            ..Default::default()
+        };
+        assert!(config.artifact_paths(|| Ok(Vec::new())).is_err());
+    }
+}
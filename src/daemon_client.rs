@@ -126,8 +126,15 @@ impl DaemonClient {
 
     /// Export a session (create/mount)
     pub async fn export_session(&mut self, vibe_id: &str) -> Result<DaemonResponse> {
+        self.export_session_at(vibe_id, None).await
+    }
+
+    /// Export a session whose inode map, if it is created now, is built from
+    /// the `base` commit's tree instead of HEAD's
+    pub async fn export_session_at(&mut self, vibe_id: &str, base: Option<&str>) -> Result<DaemonResponse> {
         self.request(DaemonRequest::ExportSession {
             vibe_id: vibe_id.to_string(),
+            base: base.map(str::to_string),
         })
         .await
     }
@@ -163,6 +170,72 @@ impl DaemonClient {
         .await
     }
 
+    /// Flush a session's buffered NFS writes to disk
+    pub async fn sync_session(&mut self, vibe_id: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::SyncSession {
+            vibe_id: vibe_id.to_string(),
+        })
+        .await
+    }
+
+    /// Copy a session's changes into a new session directory
+    pub async fn fork_session(&mut self, source: &str, vibe_id: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::ForkSession {
+            source: source.to_string(),
+            vibe_id: vibe_id.to_string(),
+        })
+        .await
+    }
+
+    /// Write files selected from session `source` into session `vibe_id`
+    pub async fn transplant_files(
+        &mut self,
+        source: &str,
+        vibe_id: &str,
+        files: &[crate::commands::transplant::TransplantFile],
+    ) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::TransplantFiles {
+            source: source.to_string(),
+            vibe_id: vibe_id.to_string(),
+            files: files.to_vec(),
+        })
+        .await
+    }
+
+    /// Dirty and deleted paths of a session, from the daemon's store
+    pub async fn get_dirty_paths(&mut self, vibe_id: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::GetDirtyPaths {
+            vibe_id: vibe_id.to_string(),
+        })
+        .await
+    }
+
+    /// Metadata of one path in a session
+    pub async fn get_inode(&mut self, vibe_id: &str, path: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::GetInode {
+            vibe_id: vibe_id.to_string(),
+            path: path.to_string(),
+        })
+        .await
+    }
+
+    /// Metadata of a path in a session and everything under it
+    pub async fn list_tree(&mut self, vibe_id: &str, path: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::ListTree {
+            vibe_id: vibe_id.to_string(),
+            path: path.to_string(),
+        })
+        .await
+    }
+
+    /// Inode, change and delta counts of a session
+    pub async fn session_stats(&mut self, vibe_id: &str) -> Result<DaemonResponse> {
+        self.request(DaemonRequest::SessionStats {
+            vibe_id: vibe_id.to_string(),
+        })
+        .await
+    }
+
     /// Request daemon shutdown
     pub async fn shutdown(&mut self) -> Result<DaemonResponse> {
         self.request(DaemonRequest::Shutdown).await
@@ -1,10 +1,10 @@
 use anyhow::{Context, Result};
-use rocksdb::{DB, Options};
+use rocksdb::{DB, Options, WriteBatch};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Metadata about a file or directory in the virtual filesystem
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InodeMetadata {
     pub path: String,
     pub git_oid: Option<String>,
@@ -13,8 +13,91 @@ pub struct InodeMetadata {
     pub volatile: bool, // For untracked files like .env, node_modules
     #[serde(default)]
     pub mtime: u64, // Unix epoch seconds; 0 = use server init time as fallback
+    #[serde(default)]
+    pub mtime_nsec: u32,
+    #[serde(default)]
+    pub ctime: u64, // Last metadata change, epoch seconds; 0 = same as mtime
+    #[serde(default)]
+    pub ctime_nsec: u32,
+    #[serde(default)]
+    pub atime: u64, // Only changed by an explicit setattr; 0 = same as mtime
+    #[serde(default)]
+    pub atime_nsec: u32,
+    #[serde(default)]
+    pub mode: u32, // Unix permission bits; 0 = default for the entry type
+    #[serde(default)]
+    pub symlink_target: Option<String>, // Set for symlinks (git mode 120000 or created in session)
+}
+
+/// Current wall-clock time as (epoch seconds, nanoseconds)
+pub fn now_timestamp() -> (u64, u32) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    (now.as_secs(), now.subsec_nanos())
 }
 
+impl InodeMetadata {
+    /// Record a content change: bumps mtime and ctime
+    pub fn touch_modified(&mut self) {
+        let (secs, nsec) = now_timestamp();
+        self.mtime = secs;
+        self.mtime_nsec = nsec;
+        self.ctime = secs;
+        self.ctime_nsec = nsec;
+    }
+
+    /// Record a metadata-only change (chmod, utimes): bumps ctime
+    pub fn touch_changed(&mut self) {
+        let (secs, nsec) = now_timestamp();
+        self.ctime = secs;
+        self.ctime_nsec = nsec;
+    }
+
+    /// Permission bits to serve, defaulting to 0o755 for directories and 0o644 for files
+    pub fn permissions(&self) -> u32 {
+        if self.mode != 0 {
+            self.mode
+        } else if self.is_dir {
+            0o755
+        } else {
+            0o644
+        }
+    }
+
+    /// Target of a symlink. Also understands the older `symlink:<target>`
+    /// encoding in `git_oid` used by stores written before `symlink_target`.
+    pub fn link_target(&self) -> Option<&str> {
+        self.symlink_target
+            .as_deref()
+            .or_else(|| self.git_oid.as_deref().and_then(|oid| oid.strip_prefix("symlink:")))
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.link_target().is_some()
+    }
+
+    /// Git tree entry mode for a regular file: 100755 if any execute bit is set
+    pub fn git_file_mode(&self) -> &'static str {
+        if self.permissions() & 0o111 != 0 {
+            "100755"
+        } else {
+            "100644"
+        }
+    }
+}
+
+/// Record of a base entry deleted in a session.
+/// Keeps the original inode so a reset can bring the entry back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Whiteout {
+    pub inode_id: u64,
+    pub metadata: InodeMetadata,
+}
+
+/// Raw key/value pair read from the store
+type KeyValue = (Box<[u8]>, Box<[u8]>);
+
 /// Bi-directional inode-to-Git mapping store
 pub struct MetadataStore {
     db: DB,
@@ -56,6 +139,23 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Store several inodes in one batch
+    pub fn put_inodes(&self, inodes: &[(u64, InodeMetadata)]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for (inode_id, metadata) in inodes {
+            batch.put(format!("inode:{}", inode_id).as_bytes(), serde_json::to_vec(metadata)?);
+            batch.put(format!("path:{}", metadata.path).as_bytes(), inode_id.to_le_bytes());
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Sync the write-ahead log so everything stored so far survives a crash
+    pub fn sync(&self) -> Result<()> {
+        self.db.flush_wal(true).context("Failed to sync metadata store")?;
+        Ok(())
+    }
+
     /// Get metadata by inode ID
     pub fn get_inode(&self, inode_id: u64) -> Result<Option<InodeMetadata>> {
         let key = format!("inode:{}", inode_id);
@@ -98,28 +198,110 @@ impl MetadataStore {
         Ok(())
     }
 
-    /// Rename an inode (update path mappings properly)
-    pub fn rename_inode(&self, inode_id: u64, old_path: &str, new_path: &str) -> Result<()> {
-        // Get current metadata
-        let mut metadata = self.get_inode(inode_id)?
-            .ok_or_else(|| anyhow::anyhow!("Inode {} not found", inode_id))?;
+    /// Get an entry and all of its descendants (for directories), by path
+    pub fn get_subtree(&self, path: &str) -> Result<Vec<(u64, InodeMetadata)>> {
+        let mut ids = Vec::new();
+        if let Some(id) = self.get_inode_by_path(path)? {
+            ids.push(id);
+        }
+        for (_, value) in self.scan_prefix(format!("path:{}/", path).as_bytes())? {
+            ids.push(u64::from_le_bytes(value.as_ref().try_into()?));
+        }
+
+        let mut entries = Vec::new();
+        for id in ids {
+            if let Some(metadata) = self.get_inode(id)? {
+                entries.push((id, metadata));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Move an entry and all of its descendants from `old_path` to `new_path`
+    /// in one atomic batch: inode records, path mappings and dirty markers are
+    /// rewritten together.
+    ///
+    /// Entries selected by `detach_base` are base files leaving their Git path:
+    /// a whiteout records the old path, the git oid is dropped and the new path
+    /// is marked dirty. Existing whiteouts stay put, since they describe base
+    /// paths and a rename doesn't change those.
+    pub fn rename_subtree<F>(&self, old_path: &str, new_path: &str, detach_base: F) -> Result<()>
+    where
+        F: Fn(&InodeMetadata) -> bool,
+    {
+        let mut batch = WriteBatch::default();
+        self.batch_rename_subtree(&mut batch, old_path, new_path, &detach_base)?;
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Like `rename_subtree`, but `new_path` is occupied by `target`, which is
+    /// replaced in the same batch. The target's inode and dirty marker go away;
+    /// with `whiteout_target` (a base file) a whiteout records it so a reset
+    /// can bring it back.
+    pub fn rename_replacing<F>(
+        &self,
+        old_path: &str,
+        new_path: &str,
+        target_id: u64,
+        target: &InodeMetadata,
+        whiteout_target: bool,
+        detach_base: F,
+    ) -> Result<()>
+    where
+        F: Fn(&InodeMetadata) -> bool,
+    {
+        let mut batch = WriteBatch::default();
+
+        batch.delete(format!("inode:{}", target_id).as_bytes());
+        batch.delete(format!("path:{}", target.path).as_bytes());
+        batch.delete(format!("dirty:{}", target.path).as_bytes());
+        let whiteout_key = format!("whiteout:{}", target.path);
+        if whiteout_target && self.db.get(whiteout_key.as_bytes())?.is_none() {
+            let whiteout = Whiteout { inode_id: target_id, metadata: target.clone() };
+            batch.put(whiteout_key.as_bytes(), serde_json::to_vec(&whiteout)?);
+        }
 
-        // Delete old path mapping
-        let old_path_key = format!("path:{}", old_path);
-        self.db.delete(old_path_key.as_bytes())?;
+        self.batch_rename_subtree(&mut batch, old_path, new_path, &detach_base)?;
 
-        // Update metadata with new path
-        metadata.path = new_path.to_string();
+        self.db.write(batch)?;
+        Ok(())
+    }
 
-        // Store updated metadata (this also creates the new path mapping)
-        self.put_inode(inode_id, &metadata)?;
+    fn batch_rename_subtree<F>(&self, batch: &mut WriteBatch, old_path: &str, new_path: &str, detach_base: &F) -> Result<()>
+    where
+        F: Fn(&InodeMetadata) -> bool,
+    {
+        let renamed = |path: &str| format!("{}{}", new_path, &path[old_path.len()..]);
+
+        for (id, metadata) in self.get_subtree(old_path)? {
+            let mut moved = metadata.clone();
+            moved.path = renamed(&metadata.path);
+
+            if detach_base(&metadata) {
+                let whiteout_key = format!("whiteout:{}", metadata.path);
+                if self.db.get(whiteout_key.as_bytes())?.is_none() {
+                    let whiteout = Whiteout { inode_id: id, metadata: metadata.clone() };
+                    batch.put(whiteout_key.as_bytes(), serde_json::to_vec(&whiteout)?);
+                }
+                moved.git_oid = None;
+                batch.put(format!("dirty:{}", moved.path).as_bytes(), b"1");
+            }
 
-        // If the file was dirty under the old path, update the dirty tracking
-        let old_dirty_key = format!("dirty:{}", old_path);
-        if self.db.get(old_dirty_key.as_bytes())?.is_some() {
-            self.db.delete(old_dirty_key.as_bytes())?;
-            let new_dirty_key = format!("dirty:{}", new_path);
-            self.db.put(new_dirty_key.as_bytes(), b"1")?;
+            batch.delete(format!("path:{}", metadata.path).as_bytes());
+            batch.put(format!("path:{}", moved.path).as_bytes(), id.to_le_bytes());
+            batch.put(format!("inode:{}", id).as_bytes(), serde_json::to_vec(&moved)?);
+        }
+
+        let mut dirty_keys = self.scan_prefix(format!("dirty:{}/", old_path).as_bytes())?;
+        let exact_key = format!("dirty:{}", old_path);
+        if let Some(value) = self.db.get(exact_key.as_bytes())? {
+            dirty_keys.push((exact_key.into_bytes().into(), value.into()));
+        }
+        for (key, _) in dirty_keys {
+            let path = String::from_utf8_lossy(&key["dirty:".len()..]).to_string();
+            batch.delete(&key);
+            batch.put(format!("dirty:{}", renamed(&path)).as_bytes(), b"1");
         }
 
         Ok(())
@@ -142,6 +324,27 @@ impl MetadataStore {
         Ok(next_id)
     }
 
+    /// Generation number for this store's NFS file handles. Created on
+    /// first use and kept in the store, so handles given out before a
+    /// daemon restart still resolve after it.
+    pub fn handle_generation(&self) -> Result<u64> {
+        let key = b"counter:handle_generation";
+        if let Some(bytes) = self.db.get(key)? {
+            let bytes: [u8; 8] = bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Corrupt handle generation ({} bytes)", bytes.len()))?;
+            return Ok(u64::from_le_bytes(bytes));
+        }
+
+        let generation = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        self.db.put(key, generation.to_le_bytes())?;
+        Ok(generation)
+    }
+
     /// Mark a path as dirty (modified in session)
     pub fn mark_dirty(&self, path: &str) -> Result<()> {
         let key = format!("dirty:{}", path);
@@ -220,6 +423,17 @@ impl MetadataStore {
         Ok(dest)
     }
 
+    /// Write a complete copy of this store (dirty markers and whiteouts
+    /// included) to a new location. RocksDB checkpoints are point-in-time
+    /// and hard-link table files, so this is safe while the store is in use.
+    pub fn checkpoint_to<P: AsRef<Path>>(&self, dest_path: P) -> Result<()> {
+        let dest_path = dest_path.as_ref();
+        rocksdb::checkpoint::Checkpoint::new(&self.db)?
+            .create_checkpoint(dest_path)
+            .with_context(|| format!("Failed to checkpoint metadata store to {}", dest_path.display()))?;
+        Ok(())
+    }
+
     /// Clear dirty mark for a specific path
     pub fn clear_dirty_path(&self, path: &str) -> Result<()> {
         let key = format!("dirty:{}", path);
@@ -229,18 +443,97 @@ impl MetadataStore {
 
     /// Clear all dirty marks
     pub fn clear_dirty(&self) -> Result<()> {
-        let prefix = b"dirty:";
-        let keys: Vec<_> = self.db.prefix_iterator(prefix)
-            .filter_map(|item| item.ok())
-            .map(|(key, _)| key)
-            .collect();
-
-        for key in keys {
+        for (key, _) in self.scan_prefix(b"dirty:")? {
             self.db.delete(&key)?;
         }
 
         Ok(())
     }
+
+    /// Record that a base entry was deleted in this session.
+    /// An existing whiteout for the path is kept, so the original inode survives
+    /// delete/recreate/delete cycles.
+    pub fn put_whiteout(&self, inode_id: u64, metadata: &InodeMetadata) -> Result<()> {
+        let key = format!("whiteout:{}", metadata.path);
+        if self.db.get(key.as_bytes())?.is_some() {
+            return Ok(());
+        }
+
+        let whiteout = Whiteout {
+            inode_id,
+            metadata: metadata.clone(),
+        };
+        self.db.put(key.as_bytes(), serde_json::to_vec(&whiteout)?)?;
+        Ok(())
+    }
+
+    /// Check if a path has a whiteout
+    pub fn is_whiteout(&self, path: &str) -> Result<bool> {
+        let key = format!("whiteout:{}", path);
+        Ok(self.db.get(key.as_bytes())?.is_some())
+    }
+
+    /// Get the whiteout recorded for a path
+    pub fn get_whiteout(&self, path: &str) -> Result<Option<Whiteout>> {
+        let key = format!("whiteout:{}", path);
+        match self.db.get(key.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all whiteouts
+    pub fn get_whiteouts(&self) -> Result<Vec<Whiteout>> {
+        let mut whiteouts = Vec::new();
+        for (_, value) in self.scan_prefix(b"whiteout:")? {
+            whiteouts.push(serde_json::from_slice(&value)?);
+        }
+        Ok(whiteouts)
+    }
+
+    /// Get all paths deleted in this session
+    pub fn get_whiteout_paths(&self) -> Result<Vec<String>> {
+        Ok(self.get_whiteouts()?.into_iter().map(|w| w.metadata.path).collect())
+    }
+
+    /// Clear the whiteout for a specific path
+    pub fn clear_whiteout(&self, path: &str) -> Result<()> {
+        let key = format!("whiteout:{}", path);
+        self.db.delete(key.as_bytes())?;
+        Ok(())
+    }
+
+    /// Bring every whiteout entry back under its original inode and drop the
+    /// whiteouts. Anything recreated at those paths in the meantime is replaced.
+    /// Returns the number of restored entries.
+    pub fn restore_whiteouts(&self) -> Result<usize> {
+        let whiteouts = self.get_whiteouts()?;
+        for whiteout in &whiteouts {
+            if let Some(current) = self.get_inode_by_path(&whiteout.metadata.path)? {
+                if current != whiteout.inode_id {
+                    self.delete_inode(current)?;
+                }
+            }
+            self.put_inode(whiteout.inode_id, &whiteout.metadata)?;
+            self.clear_whiteout(&whiteout.metadata.path)?;
+        }
+        Ok(whiteouts.len())
+    }
+
+    /// Collect all key/value pairs under a key prefix.
+    /// `prefix_iterator` alone runs past the prefix (no prefix extractor is
+    /// configured), so stop at the first key outside it.
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<KeyValue>> {
+        let mut items = Vec::new();
+        for item in self.db.prefix_iterator(prefix) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            items.push((key, value));
+        }
+        Ok(items)
+    }
 }
 
 #[cfg(test)]
@@ -248,6 +541,18 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_handle_generation_is_stable_and_checked() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(temp_dir.path().join("test.db")).unwrap();
+
+        let generation = store.handle_generation().unwrap();
+        assert_eq!(store.handle_generation().unwrap(), generation);
+
+        store.db.put(b"counter:handle_generation", b"short").unwrap();
+        assert!(store.handle_generation().is_err());
+    }
+
     #[test]
     fn test_metadata_store_roundtrip() {
         let temp_dir = TempDir::new().unwrap();
@@ -256,10 +561,8 @@ mod tests {
         let metadata = InodeMetadata {
             path: "src/main.rs".to_string(),
             git_oid: Some("abc123".to_string()),
-            is_dir: false,
             size: 1024,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
 
         let inode_id = store.next_inode_id().unwrap();
@@ -278,11 +581,8 @@ mod tests {
 
         let metadata = InodeMetadata {
             path: "src/lib.rs".to_string(),
-            git_oid: None,
-            is_dir: false,
             size: 512,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
 
         let inode_id = store.next_inode_id().unwrap();
@@ -309,4 +609,106 @@ mod tests {
         store.clear_dirty().unwrap();
         assert!(!store.is_dirty("test.txt").unwrap());
     }
+
+    #[test]
+    fn test_checkpoint_copies_dirty_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(temp_dir.path().join("test.db")).unwrap();
+        store.mark_dirty("a.txt").unwrap();
+
+        store.checkpoint_to(temp_dir.path().join("copy.db")).unwrap();
+        store.mark_dirty("b.txt").unwrap();
+
+        let copy = MetadataStore::open(temp_dir.path().join("copy.db")).unwrap();
+        assert_eq!(copy.get_dirty_paths().unwrap(), vec!["a.txt"]);
+    }
+
+    #[test]
+    fn test_clear_dirty_keeps_inodes() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(temp_dir.path().join("test.db")).unwrap();
+
+        let metadata = InodeMetadata {
+            path: "src/lib.rs".to_string(),
+            git_oid: Some("abc123".to_string()),
+            size: 512,
+            ..Default::default()
+        };
+        let inode_id = store.next_inode_id().unwrap();
+        store.put_inode(inode_id, &metadata).unwrap();
+        store.mark_dirty("src/lib.rs").unwrap();
+
+        store.clear_dirty().unwrap();
+
+        assert!(store.get_inode(inode_id).unwrap().is_some());
+        assert_eq!(store.get_inode_by_path("src/lib.rs").unwrap(), Some(inode_id));
+    }
+
+    #[test]
+    fn test_rename_subtree() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(temp_dir.path().join("test.db")).unwrap();
+
+        let entry = |path: &str, is_dir: bool, git_oid: Option<&str>| InodeMetadata {
+            path: path.to_string(),
+            git_oid: git_oid.map(|o| o.to_string()),
+            is_dir,
+            ..Default::default()
+        };
+        let dir = store.next_inode_id().unwrap();
+        store.put_inode(dir, &entry("src/old", true, None)).unwrap();
+        let tracked = store.next_inode_id().unwrap();
+        store.put_inode(tracked, &entry("src/old/lib.rs", false, Some("abc123"))).unwrap();
+        let created = store.next_inode_id().unwrap();
+        store.put_inode(created, &entry("src/old/sub/new.rs", false, None)).unwrap();
+        store.mark_dirty("src/old/sub/new.rs").unwrap();
+        // Sibling sharing the name prefix must not move
+        let sibling = store.next_inode_id().unwrap();
+        store.put_inode(sibling, &entry("src/older.rs", false, Some("def456"))).unwrap();
+
+        store.rename_subtree("src/old", "src/new", |m| m.git_oid.is_some()).unwrap();
+
+        assert_eq!(store.get_inode_by_path("src/new").unwrap(), Some(dir));
+        assert_eq!(store.get_inode_by_path("src/new/lib.rs").unwrap(), Some(tracked));
+        assert_eq!(store.get_inode_by_path("src/new/sub/new.rs").unwrap(), Some(created));
+        assert!(store.get_inode_by_path("src/old/lib.rs").unwrap().is_none());
+        assert_eq!(store.get_inode(created).unwrap().unwrap().path, "src/new/sub/new.rs");
+        assert_eq!(store.get_inode_by_path("src/older.rs").unwrap(), Some(sibling));
+
+        // Dirty markers follow; the moved base file is dirty at its new path
+        // and leaves a whiteout behind
+        let mut dirty = store.get_dirty_paths().unwrap();
+        dirty.sort();
+        assert_eq!(dirty, vec!["src/new/lib.rs".to_string(), "src/new/sub/new.rs".to_string()]);
+        assert_eq!(store.get_whiteout_paths().unwrap(), vec!["src/old/lib.rs".to_string()]);
+        assert!(store.get_inode(tracked).unwrap().unwrap().git_oid.is_none());
+    }
+
+    #[test]
+    fn test_whiteout_roundtrip_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(temp_dir.path().join("test.db")).unwrap();
+
+        let metadata = InodeMetadata {
+            path: "src/old.rs".to_string(),
+            git_oid: Some("abc123".to_string()),
+            size: 42,
+            ..Default::default()
+        };
+        let inode_id = store.next_inode_id().unwrap();
+        store.put_inode(inode_id, &metadata).unwrap();
+
+        // Delete it from the session
+        store.delete_inode(inode_id).unwrap();
+        store.put_whiteout(inode_id, &metadata).unwrap();
+        assert!(store.is_whiteout("src/old.rs").unwrap());
+        assert_eq!(store.get_whiteout_paths().unwrap(), vec!["src/old.rs".to_string()]);
+        assert!(store.get_inode_by_path("src/old.rs").unwrap().is_none());
+
+        // Reset brings the original inode back
+        assert_eq!(store.restore_whiteouts().unwrap(), 1);
+        assert!(!store.is_whiteout("src/old.rs").unwrap());
+        assert_eq!(store.get_inode_by_path("src/old.rs").unwrap(), Some(inode_id));
+        assert_eq!(store.get_inode(inode_id).unwrap().unwrap().git_oid, Some("abc123".to_string()));
+    }
 }
@@ -1,29 +1,101 @@
-// Simplified Git operations using the git command
+// Git operations: objects are read and written in-process through gix,
+// everything else uses the git command
 use anyhow::{Context, Result};
+use gix::ObjectId;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-/// Simplified Git repository interface
+/// One file that differs between two trees. `old` and `new` are
+/// `(oid, mode)`; `None` means the path is absent on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeChange {
+    pub path: String,
+    pub old: Option<(String, u32)>,
+    pub new: Option<(String, u32)>,
+}
+
+/// Commit message trailer naming the session a commit was made from. It
+/// chains `vibe commit`s on `refs/vibes/<session>` and lets `vibe log` tell
+/// session commits from the base history they sit on.
+pub const SESSION_TRAILER: &str = "Vibe-Session";
+
+/// One commit, as listed by `vibe log`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitSummary {
+    pub oid: String,
+    pub parents: Vec<String>,
+    /// Committer time, seconds since the epoch
+    pub time: i64,
+    pub subject: String,
+    /// Full message, including trailers
+    pub message: String,
+    /// Value of the `Vibe-Session` trailer, if any
+    pub session: Option<String>,
+}
+
+/// Result of a three-way tree merge (`GitRepo::merge_trees`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeMerge {
+    /// Merged tree; conflicted files hold conflict markers
+    pub tree: String,
+    /// Paths that did not merge cleanly
+    pub conflicts: Vec<String>,
+}
+
+/// One hunk of a zero-context diff (`GitRepo::diff_hunks`). Lines keep
+/// their line terminators.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// First old line replaced (1-based), or the line the hunk is inserted
+    /// after when `removed` is empty
+    pub old_start: usize,
+    pub new_start: usize,
+    pub removed: Vec<Vec<u8>>,
+    pub added: Vec<Vec<u8>>,
+}
+
+impl Hunk {
+    /// Replace the hunks' old lines in `old` with their new lines. `hunks`
+    /// must come from one diff of `old`, in order; any subset may be applied.
+    pub fn apply(old: &[u8], hunks: &[&Hunk]) -> Vec<u8> {
+        let lines: Vec<&[u8]> = old.split_inclusive(|&b| b == b'\n').collect();
+        let mut result = Vec::with_capacity(old.len());
+        let mut next = 0;
+        for hunk in hunks {
+            let start = if hunk.removed.is_empty() { hunk.old_start } else { hunk.old_start - 1 };
+            for line in &lines[next.min(lines.len())..start.min(lines.len())] {
+                result.extend_from_slice(line);
+            }
+            for line in &hunk.added {
+                result.extend_from_slice(line);
+            }
+            next = start + hunk.removed.len();
+        }
+        for line in &lines[next.min(lines.len())..] {
+            result.extend_from_slice(line);
+        }
+        result
+    }
+}
+
+/// Git repository interface.
+///
+/// Holds a thread-safe gix handle so blob reads (every NFS read of a clean
+/// file) don't fork `git cat-file`.
 pub struct GitRepo {
     repo_path: PathBuf,
+    repo: gix::ThreadSafeRepository,
 }
 
 impl GitRepo {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let repo_path = path.as_ref().to_path_buf();
 
-        // Verify it's a git repo
-        let output = Command::new("git")
-            .args(&["rev-parse", "--git-dir"])
-            .current_dir(&repo_path)
-            .output()
-            .context("Failed to run git command")?;
-
-        if !output.status.success() {
-            anyhow::bail!("Not a git repository");
-        }
+        let repo = gix::open(&repo_path)
+            .map_err(|_| anyhow::anyhow!("Not a git repository"))?
+            .into_sync();
 
-        Ok(Self { repo_path })
+        Ok(Self { repo_path, repo })
     }
 
     /// Get the repository path
@@ -48,48 +120,75 @@ impl GitRepo {
         Ok(oid)
     }
 
-    pub fn read_blob(&self, oid: &str) -> Result<Vec<u8>> {
+    /// Resolve a revision (branch, tag, commit, any ref) to a commit id
+    pub fn resolve_commit(&self, rev: &str) -> Result<String> {
         let output = Command::new("git")
-            .args(&["cat-file", "blob", oid])
+            .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
             .current_dir(&self.repo_path)
             .output()
-            .context("Failed to read blob")?;
+            .context("Failed to run git rev-parse")?;
 
         if !output.status.success() {
-            anyhow::bail!("Failed to read blob {}", oid);
+            anyhow::bail!("Unknown revision '{}'", rev);
         }
 
-        Ok(output.stdout)
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
     }
 
-    pub fn write_blob(&self, data: &[u8]) -> Result<String> {
-        let mut child = Command::new("git")
-            .args(&["hash-object", "-w", "--stdin"])
+    /// Full ref name a revision refers to (`main` -> `refs/heads/main`), or
+    /// `None` for a commit id or expression
+    pub fn symbolic_full_name(&self, rev: &str) -> Option<String> {
+        let output = Command::new("git")
+            .args(["rev-parse", "--symbolic-full-name", rev])
             .current_dir(&self.repo_path)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .spawn()
-            .context("Failed to spawn git hash-object")?;
+            .output()
+            .ok()?;
+
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && name.starts_with("refs/")).then_some(name)
+    }
 
-        {
-            use std::io::Write;
-            let stdin = child.stdin.as_mut().unwrap();
-            stdin.write_all(data)?;
+    pub fn read_blob(&self, oid: &str) -> Result<Vec<u8>> {
+        let id = ObjectId::from_hex(oid.as_bytes())
+            .with_context(|| format!("Invalid object id {}", oid))?;
+        let repo = self.repo.to_thread_local();
+        let object = repo.find_object(id)
+            .with_context(|| format!("Failed to read blob {}", oid))?;
+
+        if object.kind != gix::object::Kind::Blob {
+            anyhow::bail!("Object {} is not a blob", oid);
         }
 
-        let output = child.wait_with_output()?;
+        Ok(object.detach().data)
+    }
 
-        if !output.status.success() {
-            anyhow::bail!("Failed to write blob");
-        }
+    /// Size of a blob in bytes, read from the object header without
+    /// inflating the content.
+    pub fn blob_size(&self, oid: &str) -> Result<u64> {
+        let id = ObjectId::from_hex(oid.as_bytes())
+            .with_context(|| format!("Invalid object id {}", oid))?;
+        let repo = self.repo.to_thread_local();
+        let header = repo.find_header(id)
+            .with_context(|| format!("Failed to read blob {}", oid))?;
+        Ok(header.size())
+    }
 
-        let oid = String::from_utf8(output.stdout)?
-            .trim()
-            .to_string();
-        Ok(oid)
+    /// Object id `data` would have as a blob, without writing it
+    pub fn hash_blob(&self, data: &[u8]) -> String {
+        let repo = self.repo.to_thread_local();
+        gix::objs::compute_hash(repo.object_hash(), gix::object::Kind::Blob, data).to_string()
     }
 
-    pub fn list_tree_files(&self) -> Result<Vec<(PathBuf, String)>> {
+    pub fn write_blob(&self, data: &[u8]) -> Result<String> {
+        let repo = self.repo.to_thread_local();
+        let oid = repo.write_blob(data)
+            .context("Failed to write blob")?;
+        Ok(oid.to_string())
+    }
+
+    /// List all blobs in the HEAD tree as (path, oid, git mode).
+    /// The mode is the octal tree entry mode, e.g. 0o100644 or 0o100755.
+    pub fn list_tree_files(&self) -> Result<Vec<(PathBuf, String, u32)>> {
         let output = Command::new("git")
             .args(&["ls-tree", "-r", "HEAD"])
             .current_dir(&self.repo_path)
@@ -115,10 +214,11 @@ impl GitRepo {
                 continue;
             }
 
+            let mode = u32::from_str_radix(metadata[0], 8).unwrap_or(0o100644);
             let oid = metadata[2].to_string();
             let path = PathBuf::from(parts[1]);
 
-            files.push((path, oid));
+            files.push((path, oid, mode));
         }
 
         Ok(files)
@@ -127,18 +227,141 @@ impl GitRepo {
     /// Read file content at a specific commit (like `git show <commit>:<path>`)
     pub fn read_file_at_commit(&self, commit: &str, path: &str) -> Result<Option<Vec<u8>>> {
         let spec = format!("{}:{}", commit, path);
-        let output = Command::new("git")
-            .args(&["show", &spec])
-            .current_dir(&self.repo_path)
+        let repo = self.repo.to_thread_local();
+
+        // File doesn't exist at this commit
+        let Ok(id) = repo.rev_parse_single(spec.as_str()) else {
+            return Ok(None);
+        };
+
+        let object = id.object().context("Failed to read object")?;
+        if object.kind != gix::object::Kind::Blob {
+            return Ok(None);
+        }
+
+        Ok(Some(object.detach().data))
+    }
+
+    /// Content and tree entry mode of a file at a commit, `None` if the path
+    /// isn't a file (or symlink) there
+    pub fn read_entry_at_commit(&self, commit: &str, path: &str) -> Result<Option<(Vec<u8>, u32)>> {
+        let repo = self.repo.to_thread_local();
+        let tree = repo
+            .rev_parse_single(commit)
+            .with_context(|| format!("Unknown revision {}", commit))?
+            .object()
+            .context("Failed to read object")?
+            .peel_to_tree()
+            .with_context(|| format!("{} has no tree", commit))?;
+
+        let mut buf = Vec::new();
+        let Some(entry) = tree.lookup_entry_by_path(path, &mut buf)? else {
+            return Ok(None);
+        };
+        if !entry.mode().is_blob_or_symlink() {
+            return Ok(None);
+        }
+
+        let mode = u32::from(entry.mode().0);
+        let object = entry.object().context("Failed to read object")?;
+        Ok(Some((object.detach().data, mode)))
+    }
+
+    /// Write a tree holding just `entries` (`(path, mode, blob oid)`), built
+    /// in a scratch index so the repository's index is left alone
+    pub fn write_tree(&self, entries: &[(String, u32, String)]) -> Result<String> {
+        self.write_tree_in(None, entries)
+    }
+
+    /// Output of `git diff <args> <from> <to>` between two tree-ish revisions
+    pub fn diff_trees_output(&self, from: &str, to: &str, args: &[&str]) -> Result<Vec<u8>> {
+        self.diff_trees_output_in(None, from, to, args)
+    }
+
+    /// A throwaway object directory layered over this repository's objects,
+    /// for commands that need objects git can read but must not keep
+    pub fn scratch_objects(&self) -> Result<ScratchObjects<'_>> {
+        let dir = std::env::temp_dir().join(format!("vibe-objects-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).context("Failed to create scratch object directory")?;
+        Ok(ScratchObjects { git: self, dir })
+    }
+
+    /// A git command run in the repository. With `objects`, new objects go
+    /// there and the repository's own object store is only read, as an
+    /// alternate.
+    fn command(&self, objects: Option<&Path>) -> Command {
+        let mut command = Command::new("git");
+        command.current_dir(&self.repo_path);
+        if let Some(objects) = objects {
+            let alternate = self.repo.to_thread_local().common_dir().join("objects");
+            command
+                .env("GIT_OBJECT_DIRECTORY", objects)
+                .env("GIT_ALTERNATE_OBJECT_DIRECTORIES", alternate);
+        }
+        command
+    }
+
+    fn write_tree_in(&self, objects: Option<&Path>, entries: &[(String, u32, String)]) -> Result<String> {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let index = std::env::temp_dir().join(format!("vibe-index-{}", uuid::Uuid::new_v4()));
+        let result = (|| {
+            let mut child = self
+                .command(objects)
+                .args(["update-index", "--add", "-z", "--index-info"])
+                .env("GIT_INDEX_FILE", &index)
+                .stdin(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .context("Failed to run git update-index")?;
+
+            let mut stdin = child.stdin.take().context("Failed to open git update-index input")?;
+            for (path, mode, oid) in entries {
+                write!(stdin, "{:o} {}\t{}\0", mode, oid, path)?;
+            }
+            drop(stdin);
+
+            let output = child.wait_with_output()?;
+            if !output.status.success() {
+                anyhow::bail!("Failed to build index: {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+
+            let output = self
+                .command(objects)
+                .args(["write-tree"])
+                .env("GIT_INDEX_FILE", &index)
+                .output()
+                .context("Failed to write tree")?;
+            if !output.status.success() {
+                anyhow::bail!("Failed to write tree: {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        })();
+
+        let _ = std::fs::remove_file(&index);
+        result
+    }
+
+    fn diff_trees_output_in(&self, objects: Option<&Path>, from: &str, to: &str, args: &[&str]) -> Result<Vec<u8>> {
+        let output = self
+            .command(objects)
+            .args(["diff", "--no-color", "--no-ext-diff"])
+            .args(args)
+            .args([from, to, "--"])
             .output()
-            .context("Failed to run git show")?;
+            .context("Failed to run git diff")?;
 
         if !output.status.success() {
-            // File doesn't exist at this commit
-            return Ok(None);
+            anyhow::bail!(
+                "Failed to diff {} and {}: {}",
+                from,
+                to,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
         }
 
-        Ok(Some(output.stdout))
+        Ok(output.stdout)
     }
 
     pub fn update_ref(&self, refname: &str, oid: &str) -> Result<()> {
@@ -189,9 +412,222 @@ impl GitRepo {
         self.head_commit()
     }
 
+    /// Files that differ between two tree-ish revisions (`git diff-tree -r`),
+    /// without rename detection
+    pub fn diff_trees(&self, from: &str, to: &str) -> Result<Vec<TreeChange>> {
+        let output = Command::new("git")
+            .args(["diff-tree", "-r", "-z", "--no-renames", from, to])
+            .current_dir(&self.repo_path)
+            .output()
+            .context("Failed to diff trees")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to diff {} and {}: {}",
+                from,
+                to,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        // Records are ":<old mode> <new mode> <old oid> <new oid> <status>\0<path>\0"
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split('\0');
+        let mut changes = Vec::new();
+        while let (Some(header), Some(path)) = (fields.next(), fields.next()) {
+            let parts: Vec<&str> = header.trim_start_matches(':').split(' ').collect();
+            if parts.len() != 5 {
+                continue;
+            }
+            let side = |mode: &str, oid: &str| {
+                let mode = u32::from_str_radix(mode, 8).unwrap_or(0);
+                (mode != 0).then(|| (oid.to_string(), mode))
+            };
+            changes.push(TreeChange {
+                path: path.to_string(),
+                old: side(parts[0], parts[2]),
+                new: side(parts[1], parts[3]),
+            });
+        }
+
+        Ok(changes)
+    }
+
+    /// Three-way merge of file contents with `git merge-file`.
+    ///
+    /// `labels` name ours, base and theirs in the conflict markers. Returns the
+    /// merged content and whether it contains conflicts. Fails on binary input.
+    pub fn merge_file(
+        &self,
+        ours: &[u8],
+        base: &[u8],
+        theirs: &[u8],
+        labels: [&str; 3],
+    ) -> Result<(Vec<u8>, bool)> {
+        let scratch = std::env::temp_dir().join(format!("vibe-merge-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&scratch).context("Failed to create merge directory")?;
+
+        let result = (|| {
+            let [ours_path, base_path, theirs_path] =
+                ["ours", "base", "theirs"].map(|name| scratch.join(name));
+            std::fs::write(&ours_path, ours)?;
+            std::fs::write(&base_path, base)?;
+            std::fs::write(&theirs_path, theirs)?;
+
+            let output = Command::new("git")
+                .arg("merge-file")
+                .arg("-p")
+                .args(["-L", labels[0], "-L", labels[1], "-L", labels[2]])
+                .args([&ours_path, &base_path, &theirs_path])
+                .current_dir(&self.repo_path)
+                .output()
+                .context("Failed to run git merge-file")?;
+
+            // The exit code is the number of conflicts, negative on error
+            match output.status.code() {
+                Some(0) => Ok((output.stdout, false)),
+                Some(1..=127) => Ok((output.stdout, true)),
+                _ => anyhow::bail!(
+                    "git merge-file failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            }
+        })();
+
+        let _ = std::fs::remove_dir_all(&scratch);
+        result
+    }
+
+    /// Hunks turning `old` into `new`, without context lines
+    /// (`git diff --no-index -U0`). Fails on binary input.
+    pub fn diff_hunks(&self, old: &[u8], new: &[u8]) -> Result<Vec<Hunk>> {
+        let scratch = std::env::temp_dir().join(format!("vibe-diff-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&scratch).context("Failed to create diff directory")?;
+
+        let result = (|| {
+            let (old_path, new_path) = (scratch.join("old"), scratch.join("new"));
+            std::fs::write(&old_path, old)?;
+            std::fs::write(&new_path, new)?;
+
+            let output = Command::new("git")
+                .args(["diff", "--no-index", "--no-color", "--no-ext-diff", "-U0"])
+                .args([&old_path, &new_path])
+                .current_dir(&self.repo_path)
+                .output()
+                .context("Failed to run git diff")?;
+
+            // Exit code 1 means the files differ
+            if !matches!(output.status.code(), Some(0 | 1)) {
+                anyhow::bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+            parse_hunks(&output.stdout)
+        })();
+
+        let _ = std::fs::remove_dir_all(&scratch);
+        result
+    }
+
+    /// Merge two commits' trees against their merge base without touching
+    /// the index or working tree (`git merge-tree --write-tree`)
+    pub fn merge_trees(&self, ours: &str, theirs: &str) -> Result<TreeMerge> {
+        let output = Command::new("git")
+            .args(["merge-tree", "--write-tree", "--name-only", "-z", ours, theirs])
+            .current_dir(&self.repo_path)
+            .output()
+            .context("Failed to run git merge-tree")?;
+
+        // Exit code 1 means the merge has conflicts; anything else is an error
+        if !matches!(output.status.code(), Some(0) | Some(1)) {
+            anyhow::bail!(
+                "Failed to merge {} and {}: {}",
+                ours,
+                theirs,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        // "<tree>\0<path>\0...\0\0<messages>": conflicted paths end at the empty field
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split('\0');
+        let tree = fields.next().unwrap_or_default().trim().to_string();
+        let mut conflicts: Vec<String> = Vec::new();
+        if output.status.code() == Some(1) {
+            for path in fields.take_while(|field| !field.is_empty()) {
+                if !conflicts.iter().any(|p| p == path) {
+                    conflicts.push(path.to_string());
+                }
+            }
+        }
+
+        Ok(TreeMerge { tree, conflicts })
+    }
+
+    /// Whether `ancestor` is reachable from `descendant` (or the same commit)
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        let output = Command::new("git")
+            .args(["merge-base", "--is-ancestor", ancestor, descendant])
+            .current_dir(&self.repo_path)
+            .output()
+            .context("Failed to run git merge-base")?;
+
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => anyhow::bail!(
+                "Failed to compare {} and {}: {}",
+                ancestor,
+                descendant,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        }
+    }
+
+    pub fn commit_summary(&self, rev: &str) -> Result<CommitSummary> {
+        let format = format!(
+            "--format=%H%x00%P%x00%ct%x00%s%x00%(trailers:key={},valueonly,separator=%x2C)%x00%B",
+            SESSION_TRAILER
+        );
+        let output = Command::new("git")
+            .args(["log", "-1", &format, rev, "--"])
+            .current_dir(&self.repo_path)
+            .output()
+            .context("Failed to run git log")?;
+
+        if !output.status.success() {
+            anyhow::bail!("Unknown commit {}", rev);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let fields: Vec<&str> = stdout.splitn(6, '\0').collect();
+        if fields.len() != 6 {
+            anyhow::bail!("Unexpected git log output for {}", rev);
+        }
+
+        let session = fields[4].trim();
+        Ok(CommitSummary {
+            oid: fields[0].to_string(),
+            parents: fields[1].split_whitespace().map(str::to_string).collect(),
+            time: fields[2].trim().parse().unwrap_or(0),
+            subject: fields[3].to_string(),
+            message: fields[5].trim_end().to_string(),
+            session: (!session.is_empty()).then(|| session.to_string()),
+        })
+    }
+
     pub fn create_commit(&self, tree_oid: &str, parent_oid: &str, message: &str) -> Result<String> {
+        self.create_commit_with_parents(tree_oid, &[parent_oid], message)
+    }
+
+    /// Create a commit with any number of parents (two for a merge commit)
+    pub fn create_commit_with_parents(&self, tree_oid: &str, parents: &[&str], message: &str) -> Result<String> {
+        let mut args = vec!["commit-tree", tree_oid];
+        for parent in parents {
+            args.extend(["-p", parent]);
+        }
+        args.extend(["-m", message]);
+
         let output = Command::new("git")
-            .args(&["commit-tree", tree_oid, "-p", parent_oid, "-m", message])
+            .args(&args)
             .current_dir(&self.repo_path)
             .output()
             .context("Failed to create commit")?;
@@ -206,3 +642,276 @@ impl GitRepo {
         Ok(oid)
     }
 }
+
+/// Objects written for a single command (see [`GitRepo::scratch_objects`]).
+/// They never reach the repository's object store, and the directory is
+/// removed on drop.
+pub struct ScratchObjects<'a> {
+    git: &'a GitRepo,
+    dir: PathBuf,
+}
+
+impl ScratchObjects<'_> {
+    /// Write each of `contents` as a blob, returning their oids in order
+    pub fn write_blobs<'b>(&self, contents: impl IntoIterator<Item = &'b [u8]>) -> Result<Vec<String>> {
+        let mut paths = String::new();
+        for (i, data) in contents.into_iter().enumerate() {
+            let path = self.dir.join(format!("blob-{}", i));
+            std::fs::write(&path, data).context("Failed to write scratch blob")?;
+            paths.push_str(&path.to_string_lossy());
+            paths.push('\n');
+        }
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        use std::io::Write;
+        use std::process::Stdio;
+        let mut child = self
+            .git
+            .command(Some(&self.dir))
+            .args(["hash-object", "-w", "--no-filters", "--stdin-paths"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run git hash-object")?;
+        let mut stdin = child.stdin.take().context("Failed to open git hash-object input")?;
+        stdin.write_all(paths.as_bytes())?;
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to write blobs: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8(output.stdout)?.lines().map(str::to_string).collect())
+    }
+
+    /// [`GitRepo::write_tree`], into the scratch directory
+    pub fn write_tree(&self, entries: &[(String, u32, String)]) -> Result<String> {
+        self.git.write_tree_in(Some(&self.dir), entries)
+    }
+
+    /// [`GitRepo::diff_trees_output`], able to see scratch objects
+    pub fn diff_trees_output(&self, from: &str, to: &str, args: &[&str]) -> Result<Vec<u8>> {
+        self.git.diff_trees_output_in(Some(&self.dir), from, to, args)
+    }
+}
+
+impl Drop for ScratchObjects<'_> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Hunks of a `-U0` patch
+fn parse_hunks(patch: &[u8]) -> Result<Vec<Hunk>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut in_hunk = false;
+
+    for line in patch.split_inclusive(|&b| b == b'\n') {
+        if line.starts_with(b"Binary files ") {
+            anyhow::bail!("Cannot split a binary file into hunks");
+        }
+        if let Some(header) = line.strip_prefix(b"@@ -") {
+            // @@ -<start>[,<count>] +<start>[,<count>] @@
+            let header = String::from_utf8_lossy(header);
+            let mut ranges = header.split(' ');
+            let start = |range: Option<&str>| -> usize {
+                range
+                    .map(|r| r.trim_start_matches('+'))
+                    .and_then(|r| r.split(',').next())
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0)
+            };
+            hunks.push(Hunk {
+                old_start: start(ranges.next()),
+                new_start: start(ranges.next()),
+                removed: Vec::new(),
+                added: Vec::new(),
+            });
+            in_hunk = true;
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut().filter(|_| in_hunk) else {
+            continue;
+        };
+        match line.first() {
+            Some(b'-') => hunk.removed.push(line[1..].to_vec()),
+            Some(b'+') => hunk.added.push(line[1..].to_vec()),
+            // "\ No newline at end of file" belongs to the line before it
+            Some(b'\\') => {
+                let last = if hunk.added.is_empty() { hunk.removed.last_mut() } else { hunk.added.last_mut() };
+                if let Some(last) = last {
+                    if last.ends_with(b"\n") {
+                        last.pop();
+                    }
+                }
+            }
+            _ => in_hunk = false,
+        }
+    }
+
+    Ok(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> (TempDir, GitRepo) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+
+        Command::new("git").args(["init"]).current_dir(repo_path).output().unwrap();
+        Command::new("git").args(["config", "user.name", "Test User"]).current_dir(repo_path).output().unwrap();
+        Command::new("git").args(["config", "user.email", "test@example.com"]).current_dir(repo_path).output().unwrap();
+
+        fs::create_dir_all(repo_path.join("src")).unwrap();
+        fs::write(repo_path.join("README.md"), "# Test").unwrap();
+        fs::write(repo_path.join("src/lib.rs"), "pub fn hello() {}").unwrap();
+        Command::new("git").args(["add", "."]).current_dir(repo_path).output().unwrap();
+        Command::new("git").args(["commit", "-m", "Initial commit"]).current_dir(repo_path).output().unwrap();
+
+        let git_repo = GitRepo::open(repo_path).unwrap();
+        (temp_dir, git_repo)
+    }
+
+    #[test]
+    fn test_open_non_repo() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(GitRepo::open(temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_read_write_blob() {
+        let (_temp_dir, repo) = setup_test_repo();
+
+        let data = b"Hello, VibeFS!";
+        let oid = repo.write_blob(data).unwrap();
+
+        // Same id as git itself computes
+        let output = Command::new("git")
+            .args(["hash-object", "--stdin"])
+            .current_dir(repo.repo_path())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                use std::io::Write;
+                child.stdin.take().unwrap().write_all(data)?;
+                child.wait_with_output()
+            })
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), oid);
+
+        assert_eq!(repo.read_blob(&oid).unwrap(), data);
+        assert_eq!(repo.blob_size(&oid).unwrap(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let repo_path = repo.repo_path().to_path_buf();
+        let old = repo.head_commit().unwrap();
+
+        fs::write(repo_path.join("README.md"), "# Changed").unwrap();
+        fs::write(repo_path.join("new file.txt"), "new").unwrap();
+        fs::remove_file(repo_path.join("src/lib.rs")).unwrap();
+        Command::new("git").args(["add", "-A"]).current_dir(&repo_path).output().unwrap();
+        Command::new("git").args(["commit", "-m", "Change"]).current_dir(&repo_path).output().unwrap();
+        let new = repo.head_commit().unwrap();
+
+        let mut changes = repo.diff_trees(&old, &new).unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["README.md", "new file.txt", "src/lib.rs"]);
+
+        assert!(changes[0].old.is_some() && changes[0].new.is_some());
+        assert_eq!(changes[1].old, None);
+        assert_eq!(changes[1].new.as_ref().unwrap().1, 0o100644);
+        assert_eq!(changes[2].new, None);
+        assert_eq!(repo.read_blob(&changes[1].new.as_ref().unwrap().0).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_merge_trees() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let repo_path = repo.repo_path().to_path_buf();
+        let base = repo.head_commit().unwrap();
+        let commit_on = |parent: &str, path: &str, content: &str| {
+            Command::new("git").args(["checkout", "-q", "--detach", parent]).current_dir(&repo_path).output().unwrap();
+            fs::write(repo_path.join(path), content).unwrap();
+            Command::new("git").args(["commit", "-qam", path]).current_dir(&repo_path).output().unwrap();
+            repo.head_commit().unwrap()
+        };
+
+        let readme = commit_on(&base, "README.md", "# Changed");
+        let lib = commit_on(&base, "src/lib.rs", "pub fn hi() {}");
+        let merge = repo.merge_trees(&readme, &lib).unwrap();
+        assert!(merge.conflicts.is_empty());
+        let merged = repo.create_commit_with_parents(&merge.tree, &[&readme, &lib], "merge").unwrap();
+        assert_eq!(repo.read_file_at_commit(&merged, "README.md").unwrap().unwrap(), b"# Changed");
+        assert_eq!(repo.read_file_at_commit(&merged, "src/lib.rs").unwrap().unwrap(), b"pub fn hi() {}");
+
+        let other = commit_on(&base, "README.md", "# Other");
+        let merge = repo.merge_trees(&readme, &other).unwrap();
+        assert_eq!(merge.conflicts, vec!["README.md".to_string()]);
+    }
+
+    #[test]
+    fn test_merge_file() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let labels = ["session", "base", "HEAD"];
+
+        let (merged, conflicted) = repo
+            .merge_file(b"one\ntwo\nthree!\n", b"one\ntwo\nthree\n", b"one!\ntwo\nthree\n", labels)
+            .unwrap();
+        assert!(!conflicted);
+        assert_eq!(merged, b"one!\ntwo\nthree!\n");
+
+        let (merged, conflicted) = repo
+            .merge_file(b"mine\n", b"base\n", b"theirs\n", labels)
+            .unwrap();
+        assert!(conflicted);
+        let merged = String::from_utf8(merged).unwrap();
+        assert!(merged.contains("<<<<<<< session\nmine\n=======\ntheirs\n>>>>>>> HEAD\n"));
+
+        assert!(repo.merge_file(b"a\0b", b"a\0c", b"a\0d", labels).is_err());
+    }
+
+    #[test]
+    fn test_diff_hunks_apply_any_subset() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let old = b"a\nb\nc\nd\ne\nf\ng\nh";
+        let new = b"a\nB\nc\nd\ne\nf\nnew\ng\nh!";
+
+        let hunks = repo.diff_hunks(old, new).unwrap();
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[1].removed, Vec::<Vec<u8>>::new());
+        assert_eq!(hunks[2].added, vec![b"h!".to_vec()]);
+
+        let all: Vec<&Hunk> = hunks.iter().collect();
+        assert_eq!(Hunk::apply(old, &all), new);
+        assert_eq!(Hunk::apply(old, &[&hunks[1]]), b"a\nb\nc\nd\ne\nf\nnew\ng\nh");
+        assert_eq!(Hunk::apply(old, &[&hunks[0], &hunks[2]]), b"a\nB\nc\nd\ne\nf\ng\nh!");
+        assert!(repo.diff_hunks(old, old).unwrap().is_empty());
+        assert!(repo.diff_hunks(b"a\0b", b"a\0c").is_err());
+    }
+
+    #[test]
+    fn test_read_file_at_commit() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let head = repo.head_commit().unwrap();
+
+        assert_eq!(
+            repo.read_file_at_commit(&head, "src/lib.rs").unwrap(),
+            Some(b"pub fn hello() {}".to_vec())
+        );
+        assert_eq!(repo.read_file_at_commit(&head, "missing.txt").unwrap(), None);
+        // Directories are not files
+        assert_eq!(repo.read_file_at_commit(&head, "src").unwrap(), None);
+    }
+}
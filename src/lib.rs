@@ -1,12 +1,15 @@
 pub mod db;
 pub mod git;
 pub mod gitignore;
+pub mod linediff;
 pub mod names;
 pub mod nfs;
 pub mod commands;
+pub mod config;
 pub mod cwd_validation;
 pub mod daemon_client;
 pub mod platform;
+pub mod session_state;
 
 /// Package version from Cargo.toml
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -23,10 +26,26 @@ pub mod daemon_ipc {
     pub enum DaemonRequest {
         Ping,
         Status,
-        ExportSession { vibe_id: String },
+        ExportSession {
+            vibe_id: String,
+            /// Commit a new session's inode map is built from (HEAD if absent)
+            #[serde(default)]
+            base: Option<String>,
+        },
         UnexportSession { vibe_id: String },
         ResetSession { vibe_id: String, no_backup: bool },
         RebaseSession { vibe_id: String, force: bool },
+        SyncSession { vibe_id: String },
+        ForkSession { source: String, vibe_id: String },
+        TransplantFiles {
+            source: String,
+            vibe_id: String,
+            files: Vec<crate::commands::transplant::TransplantFile>,
+        },
+        GetDirtyPaths { vibe_id: String },
+        GetInode { vibe_id: String, path: String },
+        ListTree { vibe_id: String, path: String },
+        SessionStats { vibe_id: String },
         ListSessions,
         Shutdown,
     }
@@ -45,6 +64,8 @@ pub mod daemon_ipc {
             uptime_secs: u64,
             #[serde(default)]
             version: Option<String>,
+            #[serde(default)]
+            blob_cache: Option<crate::nfs::blob_cache::BlobCacheStats>,
         },
         SessionExported {
             vibe_id: String,
@@ -62,6 +83,47 @@ pub mod daemon_ipc {
             old_base: String,
             new_base: String,
             reconciled_count: usize,
+            #[serde(default)]
+            base_changes: usize,
+            #[serde(default)]
+            merged: usize,
+            #[serde(default)]
+            conflicts: Vec<String>,
+            #[serde(default)]
+            unmerged: Vec<String>,
+        },
+        SessionSynced {
+            vibe_id: String,
+            synced_files: usize,
+        },
+        SessionForked {
+            source: String,
+            vibe_id: String,
+            dirty_count: usize,
+        },
+        FilesTransplanted {
+            vibe_id: String,
+            outcome: crate::commands::transplant::TransplantOutcome,
+        },
+        SessionNotExported {
+            vibe_id: String,
+        },
+        DirtyPaths {
+            vibe_id: String,
+            dirty: Vec<String>,
+            deleted: Vec<String>,
+        },
+        Inode {
+            vibe_id: String,
+            inode: Option<crate::db::InodeMetadata>,
+        },
+        Tree {
+            vibe_id: String,
+            entries: Vec<crate::db::InodeMetadata>,
+        },
+        Stats {
+            vibe_id: String,
+            stats: crate::session_state::SessionStats,
         },
         Sessions {
             sessions: Vec<SessionInfo>,
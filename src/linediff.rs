@@ -0,0 +1,492 @@
+//! Line diffs for `vibe diff`: unified hunks, insertion/deletion counts and
+//! word diffs, computed in-process with imara-diff (Myers or histogram)
+
+use imara_diff::intern::InternedInput;
+use serde::Serialize;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Diff algorithm, named as in `git diff --diff-algorithm`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Histogram,
+}
+
+impl std::str::FromStr for DiffAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "myers" | "default" => Ok(DiffAlgorithm::Myers),
+            "histogram" => Ok(DiffAlgorithm::Histogram),
+            _ => Err(format!("Invalid diff algorithm: {}. Use myers or histogram.", s)),
+        }
+    }
+}
+
+impl DiffAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            DiffAlgorithm::Myers => "myers",
+            DiffAlgorithm::Histogram => "histogram",
+        }
+    }
+}
+
+/// Which whitespace differences to ignore when comparing lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    #[default]
+    Exact,
+    /// Treat runs of whitespace as one space and ignore trailing whitespace (`-b`)
+    IgnoreChange,
+    /// Ignore whitespace entirely (`-w`)
+    IgnoreAll,
+}
+
+/// How lines are compared and hunks are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub algorithm: DiffAlgorithm,
+    pub whitespace: Whitespace,
+    /// Drop changes that only add or remove blank lines
+    pub ignore_blank_lines: bool,
+    /// Show changed words inline (`[-old-]{+new+}`) instead of whole lines
+    pub word_diff: bool,
+    /// Unchanged lines around each hunk
+    pub context: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: DiffAlgorithm::default(),
+            whitespace: Whitespace::default(),
+            ignore_blank_lines: false,
+            word_diff: false,
+            context: 3,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// The same options as `git diff` arguments
+    pub fn git_args(&self) -> Vec<String> {
+        let mut args = vec![
+            format!("--diff-algorithm={}", self.algorithm.name()),
+            format!("-U{}", self.context),
+        ];
+        match self.whitespace {
+            Whitespace::Exact => {}
+            Whitespace::IgnoreChange => args.push("--ignore-space-change".to_string()),
+            Whitespace::IgnoreAll => args.push("--ignore-all-space".to_string()),
+        }
+        if self.ignore_blank_lines {
+            args.push("--ignore-blank-lines".to_string());
+        }
+        if self.word_diff {
+            args.push("--word-diff=plain".to_string());
+        }
+        args
+    }
+}
+
+/// One hunk: old and new line ranges (0-based, end exclusive), context included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkRange {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+    /// Changed ranges inside the hunk, as `(old, new)`
+    pub changes: Vec<(Range<usize>, Range<usize>)>,
+}
+
+/// What a hunk line is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+impl LineKind {
+    pub fn prefix(self) -> char {
+        match self {
+            LineKind::Context => ' ',
+            LineKind::Removed => '-',
+            LineKind::Added => '+',
+        }
+    }
+}
+
+/// One line of a hunk. Line numbers are 1-based; removed lines have no new
+/// line number and added lines no old one. `text` keeps its line terminator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkLine<'a> {
+    pub kind: LineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: &'a str,
+}
+
+impl HunkRange {
+    /// First old line as numbered in the `@@` header: an empty range names
+    /// the line before it
+    pub fn old_start(&self) -> usize {
+        header_start(&self.old)
+    }
+
+    pub fn new_start(&self) -> usize {
+        header_start(&self.new)
+    }
+
+    /// `@@ -<old> +<new> @@`
+    pub fn header(&self) -> String {
+        format!("@@ -{} +{} @@", hunk_range(&self.old), hunk_range(&self.new))
+    }
+}
+
+/// Line diff of two texts
+pub struct LineDiff<'a> {
+    old: Vec<&'a str>,
+    new: Vec<&'a str>,
+    /// Changed ranges, and whether each one counts: with `ignore_blank_lines`,
+    /// blank-only changes are only shown inside hunks of other changes
+    changes: Vec<(Range<usize>, Range<usize>, bool)>,
+    options: DiffOptions,
+}
+
+impl<'a> LineDiff<'a> {
+    pub fn new(old: &'a str, new: &'a str, options: DiffOptions) -> Self {
+        let old: Vec<&str> = old.split_inclusive('\n').collect();
+        let new: Vec<&str> = new.split_inclusive('\n').collect();
+
+        let mut input = InternedInput::default();
+        input.update_before(old.iter().map(|line| line_key(line, options.whitespace)));
+        input.update_after(new.iter().map(|line| line_key(line, options.whitespace)));
+
+        let algorithm = match options.algorithm {
+            DiffAlgorithm::Myers => imara_diff::Algorithm::Myers,
+            DiffAlgorithm::Histogram => imara_diff::Algorithm::Histogram,
+        };
+        let mut changes = Vec::new();
+        imara_diff::diff(algorithm, &input, |before: Range<u32>, after: Range<u32>| {
+            let (before, after) = (before.start as usize..before.end as usize, after.start as usize..after.end as usize);
+            let blank = old[before.clone()].iter().chain(&new[after.clone()]).all(|line| line.trim().is_empty());
+            changes.push((before, after, !(options.ignore_blank_lines && blank)));
+        });
+
+        Self { old, new, changes, options }
+    }
+
+    /// (insertions, deletions) in lines
+    pub fn counts(&self) -> (usize, usize) {
+        self.hunks()
+            .iter()
+            .flat_map(|hunk| &hunk.changes)
+            .fold((0, 0), |(ins, del), (before, after)| (ins + after.len(), del + before.len()))
+    }
+
+    /// Changes grouped into hunks with `context` lines around them; changes
+    /// closer than twice the context share a hunk
+    pub fn hunks(&self) -> Vec<HunkRange> {
+        let context = self.options.context;
+        let mut hunks: Vec<HunkRange> = Vec::new();
+
+        for (before, after, counts) in &self.changes {
+            if let Some(hunk) = hunks.last_mut() {
+                let (last_before, _) = hunk.changes.last().unwrap();
+                if before.start - last_before.end <= 2 * context {
+                    hunk.changes.push((before.clone(), after.clone()));
+                    continue;
+                }
+            }
+            if !counts {
+                continue;
+            }
+            hunks.push(HunkRange {
+                old: 0..0,
+                new: 0..0,
+                changes: vec![(before.clone(), after.clone())],
+            });
+        }
+
+        // Unchanged lines line up one to one, so context extends both sides equally
+        for hunk in &mut hunks {
+            let (first_before, first_after) = hunk.changes.first().unwrap().clone();
+            let (last_before, last_after) = hunk.changes.last().unwrap().clone();
+            let leading = context.min(first_before.start).min(first_after.start);
+            let trailing = context
+                .min(self.old.len() - last_before.end)
+                .min(self.new.len() - last_after.end);
+            hunk.old = first_before.start - leading..last_before.end + trailing;
+            hunk.new = first_after.start - leading..last_after.end + trailing;
+        }
+
+        hunks
+    }
+
+    /// Lines of a hunk in order, with their 1-based line numbers
+    pub fn hunk_lines(&self, hunk: &HunkRange) -> Vec<HunkLine<'a>> {
+        let mut lines = Vec::new();
+        let context = |lines: &mut Vec<HunkLine<'a>>, old_line: usize, new_line: usize| {
+            lines.push(HunkLine {
+                kind: LineKind::Context,
+                old_line: Some(old_line + 1),
+                new_line: Some(new_line + 1),
+                text: self.new[new_line],
+            });
+        };
+
+        let (mut old_line, mut new_line) = (hunk.old.start, hunk.new.start);
+        for (before, after) in &hunk.changes {
+            while old_line < before.start {
+                context(&mut lines, old_line, new_line);
+                old_line += 1;
+                new_line += 1;
+            }
+            for i in before.clone() {
+                lines.push(HunkLine { kind: LineKind::Removed, old_line: Some(i + 1), new_line: None, text: self.old[i] });
+            }
+            for i in after.clone() {
+                lines.push(HunkLine { kind: LineKind::Added, old_line: None, new_line: Some(i + 1), text: self.new[i] });
+            }
+            old_line = before.end;
+            new_line = after.end;
+        }
+        while new_line < hunk.new.end {
+            context(&mut lines, old_line, new_line);
+            old_line += 1;
+            new_line += 1;
+        }
+
+        lines
+    }
+
+    /// Hunks in unified diff format (or word diff format with `word_diff`),
+    /// without file headers
+    pub fn unified(&self) -> String {
+        let mut output = String::new();
+
+        for hunk in self.hunks() {
+            writeln!(output, "{}", hunk.header()).ok();
+
+            if self.options.word_diff {
+                self.push_word_hunk(&mut output, &hunk);
+                continue;
+            }
+            for line in self.hunk_lines(&hunk) {
+                push_line(&mut output, line.kind.prefix(), line.text);
+            }
+        }
+
+        output
+    }
+
+    /// A hunk with changed lines merged into word diffs. Unchanged lines
+    /// read as in the new text.
+    fn push_word_hunk(&self, output: &mut String, hunk: &HunkRange) {
+        let push_context = |output: &mut String, line: &str| {
+            output.push_str(line);
+            if !line.ends_with('\n') {
+                output.push('\n');
+            }
+        };
+
+        let (mut old_line, mut new_line) = (hunk.old.start, hunk.new.start);
+        for (before, after) in &hunk.changes {
+            while old_line < before.start {
+                push_context(output, self.new[new_line]);
+                old_line += 1;
+                new_line += 1;
+            }
+            push_word_diff(output, &self.old[before.clone()], &self.new[after.clone()]);
+            old_line = before.end;
+            new_line = after.end;
+        }
+        while new_line < hunk.new.end {
+            push_context(output, self.new[new_line]);
+            new_line += 1;
+        }
+    }
+}
+
+/// What a line is compared by
+fn line_key(line: &str, whitespace: Whitespace) -> String {
+    match whitespace {
+        Whitespace::Exact => line.to_string(),
+        // Like `git diff -b`: trailing whitespace is ignored and any other run
+        // of whitespace counts as one space, so indentation still matters
+        Whitespace::IgnoreChange => {
+            let mut key = String::with_capacity(line.len());
+            let mut in_space = false;
+            for c in line.trim_end().chars() {
+                if c.is_whitespace() {
+                    if !in_space {
+                        key.push(' ');
+                    }
+                    in_space = true;
+                } else {
+                    key.push(c);
+                    in_space = false;
+                }
+            }
+            key
+        }
+        Whitespace::IgnoreAll => line.chars().filter(|c| !c.is_whitespace()).collect(),
+    }
+}
+
+fn header_start(range: &Range<usize>) -> usize {
+    if range.is_empty() { range.start } else { range.start + 1 }
+}
+
+/// `start,count` of a hunk header; an empty range names the line before it
+fn hunk_range(range: &Range<usize>) -> String {
+    match range.len() {
+        1 => format!("{}", header_start(range)),
+        len => format!("{},{}", header_start(range), len),
+    }
+}
+
+fn push_line(output: &mut String, prefix: char, line: &str) {
+    output.push(prefix);
+    output.push_str(line);
+    if !line.ends_with('\n') {
+        output.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// Split text into alternating runs of whitespace and non-whitespace
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            words.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Removed and added lines merged into one run of text, changed words
+/// marked `[-removed-]` and `{+added+}`. Whitespace changes are not marked.
+fn push_word_diff(output: &mut String, removed: &[&str], added: &[&str]) {
+    let (old, new) = (removed.concat(), added.concat());
+    let (old_words, new_words) = (words(&old), words(&new));
+
+    // Whitespace runs only differ by whether they break the line
+    let key = |word: &str| match word.chars().next() {
+        Some(c) if c.is_whitespace() => if word.contains('\n') { "\n" } else { " " }.to_string(),
+        _ => word.to_string(),
+    };
+    let mut input = InternedInput::default();
+    input.update_before(old_words.iter().map(|w| key(w)));
+    input.update_after(new_words.iter().map(|w| key(w)));
+
+    let mut text = String::new();
+    let mut new_pos = 0;
+    let push_marked = |text: &mut String, words: &[&str], open: &str, close: &str| {
+        let joined = words.concat();
+        let trimmed = joined.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+        let lead = &joined[..joined.len() - joined.trim_start().len()];
+        let trail = &joined[joined.trim_end().len()..];
+        text.push_str(lead);
+        write!(text, "{}{}{}", open, trimmed, close).ok();
+        text.push_str(trail);
+    };
+    imara_diff::diff(imara_diff::Algorithm::Myers, &input, |before: Range<u32>, after: Range<u32>| {
+        let (before, after) = (before.start as usize..before.end as usize, after.start as usize..after.end as usize);
+        text.push_str(&new_words[new_pos..after.start].concat());
+        push_marked(&mut text, &old_words[before.clone()], "[-", "-]");
+        let added = &new_words[after.clone()];
+        if added.iter().all(|w| w.trim().is_empty()) {
+            text.push_str(&added.concat());
+        } else {
+            push_marked(&mut text, added, "{+", "+}");
+        }
+        new_pos = after.end;
+    });
+    text.push_str(&new_words[new_pos..].concat());
+
+    if text.is_empty() {
+        return;
+    }
+    output.push_str(&text);
+    if !text.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_only_changed_lines() {
+        let old: String = (0..2000).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 1000\n", "changed\n");
+        assert_eq!(LineDiff::new(&old, &new, DiffOptions::default()).counts(), (1, 1));
+
+        for algorithm in [DiffAlgorithm::Myers, DiffAlgorithm::Histogram] {
+            let options = DiffOptions { algorithm, ..Default::default() };
+            assert_eq!(LineDiff::new("a\nb\nc\n", "a\nc\nd\n", options).counts(), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_unified_matches_git() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13";
+        let diff = LineDiff::new(old, new, DiffOptions::default()).unified();
+        assert_eq!(
+            diff,
+            "@@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n\
+             @@ -10,3 +10,4 @@\n 10\n 11\n 12\n+13\n\\ No newline at end of file\n"
+        );
+
+        // Pure insertions and deletions name the line before them
+        let diff = LineDiff::new("a\n", "", DiffOptions::default()).unified();
+        assert_eq!(diff, "@@ -1 +0,0 @@\n-a\n");
+    }
+
+    #[test]
+    fn test_whitespace_options() {
+        let old = "fn main() {\n    run();\n}\n";
+        let new = "fn main() {\n\trun();  \n\n}\n";
+        assert_eq!(LineDiff::new(old, new, DiffOptions::default()).counts(), (2, 1));
+
+        let ignore_change = DiffOptions { whitespace: Whitespace::IgnoreChange, ..Default::default() };
+        assert_eq!(LineDiff::new(old, new, ignore_change).counts(), (1, 0));
+        // Adding or removing indentation is still a change
+        assert_eq!(LineDiff::new("a\n  b\n", "a\nb\n", ignore_change).counts(), (1, 1));
+
+        let ignore_all = DiffOptions {
+            whitespace: Whitespace::IgnoreAll,
+            ignore_blank_lines: true,
+            ..Default::default()
+        };
+        let diff = LineDiff::new(old, new, ignore_all);
+        assert_eq!(diff.counts(), (0, 0));
+        assert!(diff.unified().is_empty());
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let options = DiffOptions { word_diff: true, ..Default::default() };
+        let diff = LineDiff::new("a b c\nline two\n", "a B c\nline two\n", options).unified();
+        assert_eq!(diff, "@@ -1,2 +1,2 @@\na [-b-]{+B+} c\nline two\n");
+    }
+}
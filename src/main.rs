@@ -6,6 +6,7 @@ use tracing_subscriber::EnvFilter;
 use vibefs::commands;
 use vibefs::daemon_client::{self, DaemonClient};
 use vibefs::daemon_ipc::DaemonResponse;
+use vibefs::linediff::{DiffOptions, Whitespace};
 
 /// Build version string with git hash
 fn version_string() -> &'static str {
@@ -52,6 +53,10 @@ enum Commands {
         /// Additional arguments to pass to the agent (use after --)
         #[arg(last = true)]
         agent_args: Vec<String>,
+
+        /// Start from a branch, tag, commit, or another session (refs/vibes/<name>) instead of HEAD
+        #[arg(long, value_name = "REV")]
+        from: Option<String>,
     },
 
     /// Create a checkpoint of session state
@@ -89,11 +94,55 @@ enum Commands {
         /// Session to rebase (auto-detected if in mount or single session)
         session: Option<String>,
 
-        /// Force rebase even if there are potential conflicts
+        /// Rebase even if conflicts from a previous rebase are unresolved
         #[arg(short, long)]
         force: bool,
     },
 
+    /// Mark files a rebase left conflicted or unmerged as resolved
+    Resolve {
+        /// Session with the conflicts (auto-detected if in mount or single session)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Files to mark resolved, as they are now in the session
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+
+    /// Start a new session from another session's current state
+    Fork {
+        /// Session to fork
+        source: String,
+
+        /// Name of the new session
+        session: String,
+    },
+
+    /// Copy changed files (or selected hunks) from one session into another
+    Transplant {
+        /// Session to take changes from
+        #[arg(long)]
+        from: String,
+
+        /// Session to write them into
+        #[arg(long)]
+        to: String,
+
+        /// Only these files or directories (all of the source's changes if omitted)
+        paths: Vec<String>,
+
+        /// Choose hunks interactively instead of taking whole files
+        #[arg(long)]
+        hunks: bool,
+    },
+
+    /// Rebase sessions stacked on another session onto its latest commit
+    Restack {
+        /// Only restack sessions stacked on this one (all stacks if omitted)
+        session: Option<String>,
+    },
+
     /// Commit session changes to a Git ref
     #[command(alias = "promote")]
     Commit {
@@ -116,6 +165,16 @@ enum Commands {
         /// Only create the ref, don't merge into HEAD or rebase
         #[arg(long)]
         ref_only: bool,
+
+        /// Replace the session's last commit instead of adding a new one
+        #[arg(long, conflicts_with = "all")]
+        amend: bool,
+    },
+
+    /// Show the commits made from a session
+    Log {
+        /// Session to show (auto-detected if in mount or single session)
+        session: Option<String>,
     },
 
     /// Kill a session (unmount and clean up)
@@ -153,15 +212,64 @@ enum Commands {
         action: DaemonAction,
     },
 
+    /// Show or change settings (.vibe/config.toml, or the user config with --global)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
     /// Show unified diff of session changes
     Diff {
         /// Session ID to show diff for (auto-detected if in mount or single session)
         session: Option<String>,
 
+        /// Second session: compare the two sessions' trees instead
+        other: Option<String>,
+
+        /// Compare against `head`, one of the session's checkpoints, or a revision instead of the session's base
+        #[arg(long, value_name = "BASE", conflicts_with = "other")]
+        against: Option<String>,
+
         /// Show diffstat summary only
         #[arg(long)]
         stat: bool,
 
+        /// Print the changed files as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Patch format: vibe, or git for patches that `git apply` accepts
+        #[arg(long, default_value = "vibe")]
+        format: String,
+
+        /// Show only the names and status (A/D/M/R) of changed files
+        #[arg(long)]
+        name_status: bool,
+
+        /// Show added and deleted line counts per file
+        #[arg(long)]
+        numstat: bool,
+
+        /// Line diff algorithm: myers or histogram
+        #[arg(long, default_value = "myers")]
+        diff_algorithm: String,
+
+        /// Show changed words inline instead of whole lines
+        #[arg(long)]
+        word_diff: bool,
+
+        /// Ignore whitespace when comparing lines
+        #[arg(short = 'w', long)]
+        ignore_all_space: bool,
+
+        /// Ignore changes in the amount of whitespace
+        #[arg(short = 'b', long)]
+        ignore_space_change: bool,
+
+        /// Ignore changes whose lines are all blank
+        #[arg(long)]
+        ignore_blank_lines: bool,
+
         /// Color output: auto, always, never
         #[arg(long, default_value = "auto")]
         color: String,
@@ -213,6 +321,24 @@ enum DaemonAction {
     Status,
 }
 
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the value of a setting (e.g. daemon.idle_timeout_secs)
+    Get {
+        key: String,
+    },
+    /// Change a setting; the value is read as TOML (30, true, ["a", "b"]) or else as a string
+    Set {
+        key: String,
+        value: String,
+        /// Write to the user config (~/.config/vibe/config.toml) instead of the repository's
+        #[arg(long)]
+        global: bool,
+    },
+    /// List every setting, defaults included
+    List,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Handle --version / -V manually so we can show daemon version too
@@ -260,7 +386,7 @@ async fn main() -> Result<()> {
         Commands::Init => {
             commands::init::init(&repo_path).await?;
         }
-        Commands::New { session, command, agent, agent_args } => {
+        Commands::New { session, command, agent, agent_args, from } => {
             // Auto-init if .vibe/ doesn't exist
             let vibe_dir = repo_path.join(".vibe");
             if !vibe_dir.exists() {
@@ -287,10 +413,10 @@ async fn main() -> Result<()> {
 
             // If agent is specified, delegate to launch
             if let Some(agent_name) = agent {
-                commands::launch::launch(&repo_path, &agent_name, Some(&session), &agent_args).await?;
+                commands::launch::launch(&repo_path, &agent_name, Some(&session), &agent_args, from.as_deref()).await?;
             } else {
                 // Spawn the session
-                commands::spawn::spawn(&repo_path, &session).await?;
+                commands::spawn::spawn(&repo_path, &session, from.as_deref()).await?;
 
                 // Connect to daemon and enter shell
                 let mut client = DaemonClient::connect(&repo_path).await?;
@@ -355,14 +481,31 @@ async fn main() -> Result<()> {
             let session = commands::require_session(&repo_path, session)?;
             commands::rebase::rebase(&repo_path, &session, force).await?;
         }
-        Commands::Commit { session, all, only, message, ref_only } => {
+        Commands::Resolve { session, paths } => {
+            let session = commands::require_session(&repo_path, session)?;
+            commands::rebase::resolve(&repo_path, &session, &paths)?;
+        }
+        Commands::Fork { source, session } => {
+            commands::fork::fork(&repo_path, &source, &session).await?;
+        }
+        Commands::Transplant { from, to, paths, hunks } => {
+            commands::transplant::transplant(&repo_path, &from, &to, &paths, hunks).await?;
+        }
+        Commands::Restack { session } => {
+            commands::restack::restack(&repo_path, session.as_deref()).await?;
+        }
+        Commands::Commit { session, all, only, message, ref_only, amend } => {
             if all {
                 commands::promote::promote_all(&repo_path, message.as_deref(), ref_only).await?;
             } else {
                 let id = commands::require_session(&repo_path, session)?;
-                commands::promote::promote(&repo_path, &id, only, message.as_deref(), ref_only).await?;
+                commands::promote::promote(&repo_path, &id, only, message.as_deref(), ref_only, amend).await?;
             }
         }
+        Commands::Log { session } => {
+            let id = commands::require_session(&repo_path, session)?;
+            commands::log::log(&repo_path, &id)?;
+        }
         Commands::Kill { session, force, all, purge } => {
             if all {
                 commands::purge::purge(&repo_path, force).await?;
@@ -417,11 +560,59 @@ async fn main() -> Result<()> {
                 }
             }
         }
-        Commands::Diff { session, stat, color, no_pager } => {
-            let session = commands::require_session(&repo_path, session)?;
-            let color_opt = color.parse().unwrap_or(commands::diff::ColorOption::Auto);
-            commands::diff::diff(&repo_path, &session, stat, color_opt, no_pager).await?;
+        Commands::Diff {
+            session,
+            other,
+            against,
+            stat,
+            json,
+            format,
+            name_status,
+            numstat,
+            diff_algorithm,
+            word_diff,
+            ignore_all_space,
+            ignore_space_change,
+            ignore_blank_lines,
+            color,
+            no_pager,
+        } => {
+            let whitespace = if ignore_all_space {
+                Whitespace::IgnoreAll
+            } else if ignore_space_change {
+                Whitespace::IgnoreChange
+            } else {
+                Whitespace::Exact
+            };
+            let display = commands::diff::DiffDisplay {
+                stat_only: stat,
+                name_status,
+                numstat,
+                json,
+                format: format.parse().map_err(anyhow::Error::msg)?,
+                options: DiffOptions {
+                    algorithm: diff_algorithm.parse().map_err(anyhow::Error::msg)?,
+                    whitespace,
+                    ignore_blank_lines,
+                    word_diff,
+                    ..Default::default()
+                },
+                color: color.parse().unwrap_or(commands::diff::ColorOption::Auto),
+                no_pager,
+            };
+            match (session, other) {
+                (Some(a), Some(b)) => commands::diff::compare(&repo_path, &a, &b, display).await?,
+                (session, _) => {
+                    let session = commands::require_session(&repo_path, session)?;
+                    commands::diff::diff(&repo_path, &session, against.as_deref(), display).await?;
+                }
+            }
         }
+        Commands::Config { action } => match action {
+            ConfigAction::Get { key } => commands::config::get(&repo_path, &key)?,
+            ConfigAction::Set { key, value, global } => commands::config::set(&repo_path, &key, &value, global)?,
+            ConfigAction::List => commands::config::list(&repo_path)?,
+        },
         Commands::Daemon { action } => match action {
             DaemonAction::Start { foreground } => {
                 if foreground {
@@ -451,6 +642,7 @@ async fn main() -> Result<()> {
                             session_count,
                             uptime_secs,
                             version,
+                            blob_cache,
                         } => {
                             println!("Daemon Status:");
                             println!("  Repository: {}", repo_path);
@@ -460,6 +652,16 @@ async fn main() -> Result<()> {
                             println!("  NFS Port: {}", nfs_port);
                             println!("  Active Sessions: {}", session_count);
                             println!("  Uptime: {}s", uptime_secs);
+                            if let Some(cache) = blob_cache {
+                                println!(
+                                    "  Blob Cache: {} hits, {} misses, {} blobs ({} / {} MB)",
+                                    cache.hits,
+                                    cache.misses,
+                                    cache.entries,
+                                    cache.bytes / (1024 * 1024),
+                                    cache.capacity_bytes / (1024 * 1024)
+                                );
+                            }
                         }
                         _ => {
                             println!("Failed to get daemon status");
@@ -487,7 +689,7 @@ async fn main() -> Result<()> {
         Commands::Agent(args) => {
             // Check if first arg is a known agent
             if let Some(agent) = args.first() {
-                if commands::launch::is_known_agent(agent) {
+                if commands::launch::is_known_agent(&repo_path, agent)? {
                     // Auto-init if .vibe/ doesn't exist
                     let vibe_dir = repo_path.join(".vibe");
                     if !vibe_dir.exists() {
@@ -495,7 +697,7 @@ async fn main() -> Result<()> {
                     }
                     // Pass remaining args to the agent
                     let agent_args: Vec<String> = args.iter().skip(1).cloned().collect();
-                    commands::launch::launch(&repo_path, agent, None, &agent_args).await?;
+                    commands::launch::launch(&repo_path, agent, None, &agent_args, None).await?;
                 } else {
                     anyhow::bail!(
                         "Unknown command '{}'. Run 'vibe --help' to see available commands.",
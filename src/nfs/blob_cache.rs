@@ -0,0 +1,166 @@
+//! Byte-bounded LRU cache of decompressed Git blobs.
+//!
+//! NFS clients read files in small chunks (typically 32 KB), so a clean file
+//! is requested many times in a row. Keeping the inflated blob around turns
+//! each of those reads into a slice copy. The cache is keyed by blob oid, so
+//! one instance can be shared by every session the daemon exports: identical
+//! content is stored once no matter how many sessions read it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default cache budget for a standalone `VibeNFS` (the daemon sizes its own)
+pub const DEFAULT_BLOB_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Snapshot of cache counters, reported through the daemon's Status response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: u64,
+    pub capacity_bytes: u64,
+}
+
+struct Entry {
+    data: Arc<[u8]>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// last_used tick -> oid, oldest first
+    lru: BTreeMap<u64, String>,
+    bytes: u64,
+    tick: u64,
+}
+
+/// Thread-safe LRU cache of blob contents, bounded by total bytes
+pub struct BlobCache {
+    inner: Mutex<Inner>,
+    capacity_bytes: u64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BlobCache {
+    pub fn new(capacity_bytes: u64) -> Self {
+        Self {
+            inner: Mutex::new(Inner::default()),
+            capacity_bytes,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Look up a blob, marking it most recently used
+    pub fn get(&self, oid: &str) -> Option<Arc<[u8]>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let Some(entry) = inner.entries.get_mut(oid) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        let previous = std::mem::replace(&mut entry.last_used, tick);
+        let data = entry.data.clone();
+        inner.lru.remove(&previous);
+        inner.lru.insert(tick, oid.to_string());
+
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(data)
+    }
+
+    /// Insert a blob, evicting least recently used entries to stay within budget.
+    /// Blobs larger than the whole budget are not cached.
+    pub fn insert(&self, oid: &str, data: Arc<[u8]>) {
+        let size = data.len() as u64;
+        if size > self.capacity_bytes {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.contains_key(oid) {
+            return;
+        }
+
+        while inner.bytes + size > self.capacity_bytes {
+            let Some((_, victim)) = inner.lru.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = inner.entries.remove(&victim) {
+                inner.bytes -= evicted.data.len() as u64;
+            }
+        }
+
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.lru.insert(tick, oid.to_string());
+        inner.entries.insert(oid.to_string(), Entry { data, last_used: tick });
+        inner.bytes += size;
+    }
+
+    pub fn stats(&self) -> BlobCacheStats {
+        let inner = self.inner.lock().unwrap();
+        BlobCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: inner.entries.len(),
+            bytes: inner.bytes,
+            capacity_bytes: self.capacity_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(len: usize) -> Arc<[u8]> {
+        vec![b'x'; len].into()
+    }
+
+    #[test]
+    fn test_hit_and_miss_counters() {
+        let cache = BlobCache::new(1024);
+        assert!(cache.get("a").is_none());
+        cache.insert("a", blob(10));
+        assert_eq!(cache.get("a").unwrap().len(), 10);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 10);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = BlobCache::new(30);
+        cache.insert("a", blob(10));
+        cache.insert("b", blob(10));
+        cache.insert("c", blob(10));
+
+        // Touch "a" so "b" becomes the oldest
+        cache.get("a");
+        cache.insert("d", blob(10));
+
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert!(cache.get("d").is_some());
+        assert_eq!(cache.stats().bytes, 30);
+    }
+
+    #[test]
+    fn test_oversized_blob_not_cached() {
+        let cache = BlobCache::new(16);
+        cache.insert("big", blob(17));
+        assert!(cache.get("big").is_none());
+        assert_eq!(cache.stats().bytes, 0);
+    }
+}
@@ -3,20 +3,26 @@
 //! This module implements the NFSv3 protocol using the nfsserve crate.
 //! It provides a virtual filesystem that reads from Git ODB and writes to session deltas.
 
+pub mod blob_cache;
+mod write_back;
+
 use anyhow::Result;
 use nfsserve::nfs::{
-    fattr3, fileid3, filename3, ftype3, nfspath3, nfsstat3, nfsstring, nfstime3, sattr3, set_size3, specdata3,
+    cookieverf3, writeverf3, fattr3, fileid3, filename3, ftype3, nfs_fh3, nfspath3, nfsstat3, nfsstring, nfstime3, sattr3, set_atime, set_mode3, set_mtime, set_size3, specdata3,
 };
-use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use nfsserve::vfs::{DirEntry, NFSFileSystem, ReadDirResult, VFSCapabilities};
 use std::collections::HashMap;
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, OwnedRwLockWriteGuard, RwLock};
 
-use crate::db::{InodeMetadata, MetadataStore};
+use crate::db::{now_timestamp, InodeMetadata, MetadataStore};
 use crate::git::GitRepo;
+use write_back::WriteBack;
+use blob_cache::{BlobCache, DEFAULT_BLOB_CACHE_BYTES};
 
 /// Root inode is always 1
 const ROOT_INODE: fileid3 = 1;
@@ -38,6 +44,19 @@ pub struct VibeNFS {
     /// Arc<AtomicU64> so bumping it on the daemon's clone also affects the NFS server's clone
     /// (forces NFS clients to re-read attributes).
     init_time: Arc<AtomicU64>,
+    /// Decompressed blob contents, keyed by oid. The daemon shares one cache
+    /// across all sessions (see `with_blob_cache`).
+    blob_cache: Arc<BlobCache>,
+    /// Open session files and unstored metadata from WRITEs, until `sync`
+    write_back: Arc<Mutex<WriteBack>>,
+    /// Held shared by every operation that changes the session;
+    /// `pause_mutations` takes it exclusively
+    mutations: Arc<RwLock<()>>,
+    /// Embedded in file handles; handles with another generation are stale
+    handle_generation: u64,
+    /// Returned by WRITE and COMMIT. Unlike the handle generation it changes
+    /// with every instance, so clients resend writes a restart didn't commit.
+    write_verifier: u64,
 }
 
 impl VibeNFS {
@@ -48,10 +67,10 @@ impl VibeNFS {
         repo_path: PathBuf,
         vibe_id: String,
     ) -> Self {
-        let init_time = std::time::SystemTime::now()
+        let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+            .unwrap();
+        let init_time = now.as_secs();
         Self {
             metadata,
             git,
@@ -60,7 +79,69 @@ impl VibeNFS {
             vibe_id,
             dir_children: Arc::new(RwLock::new(HashMap::new())),
             init_time: Arc::new(AtomicU64::new(init_time)),
+            blob_cache: Arc::new(BlobCache::new(DEFAULT_BLOB_CACHE_BYTES)),
+            write_back: Arc::new(Mutex::new(WriteBack::default())),
+            mutations: Arc::new(RwLock::new(())),
+            handle_generation: init_time,
+            write_verifier: now.as_nanos() as u64,
+        }
+    }
+
+    /// Use a shared blob cache instead of this instance's private one
+    pub fn with_blob_cache(mut self, blob_cache: Arc<BlobCache>) -> Self {
+        self.blob_cache = blob_cache;
+        self
+    }
+
+    /// Issue file handles with a fixed generation (see
+    /// `MetadataStore::handle_generation`) instead of one tied to this
+    /// instance, so clients' handles survive a daemon restart
+    pub fn with_handle_generation(mut self, generation: u64) -> Self {
+        self.handle_generation = generation;
+        self
+    }
+
+    /// Read a blob through the blob cache
+    async fn read_blob_cached(&self, oid: &str) -> std::result::Result<Arc<[u8]>, nfsstat3> {
+        if let Some(data) = self.blob_cache.get(oid) {
+            return Ok(data);
+        }
+
+        let data: Arc<[u8]> = {
+            let git = self.git.read().await;
+            git.read_blob(oid).map_err(|_| nfsstat3::NFS3ERR_IO)?.into()
+        };
+        self.blob_cache.insert(oid, data.clone());
+        Ok(data)
+    }
+
+    /// Read `count` bytes at `offset` from a file on disk without loading the
+    /// rest of it. Returns the data and whether the end of file was reached.
+    async fn read_file_range(path: &Path, offset: u64, count: u32) -> std::result::Result<(Vec<u8>, bool), nfsstat3> {
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        let len = file
+            .metadata()
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?
+            .len();
+
+        if offset >= len {
+            return Ok((Vec::new(), true));
         }
+
+        file.seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        let mut data = Vec::with_capacity(count.min((len - offset) as u32) as usize);
+        file.take(count as u64)
+            .read_to_end(&mut data)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+
+        let eof = offset + data.len() as u64 >= len;
+        Ok((data, eof))
     }
 
     /// Clear the directory children cache and rebuild it from metadata.
@@ -73,6 +154,92 @@ impl VibeNFS {
         self.build_directory_cache().await
     }
 
+    /// Make every write so far durable: fsync and close the cached session
+    /// file handles, then store the pending inode metadata in one batch and
+    /// sync the metadata WAL. Returns the number of files synced.
+    pub async fn sync(&self) -> Result<usize> {
+        let (handles, pending) = {
+            let mut write_back = self.write_back.lock().await;
+            (write_back.take_handles(), write_back.take_pending())
+        };
+
+        let synced = handles.len();
+        tokio::task::spawn_blocking(move || {
+            for file in handles {
+                file.sync_data()?;
+            }
+            std::io::Result::Ok(())
+        })
+        .await??;
+
+        let store = self.metadata.write().await;
+        store.put_inodes(&pending)?;
+        store.sync()?;
+        Ok(synced)
+    }
+
+    /// Wait for in-flight changes to finish, hold off new ones, and `sync`.
+    /// The session directory and metadata store stay as they are on disk
+    /// until the returned guard is dropped; reads are still served.
+    pub async fn pause_mutations(&self) -> Result<OwnedRwLockWriteGuard<()>> {
+        let guard = self.mutations.clone().write_owned().await;
+        self.sync().await?;
+        Ok(guard)
+    }
+
+    /// Drop cached handles and pending metadata without storing them. Used by
+    /// reset, which throws the session's changes away.
+    pub async fn discard_pending_writes(&self) {
+        self.write_back.lock().await.clear();
+    }
+
+    /// Store pending write metadata before an operation that reads or
+    /// rewrites inodes straight from the store
+    async fn flush_pending_metadata(&self) -> std::result::Result<(), nfsstat3> {
+        let pending = {
+            let mut write_back = self.write_back.lock().await;
+            if !write_back.has_pending() {
+                return Ok(());
+            }
+            write_back.take_pending()
+        };
+        let store = self.metadata.write().await;
+        store.put_inodes(&pending).map_err(|_| nfsstat3::NFS3ERR_IO)
+    }
+
+    /// Cached write handle for a session file. Opening one materializes the
+    /// file and marks it dirty; later writes through the handle skip both.
+    async fn write_handle(&self, id: fileid3, metadata: &InodeMetadata) -> std::result::Result<Arc<std::fs::File>, nfsstat3> {
+        if let Some(file) = self.write_back.lock().await.handle(id) {
+            return Ok(file);
+        }
+
+        let session_path = self.get_session_path(Path::new(&metadata.path)).await;
+        self.ensure_session_file(metadata, &session_path).await?;
+        let file = tokio::task::spawn_blocking(move || std::fs::OpenOptions::new().write(true).open(&session_path))
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+
+        if !Self::is_ignored_path(&metadata.path) {
+            let store = self.metadata.write().await;
+            store
+                .mark_dirty(&metadata.path)
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        }
+
+        // An evicted handle is synced on the way out: COMMIT only syncs the
+        // handles still cached
+        let (file, evicted) = self.write_back.lock().await.insert_handle(id, file);
+        if let Some(evicted) = evicted {
+            tokio::task::spawn_blocking(move || evicted.sync_data())
+                .await
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        }
+        Ok(file)
+    }
+
     /// Bump the init_time timestamp so NFS clients see new mtime/ctime values
     /// and re-read file attributes instead of using cached data.
     pub fn bump_init_time(&self) {
@@ -162,8 +329,7 @@ impl VibeNFS {
         // Copy content from source (Git ODB or repo filesystem)
         let content = if let Some(oid) = &metadata.git_oid {
             // Read from Git ODB
-            let git = self.git.read().await;
-            git.read_blob(oid).map_err(|_| nfsstat3::NFS3ERR_IO)?
+            self.read_blob_cached(oid).await?.to_vec()
         } else {
             // Try repo filesystem (untracked file)
             let repo_file = self.repo_path.join(&metadata.path);
@@ -184,7 +350,21 @@ impl VibeNFS {
         Ok(())
     }
 
+    /// Mirror the execute bit onto the session copy of a file, so the delta
+    /// directory agrees with what NFS serves. Only 0o755/0o644 are used: the
+    /// session file must stay writable by the server whatever the client asks for.
+    async fn sync_session_exec_bit(session_path: &Path, metadata: &InodeMetadata) -> std::result::Result<(), nfsstat3> {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if metadata.permissions() & 0o111 != 0 { 0o755 } else { 0o644 };
+        tokio::fs::set_permissions(session_path, std::fs::Permissions::from_mode(mode))
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)
+    }
+
     async fn get_metadata_by_inode(&self, inode: fileid3) -> Result<Option<InodeMetadata>> {
+        if let Some(pending) = self.write_back.lock().await.pending(inode) {
+            return Ok(Some(pending.clone()));
+        }
         let store = self.metadata.read().await;
         store.get_inode(inode)
     }
@@ -205,7 +385,7 @@ impl VibeNFS {
     fn metadata_to_fattr(&self, inode: fileid3, metadata: &InodeMetadata) -> fattr3 {
         let ftype = if metadata.is_dir {
             ftype3::NF3DIR
-        } else if metadata.git_oid.as_ref().map(|o| o.starts_with("symlink:")).unwrap_or(false) {
+        } else if metadata.is_symlink() {
             ftype3::NF3LNK
         } else {
             ftype3::NF3REG
@@ -218,6 +398,10 @@ impl VibeNFS {
         // 3. Cached metadata.size from RocksDB (for clean git-tracked files)
         let size = if metadata.is_dir {
             metadata.size
+        } else if metadata.is_symlink() {
+            // Never stat the session path: it may be a physical symlink that
+            // points to a directory (artifact dirs)
+            metadata.size
         } else {
             let session_file = self.session_dir.join(&metadata.path);
             if let Ok(m) = std::fs::metadata(&session_file) {
@@ -233,11 +417,26 @@ impl VibeNFS {
         // Use stored mtime if available, otherwise fall back to server init time.
         // This ensures timestamps are stable across GETATTR calls, which prevents
         // tools from thinking files changed between read and write operations.
-        let ts = if metadata.mtime > 0 { metadata.mtime } else { self.init_time.load(Ordering::Acquire) };
+        // ctime and atime default to mtime for entries that never had them set.
+        let mtime = if metadata.mtime > 0 {
+            nfstime3 { seconds: metadata.mtime as u32, nseconds: metadata.mtime_nsec }
+        } else {
+            nfstime3 { seconds: self.init_time.load(Ordering::Acquire) as u32, nseconds: 0 }
+        };
+        let ctime = if metadata.ctime > 0 {
+            nfstime3 { seconds: metadata.ctime as u32, nseconds: metadata.ctime_nsec }
+        } else {
+            mtime
+        };
+        let atime = if metadata.atime > 0 {
+            nfstime3 { seconds: metadata.atime as u32, nseconds: metadata.atime_nsec }
+        } else {
+            mtime
+        };
 
         fattr3 {
             ftype,
-            mode: if metadata.is_dir { 0o755 } else { 0o644 },
+            mode: metadata.permissions(),
             nlink: 1,
             uid: unsafe { libc::getuid() },
             gid: unsafe { libc::getgid() },
@@ -249,18 +448,9 @@ impl VibeNFS {
             },
             fsid: 1,
             fileid: inode,
-            atime: nfstime3 {
-                seconds: ts as u32,
-                nseconds: 0,
-            },
-            mtime: nfstime3 {
-                seconds: ts as u32,
-                nseconds: 0,
-            },
-            ctime: nfstime3 {
-                seconds: ts as u32,
-                nseconds: 0,
-            },
+            atime,
+            mtime,
+            ctime,
         }
     }
 
@@ -315,6 +505,19 @@ impl VibeNFS {
         nfsstring(s.as_bytes().to_vec())
     }
 
+    /// Whether an inode is a regular file from the base tree, i.e. one whose
+    /// deletion must be recorded as a whiteout.
+    fn is_base_file(metadata: &InodeMetadata) -> bool {
+        !metadata.is_dir
+            && !metadata.volatile
+            && !Self::is_ignored_path(&metadata.path)
+            && metadata
+                .git_oid
+                .as_ref()
+                .map(|oid| !oid.starts_with("symlink:"))
+                .unwrap_or(false)
+    }
+
     /// Check if a path should be ignored for dirty tracking (e.g., macOS metadata files)
     fn is_ignored_path(path: &str) -> bool {
         let p = Path::new(path);
@@ -338,6 +541,32 @@ impl NFSFileSystem for VibeNFS {
         VFSCapabilities::ReadWrite
     }
 
+    fn id_to_fh(&self, id: fileid3) -> nfs_fh3 {
+        let mut data = Vec::with_capacity(16);
+        data.extend_from_slice(&self.handle_generation.to_le_bytes());
+        data.extend_from_slice(&id.to_le_bytes());
+        nfs_fh3 { data }
+    }
+
+    fn fh_to_id(&self, fh: &nfs_fh3) -> Result<fileid3, nfsstat3> {
+        if fh.data.len() != 16 {
+            return Err(nfsstat3::NFS3ERR_BADHANDLE);
+        }
+        let generation = u64::from_le_bytes(fh.data[0..8].try_into().unwrap());
+        if generation != self.handle_generation {
+            return Err(nfsstat3::NFS3ERR_STALE);
+        }
+        Ok(u64::from_le_bytes(fh.data[8..16].try_into().unwrap()))
+    }
+
+    fn serverid(&self) -> cookieverf3 {
+        self.handle_generation.to_le_bytes()
+    }
+
+    fn write_verifier(&self) -> writeverf3 {
+        self.write_verifier.to_le_bytes()
+    }
+
     async fn lookup(&self, dirid: fileid3, filename: &filename3) -> Result<fileid3, nfsstat3> {
         let name = String::from_utf8_lossy(&filename.0).to_string();
 
@@ -416,14 +645,43 @@ impl NFSFileSystem for VibeNFS {
     }
 
     async fn setattr(&self, id: fileid3, setattr: sattr3) -> Result<fattr3, nfsstat3> {
-        // Handle size change (truncation)
-        if let set_size3::size(new_size) = setattr.size {
-            let metadata = self
-                .get_metadata_by_inode(id)
-                .await
-                .map_err(|_| nfsstat3::NFS3ERR_IO)?
-                .ok_or(nfsstat3::NFS3ERR_NOENT)?;
+        let _mutation = self.mutations.read().await;
+        let new_size = match setattr.size {
+            set_size3::size(size) => Some(size),
+            set_size3::Void => None,
+        };
+        let new_mode = match setattr.mode {
+            set_mode3::mode(mode) => Some(mode & 0o7777),
+            set_mode3::Void => None,
+        };
+        let new_atime = match setattr.atime {
+            set_atime::DONT_CHANGE => None,
+            set_atime::SET_TO_SERVER_TIME => Some(now_timestamp()),
+            set_atime::SET_TO_CLIENT_TIME(t) => Some((t.seconds as u64, t.nseconds)),
+        };
+        let new_mtime = match setattr.mtime {
+            set_mtime::DONT_CHANGE => None,
+            set_mtime::SET_TO_SERVER_TIME => Some(now_timestamp()),
+            set_mtime::SET_TO_CLIENT_TIME(t) => Some((t.seconds as u64, t.nseconds)),
+        };
+        if new_size.is_none() && new_mode.is_none() && new_atime.is_none() && new_mtime.is_none() {
+            return self.getattr(id).await;
+        }
+        // The root's times follow the server, touching it is accepted as a no-op
+        if id == ROOT_INODE && new_size.is_none() && new_mode.is_none() {
+            return self.getattr(id).await;
+        }
+
+        self.flush_pending_metadata().await?;
+        let metadata = self
+            .get_metadata_by_inode(id)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?
+            .ok_or(nfsstat3::NFS3ERR_NOENT)?;
+        let mut updated_metadata = metadata.clone();
 
+        // Handle size change (truncation)
+        if let Some(new_size) = new_size {
             if metadata.is_dir {
                 return Err(nfsstat3::NFS3ERR_ISDIR);
             }
@@ -443,33 +701,57 @@ impl NFSFileSystem for VibeNFS {
                 .await
                 .map_err(|_| nfsstat3::NFS3ERR_IO)?;
 
-            // Mark as dirty and update metadata
-            if !Self::is_ignored_path(&metadata.path) {
-                let store = self.metadata.write().await;
-                store
-                    .mark_dirty(&metadata.path)
-                    .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-                drop(store);
+            updated_metadata.size = new_size;
+            updated_metadata.touch_modified();
+        }
+
+        // Handle mode change (chmod). A file's mode is committed with it, so the
+        // file is copied into the session like any other modification.
+        if let Some(new_mode) = new_mode {
+            if new_mode != metadata.permissions() {
+                updated_metadata.mode = new_mode;
+                updated_metadata.touch_changed();
+                if !metadata.is_dir {
+                    let session_path = self.get_session_path(Path::new(&metadata.path)).await;
+                    self.ensure_session_file(&metadata, &session_path).await?;
+                    Self::sync_session_exec_bit(&session_path, &updated_metadata).await?;
+                }
             }
+        }
 
-            // Update size and mtime in metadata
-            let mut updated_metadata = metadata.clone();
-            updated_metadata.size = new_size;
-            updated_metadata.mtime = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+        // Explicit times (touch, utimensat). These live only in the inode:
+        // Git doesn't record them, so they never make a file dirty.
+        if let Some((secs, nsec)) = new_atime {
+            updated_metadata.atime = secs;
+            updated_metadata.atime_nsec = nsec;
+        }
+        if let Some((secs, nsec)) = new_mtime {
+            updated_metadata.mtime = secs;
+            updated_metadata.mtime_nsec = nsec;
+        }
+        if new_atime.is_some() || new_mtime.is_some() {
+            updated_metadata.touch_changed();
+        }
 
-            let store = self.metadata.write().await;
+        // chmod to the current mode with no other change: nothing to do
+        let content_changed = new_size.is_some() || updated_metadata.mode != metadata.mode;
+        if !content_changed && new_atime.is_none() && new_mtime.is_none() {
+            return Ok(self.metadata_to_fattr(id, &metadata));
+        }
+
+        // Mark as dirty and update metadata
+        let store = self.metadata.write().await;
+        if content_changed && !metadata.is_dir && !Self::is_ignored_path(&metadata.path) {
             store
-                .put_inode(id, &updated_metadata)
+                .mark_dirty(&metadata.path)
                 .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-            drop(store);
-
-            return Ok(self.metadata_to_fattr(id, &updated_metadata));
         }
+        store
+            .put_inode(id, &updated_metadata)
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        drop(store);
 
-        self.getattr(id).await
+        Ok(self.metadata_to_fattr(id, &updated_metadata))
     }
 
     async fn read(
@@ -491,42 +773,32 @@ impl NFSFileSystem for VibeNFS {
         // Session path for potential reads
         let session_path = self.get_session_path(Path::new(&metadata.path)).await;
 
-        let data = if session_path.exists() {
+        if session_path.exists() {
             // Session file takes priority (handles dirty files and AppleDouble metadata).
             // If a file is marked dirty but its session file was removed (e.g., after
             // rebase reconciliation), we gracefully fall through to git/repo below.
-            tokio::fs::read(&session_path)
-                .await
-                .map_err(|_| nfsstat3::NFS3ERR_IO)?
-        } else if metadata.volatile {
-            // Volatile (untracked/gitignored) files always passthrough to real filesystem.
-            // Never trust cached git_oid or size — the file changes independently of git.
-            let repo_file = self.repo_path.join(&metadata.path);
-            if repo_file.exists() && repo_file.is_file() {
-                tokio::fs::read(&repo_file)
-                    .await
-                    .map_err(|_| nfsstat3::NFS3ERR_IO)?
-            } else {
-                Vec::new()
-            }
-        } else if let Some(oid) = &metadata.git_oid {
-            // Read from Git ODB
-            let git = self.git.read().await;
-            git.read_blob(oid).map_err(|_| nfsstat3::NFS3ERR_IO)?
-        } else {
-            // Untracked file without volatile flag - try repo filesystem
-            let repo_file = self.repo_path.join(&metadata.path);
-            if repo_file.exists() && repo_file.is_file() {
-                tokio::fs::read(&repo_file)
-                    .await
-                    .map_err(|_| nfsstat3::NFS3ERR_IO)?
-            } else {
-                Vec::new()
+            return Self::read_file_range(&session_path, offset, count).await;
+        }
+
+        // Volatile (untracked/gitignored) files always passthrough to real filesystem.
+        // Never trust cached git_oid or size — the file changes independently of git.
+        // Untracked files without the volatile flag are read from the repo too.
+        let oid = match &metadata.git_oid {
+            Some(oid) if !metadata.volatile => oid,
+            _ => {
+                let repo_file = self.repo_path.join(&metadata.path);
+                if repo_file.exists() && repo_file.is_file() {
+                    return Self::read_file_range(&repo_file, offset, count).await;
+                }
+                return Ok((Vec::new(), true));
             }
         };
 
+        // Read from Git ODB (through the shared blob cache)
+        let data = self.read_blob_cached(oid).await?;
+
         let start = offset as usize;
-        let end = std::cmp::min(start + count as usize, data.len());
+        let end = std::cmp::min(start.saturating_add(count as usize), data.len());
         let chunk = if start < data.len() {
             data[start..end].to_vec()
         } else {
@@ -538,6 +810,7 @@ impl NFSFileSystem for VibeNFS {
     }
 
     async fn write(&self, id: fileid3, offset: u64, data: &[u8]) -> Result<fattr3, nfsstat3> {
+        let _mutation = self.mutations.read().await;
         let metadata = self
             .get_metadata_by_inode(id)
             .await
@@ -548,72 +821,60 @@ impl NFSFileSystem for VibeNFS {
             return Err(nfsstat3::NFS3ERR_ISDIR);
         }
 
-        // Write to session directory
-        let session_path = self.get_session_path(Path::new(&metadata.path)).await;
-
-        // Ensure file exists in session (copy from git if needed)
-        self.ensure_session_file(&metadata, &session_path).await?;
-
-        // Open file with read+write access for proper seeking
-        let mut file = tokio::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&session_path)
-            .await
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-
-        // Seek to offset and write data directly
-        file.seek(std::io::SeekFrom::Start(offset))
-            .await
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-
-        file.write_all(data)
-            .await
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        // Positioned write through the cached handle. Nothing is fsynced:
+        // the WRITE is acknowledged as UNSTABLE and made durable by `commit`
+        // (or `sync`). The new size/mtime stay in memory until then; the size
+        // can always be recovered from the session file.
+        let file = self.write_handle(id, &metadata).await?;
+        let data = data.to_vec();
+        let new_size = tokio::task::spawn_blocking(move || {
+            file.write_all_at(&data, offset)?;
+            file.metadata().map(|m| m.len())
+        })
+        .await
+        .map_err(|_| nfsstat3::NFS3ERR_IO)?
+        .map_err(|_| nfsstat3::NFS3ERR_IO)?;
 
-        // Sync to ensure data is written
-        file.sync_all()
-            .await
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        let mut updated_metadata = metadata;
+        updated_metadata.size = new_size;
+        updated_metadata.touch_modified();
+        let fattr = self.metadata_to_fattr(id, &updated_metadata);
+        self.write_back.lock().await.set_pending(id, updated_metadata);
 
-        // Get final file size
-        let file_metadata = file.metadata()
-            .await
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-        let new_size = file_metadata.len();
+        Ok(fattr)
+    }
 
-        // Mark as dirty
-        if !Self::is_ignored_path(&metadata.path) {
-            let store = self.metadata.write().await;
-            store
-                .mark_dirty(&metadata.path)
+    /// NFS COMMIT (and stable WRITEs): fsync the file's data and store its
+    /// pending metadata. The handle stays open for further writes, and the
+    /// metadata stays pending so GETATTR never sees an older copy; `sync`
+    /// stores it again.
+    async fn commit(&self, id: fileid3) -> Result<fattr3, nfsstat3> {
+        let mutation = self.mutations.read().await;
+        let (handle, pending) = {
+            let write_back = self.write_back.lock().await;
+            (write_back.handle(id), write_back.pending(id).cloned())
+        };
+        if let Some(file) = handle {
+            tokio::task::spawn_blocking(move || file.sync_data())
+                .await
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?
                 .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-            drop(store);
         }
-
-        // Update size and mtime in metadata
-        let mut updated_metadata = metadata.clone();
-        updated_metadata.size = new_size;
-        updated_metadata.mtime = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let store = self.metadata.write().await;
-        store
-            .put_inode(id, &updated_metadata)
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-        drop(store);
-
-        Ok(self.metadata_to_fattr(id, &updated_metadata))
+        if let Some(metadata) = pending {
+            let store = self.metadata.write().await;
+            store.put_inode(id, &metadata).map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        }
+        drop(mutation);
+        self.getattr(id).await
     }
 
     async fn create(
         &self,
         dirid: fileid3,
         filename: &filename3,
-        _attr: sattr3,
+        attr: sattr3,
     ) -> Result<(fileid3, fattr3), nfsstat3> {
+        let mutation = self.mutations.read().await;
         let name = String::from_utf8_lossy(&filename.0).to_string();
 
         let full_path = if dirid == ROOT_INODE {
@@ -627,21 +888,39 @@ impl NFSFileSystem for VibeNFS {
             PathBuf::from(&dir_meta.path).join(&name)
         };
 
+        // UNCHECKED create of an existing file opens it and applies the
+        // attributes (usually a truncate) instead of minting a second inode
+        if let Some((existing, existing_meta)) = self
+            .get_metadata_by_path(&full_path)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?
+        {
+            if existing_meta.is_dir {
+                return Err(nfsstat3::NFS3ERR_EXIST);
+            }
+            // setattr takes the mutation lock itself
+            drop(mutation);
+            let fattr = self.setattr(existing, attr).await?;
+            return Ok((existing, fattr));
+        }
+
         let store = self.metadata.write().await;
         let new_inode = store
             .next_inode_id()
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
 
+        let (now, now_nsec) = now_timestamp();
         let metadata = InodeMetadata {
             path: full_path.to_string_lossy().to_string(),
-            git_oid: None,
-            is_dir: false,
-            size: 0,
-            volatile: false,
-            mtime: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            mtime: now,
+            mtime_nsec: now_nsec,
+            ctime: now,
+            ctime_nsec: now_nsec,
+            mode: match attr.mode {
+                set_mode3::mode(mode) => mode & 0o7777,
+                set_mode3::Void => 0,
+            },
+            ..Default::default()
         };
 
         store
@@ -669,6 +948,9 @@ impl NFSFileSystem for VibeNFS {
         tokio::fs::write(&session_path, b"")
             .await
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        if metadata.mode != 0 {
+            Self::sync_session_exec_bit(&session_path, &metadata).await?;
+        }
 
         let fattr = self.metadata_to_fattr(new_inode, &metadata);
         Ok((new_inode, fattr))
@@ -679,6 +961,29 @@ impl NFSFileSystem for VibeNFS {
         dirid: fileid3,
         filename: &filename3,
     ) -> Result<fileid3, nfsstat3> {
+        // Only reads before handing off to `create`
+        let name = String::from_utf8_lossy(&filename.0).to_string();
+        let full_path = if dirid == ROOT_INODE {
+            PathBuf::from(&name)
+        } else {
+            let dir_meta = self
+                .get_metadata_by_inode(dirid)
+                .await
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?
+                .ok_or(nfsstat3::NFS3ERR_NOENT)?;
+            PathBuf::from(&dir_meta.path).join(&name)
+        };
+
+        // O_EXCL: never reuse an existing entry
+        if self
+            .get_metadata_by_path(&full_path)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?
+            .is_some()
+        {
+            return Err(nfsstat3::NFS3ERR_EXIST);
+        }
+
         let (inode, _) = self.create(dirid, filename, sattr3::default()).await?;
         Ok(inode)
     }
@@ -688,6 +993,7 @@ impl NFSFileSystem for VibeNFS {
         dirid: fileid3,
         dirname: &filename3,
     ) -> Result<(fileid3, fattr3), nfsstat3> {
+        let _mutation = self.mutations.read().await;
         let name = String::from_utf8_lossy(&dirname.0).to_string();
 
         let full_path = if dirid == ROOT_INODE {
@@ -706,16 +1012,15 @@ impl NFSFileSystem for VibeNFS {
             .next_inode_id()
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
 
+        let (now, now_nsec) = now_timestamp();
         let metadata = InodeMetadata {
             path: full_path.to_string_lossy().to_string(),
-            git_oid: None,
             is_dir: true,
-            size: 0,
-            volatile: false,
-            mtime: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            mtime: now,
+            mtime_nsec: now_nsec,
+            ctime: now,
+            ctime_nsec: now_nsec,
+            ..Default::default()
         };
 
         store
@@ -737,6 +1042,7 @@ impl NFSFileSystem for VibeNFS {
     }
 
     async fn remove(&self, dirid: fileid3, filename: &filename3) -> Result<(), nfsstat3> {
+        let _mutation = self.mutations.read().await;
         let name = String::from_utf8_lossy(&filename.0).to_string();
 
         let full_path = if dirid == ROOT_INODE {
@@ -750,16 +1056,30 @@ impl NFSFileSystem for VibeNFS {
             PathBuf::from(&dir_meta.path).join(&name)
         };
 
-        let (inode, _) = self
+        let (inode, metadata) = self
             .get_metadata_by_path(&full_path)
             .await
             .map_err(|_| nfsstat3::NFS3ERR_IO)?
             .ok_or(nfsstat3::NFS3ERR_NOENT)?;
 
+        // Unstored writes to a file being deleted don't matter anymore
+        self.write_back.lock().await.take(inode);
+
         let store = self.metadata.write().await;
         store
             .delete_inode(inode)
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+
+        // Deleting a file from the base tree leaves a whiteout so diff and
+        // commit see the deletion. Session-only files simply disappear.
+        if Self::is_base_file(&metadata) {
+            store
+                .put_whiteout(inode, &metadata)
+                .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        }
+        store
+            .clear_dirty_path(&metadata.path)
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
         drop(store);
 
         // Update directory cache
@@ -790,6 +1110,7 @@ impl NFSFileSystem for VibeNFS {
         to_dirid: fileid3,
         to_filename: &filename3,
     ) -> Result<(), nfsstat3> {
+        let _mutation = self.mutations.read().await;
         let from_name = String::from_utf8_lossy(&from_filename.0).to_string();
         let to_name = String::from_utf8_lossy(&to_filename.0).to_string();
 
@@ -817,6 +1138,10 @@ impl NFSFileSystem for VibeNFS {
             PathBuf::from(&dir_meta.path).join(&to_name)
         };
 
+        // Rename rewrites inodes in the store, so pending write metadata (which
+        // carries the old paths) goes in first
+        self.flush_pending_metadata().await?;
+
         // Get source inode and metadata
         let (inode, metadata) = self
             .get_metadata_by_path(&from_path)
@@ -828,23 +1153,95 @@ impl NFSFileSystem for VibeNFS {
         let old_path_str = from_path.to_string_lossy().to_string();
         let new_path_str = to_path.to_string_lossy().to_string();
 
-        let store = self.metadata.write().await;
-        store
-            .rename_inode(inode, &old_path_str, &new_path_str)
-            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-        drop(store);
+        if old_path_str == new_path_str {
+            return Ok(());
+        }
+        // A directory can't be moved into itself
+        if metadata.is_dir && new_path_str.starts_with(&format!("{}/", old_path_str)) {
+            return Err(nfsstat3::NFS3ERR_INVAL);
+        }
 
-        // Keep metadata reference for later checks
-        let _ = metadata;
+        // An existing destination is replaced, as with rename(2): the kinds
+        // must match and a directory being replaced must be empty
+        let target = self
+            .get_metadata_by_path(&to_path)
+            .await
+            .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        if let Some((_, target_meta)) = &target {
+            match (metadata.is_dir, target_meta.is_dir) {
+                (false, true) => return Err(nfsstat3::NFS3ERR_ISDIR),
+                (true, false) => return Err(nfsstat3::NFS3ERR_NOTDIR),
+                (true, true) => {
+                    let store = self.metadata.read().await;
+                    let entries = store.get_subtree(&new_path_str).map_err(|_| nfsstat3::NFS3ERR_IO)?;
+                    if entries.len() > 1 {
+                        return Err(nfsstat3::NFS3ERR_NOTEMPTY);
+                    }
+                }
+                (false, false) => {}
+            }
+        }
+
+        let from_session = self.get_session_path(&from_path).await;
+        let to_session = self.get_session_path(&to_path).await;
+
+        // Files whose content lives outside the session (git blobs, repo
+        // passthrough) are materialized first: after the move there is no git
+        // entry or repo file at the new path to read from. Symlinks carry their
+        // target in metadata.
+        let subtree = {
+            let store = self.metadata.read().await;
+            store.get_subtree(&old_path_str).map_err(|_| nfsstat3::NFS3ERR_IO)?
+        };
+        for (_, entry) in &subtree {
+            if !entry.is_dir && !entry.is_symlink() {
+                let session_path = self.get_session_path(Path::new(&entry.path)).await;
+                self.ensure_session_file(entry, &session_path).await?;
+            }
+        }
+
+        // Rewrite the whole subtree in one batch. Base files moving away leave
+        // a whiteout at their old path, and so does a base file being replaced.
+        let store = self.metadata.write().await;
+        match &target {
+            Some((target_inode, target_meta)) => store.rename_replacing(
+                &old_path_str,
+                &new_path_str,
+                *target_inode,
+                target_meta,
+                Self::is_base_file(target_meta),
+                Self::is_base_file,
+            ),
+            None => store.rename_subtree(&old_path_str, &new_path_str, Self::is_base_file),
+        }
+        .map_err(|_| nfsstat3::NFS3ERR_IO)?;
+        drop(store);
 
         // Update directory cache
+        if let Some((target_inode, _)) = &target {
+            self.write_back.lock().await.take(*target_inode);
+            self.remove_child_from_cache(to_dirid, *target_inode).await;
+            self.dir_children.write().await.remove(target_inode);
+        }
         self.remove_child_from_cache(from_dirid, inode).await;
         self.add_child_to_cache(to_dirid, inode).await;
 
+        // The replaced target's session copy must not outlive it. When the
+        // source has a session copy, the rename below replaces it atomically.
+        let from_exists = tokio::fs::symlink_metadata(&from_session).await.is_ok();
+        if target.is_some() && !from_exists {
+            if let Ok(meta) = tokio::fs::symlink_metadata(&to_session).await {
+                let removed = if meta.is_dir() {
+                    tokio::fs::remove_dir_all(&to_session).await
+                } else {
+                    tokio::fs::remove_file(&to_session).await
+                };
+                removed.map_err(|_| nfsstat3::NFS3ERR_IO)?;
+            }
+        }
+
         // Move file in session directory if it exists
-        let from_session = self.get_session_path(&from_path).await;
-        let to_session = self.get_session_path(&to_path).await;
-        if from_session.exists() {
+        if from_exists {
             if let Some(parent) = to_session.parent() {
                 tokio::fs::create_dir_all(parent)
                     .await
@@ -864,6 +1261,9 @@ impl NFSFileSystem for VibeNFS {
         start_after: fileid3,
         max_entries: usize,
     ) -> Result<ReadDirResult, nfsstat3> {
+        // Entries are read from the store below
+        self.flush_pending_metadata().await?;
+
         // Get directory metadata (for non-root)
         if dirid != ROOT_INODE {
             let metadata = self
@@ -1013,6 +1413,7 @@ impl NFSFileSystem for VibeNFS {
         symlink: &nfspath3,
         _attr: &sattr3,
     ) -> Result<(fileid3, fattr3), nfsstat3> {
+        let _mutation = self.mutations.read().await;
         let name = String::from_utf8_lossy(&linkname.0).to_string();
         let target = String::from_utf8_lossy(&symlink.0).to_string();
 
@@ -1032,44 +1433,34 @@ impl NFSFileSystem for VibeNFS {
             .next_inode_id()
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
 
-        // Store symlink target in git_oid field (temporary solution)
+        let (now, now_nsec) = now_timestamp();
         let metadata = InodeMetadata {
             path: full_path.to_string_lossy().to_string(),
-            git_oid: Some(format!("symlink:{}", target)),
-            is_dir: false,
             size: target.len() as u64,
-            volatile: true,
-            mtime: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            mtime: now,
+            mtime_nsec: now_nsec,
+            ctime: now,
+            ctime_nsec: now_nsec,
+            symlink_target: Some(target),
+            ..Default::default()
         };
 
         store
             .put_inode(new_inode, &metadata)
             .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-        drop(store);
 
-        // Update directory cache
-        self.add_child_to_cache(dirid, new_inode).await;
-
-        // Create symlink in session
-        let session_path = self.get_session_path(&full_path).await;
-        if let Some(parent) = session_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
+        // Symlinks live only in metadata; commit hashes the target as a 120000 blob
+        if !Self::is_ignored_path(&metadata.path) {
+            store
+                .mark_dirty(&metadata.path)
                 .map_err(|_| nfsstat3::NFS3ERR_IO)?;
         }
+        drop(store);
 
-        #[cfg(unix)]
-        {
-            tokio::fs::symlink(&target, &session_path)
-                .await
-                .map_err(|_| nfsstat3::NFS3ERR_IO)?;
-        }
+        // Update directory cache
+        self.add_child_to_cache(dirid, new_inode).await;
 
-        let mut fattr = self.metadata_to_fattr(new_inode, &metadata);
-        fattr.ftype = ftype3::NF3LNK;
+        let fattr = self.metadata_to_fattr(new_inode, &metadata);
         Ok((new_inode, fattr))
     }
 
@@ -1080,11 +1471,8 @@ impl NFSFileSystem for VibeNFS {
             .map_err(|_| nfsstat3::NFS3ERR_IO)?
             .ok_or(nfsstat3::NFS3ERR_NOENT)?;
 
-        // Check if this is a symlink (stored with symlink: prefix in git_oid)
-        if let Some(oid) = &metadata.git_oid {
-            if let Some(target) = oid.strip_prefix("symlink:") {
-                return Ok(nfsstring(target.as_bytes().to_vec()));
-            }
+        if let Some(target) = metadata.link_target() {
+            return Ok(nfsstring(target.as_bytes().to_vec()));
         }
 
         // Try reading from session directory
@@ -1263,10 +1651,8 @@ mod tests {
         let regular_meta = InodeMetadata {
             path: "regular.txt".to_string(),
             git_oid: Some("abc123".to_string()),
-            is_dir: false,
             size: 100,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
         let regular_fattr = nfs.metadata_to_fattr(100, &regular_meta);
         // ftype3::NF3REG has mode 0o644 in our impl
@@ -1275,11 +1661,8 @@ mod tests {
         // Test directory
         let dir_meta = InodeMetadata {
             path: "subdir".to_string(),
-            git_oid: None,
             is_dir: true,
-            size: 0,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
         let dir_fattr = nfs.metadata_to_fattr(101, &dir_meta);
         assert_eq!(dir_fattr.mode, 0o755);
@@ -1288,10 +1671,9 @@ mod tests {
         let symlink_meta = InodeMetadata {
             path: "target".to_string(),
             git_oid: Some("symlink:/tmp/vibe-artifacts/test/target".to_string()),
-            is_dir: false,
             size: 35,
             volatile: true,
-            mtime: 0,
+            ..Default::default()
         };
         let symlink_fattr = nfs.metadata_to_fattr(102, &symlink_meta);
         // Symlinks should also have mode 0o644 but ftype should be NF3LNK
@@ -1331,11 +1713,9 @@ mod tests {
         // Volatile file with stale size (10) — should report real disk size
         let volatile_meta = InodeMetadata {
             path: "Cargo.lock".to_string(),
-            git_oid: None,
-            is_dir: false,
             size: 10, // stale
             volatile: true,
-            mtime: 0,
+            ..Default::default()
         };
         let fattr = nfs.metadata_to_fattr(200, &volatile_meta);
         assert_eq!(fattr.size, disk_content.len() as u64);
@@ -1344,10 +1724,8 @@ mod tests {
         let tracked_meta = InodeMetadata {
             path: "src/main.rs".to_string(),
             git_oid: Some("abc123".to_string()),
-            is_dir: false,
             size: 999,
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
         let fattr = nfs.metadata_to_fattr(201, &tracked_meta);
         assert_eq!(fattr.size, 999); // uses cached size
@@ -1386,10 +1764,8 @@ mod tests {
         let meta = InodeMetadata {
             path: "test.txt".to_string(),
             git_oid: Some(oid),
-            is_dir: false,
             size: 5, // "hello" = 5 bytes
-            volatile: false,
-            mtime: 0,
+            ..Default::default()
         };
         metadata_store.put_inode(inode_id, &meta).unwrap();
         metadata_store.mark_dirty("test.txt").unwrap();
@@ -1482,10 +1858,9 @@ mod tests {
         let volatile_meta = InodeMetadata {
             path: "passthrough.txt".to_string(),
             git_oid: Some(old_oid.clone()), // stale OID from before the file changed
-            is_dir: false,
             size: 20,
             volatile: true, // marked volatile — should passthrough regardless of git_oid
-            mtime: 0,
+            ..Default::default()
         };
         metadata.put_inode(inode_id, &volatile_meta).unwrap();
 
@@ -1511,4 +1886,537 @@ mod tests {
         assert_eq!(String::from_utf8(blob).unwrap(), "old content from git",
             "git blob should still contain old content");
     }
+
+    #[tokio::test]
+    async fn test_remove_tracked_file_leaves_whiteout() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let tracked_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(tracked_inode, &InodeMetadata {
+            path: "tracked.txt".to_string(),
+            git_oid: Some("abc123".to_string()),
+            size: 5,
+            ..Default::default()
+        }).unwrap();
+        let new_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(new_inode, &InodeMetadata {
+            path: "scratch.txt".to_string(),
+            ..Default::default()
+        }).unwrap();
+        metadata_store.mark_dirty("scratch.txt").unwrap();
+        std::fs::write(session_dir.join("scratch.txt"), "").unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        nfs.remove(ROOT_INODE, &VibeNFS::to_nfsstring("tracked.txt")).await.unwrap();
+        nfs.remove(ROOT_INODE, &VibeNFS::to_nfsstring("scratch.txt")).await.unwrap();
+
+        let store = nfs.metadata.read().await;
+        // Base file: deletion recorded as a whiteout
+        assert_eq!(store.get_whiteout_paths().unwrap(), vec!["tracked.txt".to_string()]);
+        // Session-only file: gone without a trace
+        assert!(!store.is_dirty("scratch.txt").unwrap());
+        assert!(!session_dir.join("scratch.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_setattr_chmod_marks_dirty() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let oid = git.write_blob(b"#!/bin/sh\n").unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let inode_id = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(inode_id, &InodeMetadata {
+            path: "build.sh".to_string(),
+            git_oid: Some(oid),
+            size: 10,
+            mode: 0o644,
+            ..Default::default()
+        }).unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+
+        let attr = sattr3 {
+            mode: set_mode3::mode(0o755),
+            ..sattr3::default()
+        };
+        let fattr = nfs.setattr(inode_id, attr).await.unwrap();
+        assert_eq!(fattr.mode, 0o755);
+
+        let store = nfs.metadata.read().await;
+        assert!(store.is_dirty("build.sh").unwrap());
+        assert_eq!(store.get_inode(inode_id).unwrap().unwrap().git_file_mode(), "100755");
+
+        // Content was materialized in the session with the exec bit
+        use std::os::unix::fs::PermissionsExt;
+        let session_file = std::fs::metadata(session_dir.join("build.sh")).unwrap();
+        assert_eq!(session_file.permissions().mode() & 0o111, 0o111);
+    }
+
+    #[tokio::test]
+    async fn test_setattr_times_keep_nanoseconds() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let oid = git.write_blob(b"all: build\n").unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let inode_id = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(inode_id, &InodeMetadata {
+            path: "Makefile".to_string(),
+            git_oid: Some(oid),
+            size: 11,
+            ..Default::default()
+        }).unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir,
+            repo_dir,
+            "test".to_string(),
+        );
+
+        // touch -d: client-supplied times are stored exactly, and ctime moves
+        // independently to "now"
+        let attr = sattr3 {
+            atime: set_atime::SET_TO_CLIENT_TIME(nfstime3 { seconds: 1_600_000_000, nseconds: 1 }),
+            mtime: set_mtime::SET_TO_CLIENT_TIME(nfstime3 { seconds: 1_600_000_000, nseconds: 123_456_789 }),
+            ..sattr3::default()
+        };
+        let fattr = nfs.setattr(inode_id, attr).await.unwrap();
+        assert_eq!((fattr.mtime.seconds, fattr.mtime.nseconds), (1_600_000_000, 123_456_789));
+        assert_eq!((fattr.atime.seconds, fattr.atime.nseconds), (1_600_000_000, 1));
+        assert!(fattr.ctime.seconds > 1_600_000_000);
+
+        // Times alone don't make a file dirty
+        assert!(!nfs.metadata.read().await.is_dirty("Makefile").unwrap());
+
+        // Two writes in the same second still produce distinct mtimes
+        let first = nfs.write(inode_id, 0, b"all: test\n").await.unwrap().mtime;
+        let second = nfs.write(inode_id, 0, b"all: lint\n").await.unwrap().mtime;
+        assert!((second.seconds, second.nseconds) > (first.seconds, first.nseconds));
+        let fattr = nfs.getattr(inode_id).await.unwrap();
+        assert_eq!((fattr.atime.seconds, fattr.atime.nseconds), (1_600_000_000, 1));
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_buffered_until_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(MetadataStore::open(&db_path).unwrap())),
+            Arc::new(RwLock::new(GitRepo::open(&repo_dir).unwrap())),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        let (a, _) = nfs.create(ROOT_INODE, &VibeNFS::to_nfsstring("a.o"), sattr3::default()).await.unwrap();
+        let (b, _) = nfs.create(ROOT_INODE, &VibeNFS::to_nfsstring("b.o"), sattr3::default()).await.unwrap();
+        for chunk in 0..4u64 {
+            nfs.write(a, chunk * 4, b"abcd").await.unwrap();
+        }
+        nfs.write(b, 0, b"xyz").await.unwrap();
+
+        // Data is on disk and served right away, the inode update is not stored yet
+        assert_eq!(std::fs::read(session_dir.join("a.o")).unwrap().len(), 16);
+        assert_eq!(nfs.getattr(a).await.unwrap().size, 16);
+        assert_eq!(nfs.read(a, 12, 4).await.unwrap().0, b"abcd");
+        {
+            let store = nfs.metadata.read().await;
+            assert!(store.is_dirty("a.o").unwrap());
+            assert_eq!(store.get_inode(a).unwrap().unwrap().size, 0);
+        }
+
+        // COMMIT stores one file, sync stores every pending inode in one batch
+        assert_eq!(nfs.commit(a).await.unwrap().size, 16);
+        assert_eq!(nfs.metadata.read().await.get_inode(a).unwrap().unwrap().size, 16);
+        assert_eq!(nfs.metadata.read().await.get_inode(b).unwrap().unwrap().size, 0);
+        assert_eq!(nfs.sync().await.unwrap(), 2);
+        assert_eq!(nfs.metadata.read().await.get_inode(b).unwrap().unwrap().size, 3);
+
+        // Writing after a sync reopens the file and keeps appending correctly
+        nfs.write(b, 3, b"!").await.unwrap();
+        assert_eq!(nfs.read(b, 0, 10).await.unwrap().0, b"xyz!");
+    }
+
+    #[tokio::test]
+    async fn test_file_handles_survive_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let serve = |generation: Option<u64>| {
+            let store = MetadataStore::open(&db_path).unwrap();
+            let nfs = VibeNFS::new(
+                Arc::new(RwLock::new(store)),
+                Arc::new(RwLock::new(GitRepo::open(&repo_dir).unwrap())),
+                session_dir.clone(),
+                repo_dir.clone(),
+                "test".to_string(),
+            );
+            match generation {
+                Some(generation) => nfs.with_handle_generation(generation),
+                None => nfs,
+            }
+        };
+
+        let generation = MetadataStore::open(&db_path).unwrap().handle_generation().unwrap();
+        let fh = serve(Some(generation)).id_to_fh(ROOT_INODE);
+
+        // The store keeps the generation, so a new server accepts old handles
+        let generation = MetadataStore::open(&db_path).unwrap().handle_generation().unwrap();
+        assert_eq!(serve(Some(generation)).fh_to_id(&fh).ok(), Some(ROOT_INODE));
+        assert!(matches!(serve(Some(generation + 1)).fh_to_id(&fh), Err(nfsstat3::NFS3ERR_STALE)));
+        assert!(serve(None).fh_to_id(&nfs_fh3 { data: vec![0; 4] }).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pause_mutations_holds_off_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(MetadataStore::open(&db_path).unwrap())),
+            Arc::new(RwLock::new(GitRepo::open(&repo_dir).unwrap())),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        let (a, _) = nfs.create(ROOT_INODE, &VibeNFS::to_nfsstring("a.txt"), sattr3::default()).await.unwrap();
+        nfs.write(a, 0, b"one").await.unwrap();
+
+        // Pausing stores the buffered write
+        let paused = nfs.pause_mutations().await.unwrap();
+        assert_eq!(nfs.metadata.read().await.get_inode(a).unwrap().unwrap().size, 3);
+
+        let writer = nfs.clone();
+        let pending = tokio::spawn(async move { writer.write(a, 3, b"two").await.unwrap() });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!pending.is_finished());
+        assert_eq!(std::fs::read(session_dir.join("a.txt")).unwrap(), b"one");
+        assert_eq!(nfs.read(a, 0, 10).await.unwrap().0, b"one");
+
+        drop(paused);
+        assert_eq!(pending.await.unwrap().size, 6);
+    }
+
+    #[tokio::test]
+    async fn test_symlink_is_dirty_and_readable() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+
+        let (inode, fattr) = nfs
+            .symlink(
+                ROOT_INODE,
+                &VibeNFS::to_nfsstring("latest"),
+                &VibeNFS::to_nfsstring("releases/v2"),
+                &sattr3::default(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(fattr.ftype, ftype3::NF3LNK));
+        assert_eq!(nfs.readlink(inode).await.unwrap().0, b"releases/v2");
+
+        // Tracked like any other session change; nothing physical in the delta dir
+        let store = nfs.metadata.read().await;
+        assert!(store.is_dirty("latest").unwrap());
+        assert!(!session_dir.join("latest").is_symlink());
+    }
+
+    #[tokio::test]
+    async fn test_chunked_reads_use_offsets_and_blob_cache() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let content: Vec<u8> = (0..100u8).collect();
+        let oid = git.write_blob(&content).unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let clean_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(clean_inode, &InodeMetadata {
+            path: "clean.bin".to_string(),
+            git_oid: Some(oid),
+            size: 100,
+            ..Default::default()
+        }).unwrap();
+        let dirty_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(dirty_inode, &InodeMetadata {
+            path: "dirty.bin".to_string(),
+            size: 100,
+            ..Default::default()
+        }).unwrap();
+        std::fs::write(session_dir.join("dirty.bin"), &content).unwrap();
+
+        let cache = Arc::new(BlobCache::new(1024));
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir,
+            repo_dir,
+            "test".to_string(),
+        ).with_blob_cache(cache.clone());
+
+        for id in [clean_inode, dirty_inode] {
+            let (chunk, eof) = nfs.read(id, 0, 40).await.unwrap();
+            assert_eq!(chunk, &content[..40]);
+            assert!(!eof);
+            let (chunk, eof) = nfs.read(id, 40, 40).await.unwrap();
+            assert_eq!(chunk, &content[40..80]);
+            assert!(!eof);
+            let (chunk, eof) = nfs.read(id, 80, 40).await.unwrap();
+            assert_eq!(chunk, &content[80..]);
+            assert!(eof);
+            let (chunk, eof) = nfs.read(id, 200, 40).await.unwrap();
+            assert!(chunk.is_empty());
+            assert!(eof);
+        }
+
+        // The clean blob was inflated once and served from cache afterwards
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 3);
+    }
+
+    #[tokio::test]
+    async fn test_rename_directory_moves_subtree() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let oid = git.write_blob(b"pub fn old() {}").unwrap();
+
+        let entry = |path: &str, is_dir: bool, git_oid: Option<String>| InodeMetadata {
+            path: path.to_string(),
+            git_oid,
+            is_dir,
+            size: 15,
+            ..Default::default()
+        };
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let dir_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(dir_inode, &entry("old", true, None)).unwrap();
+        let tracked_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(tracked_inode, &entry("old/lib.rs", false, Some(oid))).unwrap();
+        let created_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(created_inode, &entry("old/notes.md", false, None)).unwrap();
+        metadata_store.mark_dirty("old/notes.md").unwrap();
+        std::fs::create_dir_all(session_dir.join("old")).unwrap();
+        std::fs::write(session_dir.join("old/notes.md"), "draft").unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        nfs.rename(
+            ROOT_INODE,
+            &VibeNFS::to_nfsstring("old"),
+            ROOT_INODE,
+            &VibeNFS::to_nfsstring("new"),
+        ).await.unwrap();
+
+        // Lookups under the new path resolve to the same inodes
+        let dir = nfs.lookup(ROOT_INODE, &VibeNFS::to_nfsstring("new")).await.unwrap();
+        assert_eq!(dir, dir_inode);
+        assert_eq!(nfs.lookup(dir, &VibeNFS::to_nfsstring("lib.rs")).await.unwrap(), tracked_inode);
+        assert_eq!(nfs.lookup(dir, &VibeNFS::to_nfsstring("notes.md")).await.unwrap(), created_inode);
+        assert!(nfs.lookup(ROOT_INODE, &VibeNFS::to_nfsstring("old")).await.is_err());
+
+        // Content survives the move, for both git-backed and session files
+        assert_eq!(nfs.read(tracked_inode, 0, 100).await.unwrap().0, b"pub fn old() {}");
+        assert_eq!(nfs.read(created_inode, 0, 100).await.unwrap().0, b"draft");
+
+        let store = nfs.metadata.read().await;
+        let mut dirty = store.get_dirty_paths().unwrap();
+        dirty.sort();
+        assert_eq!(dirty, vec!["new/lib.rs".to_string(), "new/notes.md".to_string()]);
+        assert_eq!(store.get_whiteout_paths().unwrap(), vec!["old/lib.rs".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_create_exclusive_existing_returns_exist() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(MetadataStore::open(&db_path).unwrap())),
+            Arc::new(RwLock::new(GitRepo::open(&repo_dir).unwrap())),
+            session_dir,
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        let name = VibeNFS::to_nfsstring("lock");
+        let inode = nfs.create_exclusive(ROOT_INODE, &name).await.unwrap();
+        nfs.write(inode, 0, b"held").await.unwrap();
+
+        assert!(matches!(
+            nfs.create_exclusive(ROOT_INODE, &name).await,
+            Err(nfsstat3::NFS3ERR_EXIST)
+        ));
+        assert_eq!(nfs.read(inode, 0, 100).await.unwrap().0, b"held");
+
+        // A plain create reopens the same inode rather than adding a second one
+        let (again, _) = nfs.create(ROOT_INODE, &name, sattr3::default()).await.unwrap();
+        assert_eq!(again, inode);
+    }
+
+    #[tokio::test]
+    async fn test_rename_over_existing_replaces_target() {
+        use crate::db::InodeMetadata;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("metadata.db");
+        let session_dir = temp_dir.path().join("session");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::process::Command::new("git").args(["init"]).current_dir(&repo_dir).output().unwrap();
+
+        let git = GitRepo::open(&repo_dir).unwrap();
+        let oid = git.write_blob(b"old config").unwrap();
+
+        let metadata_store = MetadataStore::open(&db_path).unwrap();
+        let target_inode = metadata_store.next_inode_id().unwrap();
+        metadata_store.put_inode(target_inode, &InodeMetadata {
+            path: "config.toml".to_string(),
+            git_oid: Some(oid),
+            size: 10,
+            ..Default::default()
+        }).unwrap();
+
+        let nfs = VibeNFS::new(
+            Arc::new(RwLock::new(metadata_store)),
+            Arc::new(RwLock::new(git)),
+            session_dir.clone(),
+            repo_dir,
+            "test".to_string(),
+        );
+        nfs.build_directory_cache().await.unwrap();
+
+        // Editor-style save: write a temp file, then rename it over the original
+        let (temp_inode, _) = nfs
+            .create(ROOT_INODE, &VibeNFS::to_nfsstring("config.toml.tmp"), sattr3::default())
+            .await
+            .unwrap();
+        nfs.write(temp_inode, 0, b"new config").await.unwrap();
+        nfs.rename(
+            ROOT_INODE,
+            &VibeNFS::to_nfsstring("config.toml.tmp"),
+            ROOT_INODE,
+            &VibeNFS::to_nfsstring("config.toml"),
+        ).await.unwrap();
+
+        assert_eq!(nfs.lookup(ROOT_INODE, &VibeNFS::to_nfsstring("config.toml")).await.unwrap(), temp_inode);
+        assert_eq!(nfs.read(temp_inode, 0, 100).await.unwrap().0, b"new config");
+        assert!(nfs.getattr(target_inode).await.is_err());
+
+        let children = nfs.dir_children.read().await.get(&ROOT_INODE).cloned().unwrap();
+        assert_eq!(children, vec![temp_inode]);
+
+        let store = nfs.metadata.read().await;
+        assert_eq!(store.get_dirty_paths().unwrap(), vec!["config.toml".to_string()]);
+        assert_eq!(store.get_whiteout_paths().unwrap(), vec!["config.toml".to_string()]);
+    }
 }
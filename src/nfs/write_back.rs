@@ -0,0 +1,119 @@
+//! Write-back state for NFS WRITE.
+//!
+//! Clients send a large file as a stream of small WRITEs. Instead of opening
+//! the file and updating RocksDB for each one, the server keeps the session
+//! file open per inode and holds the latest size/mtime in memory. WRITEs are
+//! acknowledged as UNSTABLE and nothing is fsynced until the client sends
+//! COMMIT (`VibeNFS::commit`, one file) or the daemon runs `VibeNFS::sync`
+//! (every file, metadata stored in one batch) before `vibe save`,
+//! `vibe commit` and `vibe kill` look at the session.
+
+use nfsserve::nfs::fileid3;
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::db::InodeMetadata;
+
+/// Open session files kept per session. Past this, a handle is synced and
+/// closed before another one is opened.
+pub const MAX_OPEN_HANDLES: usize = 256;
+
+#[derive(Default)]
+pub struct WriteBack {
+    /// Session files open for writing, by inode
+    handles: HashMap<fileid3, Arc<File>>,
+    /// Inode metadata updated by writes but not yet stored
+    pending: HashMap<fileid3, InodeMetadata>,
+}
+
+impl WriteBack {
+    pub fn handle(&self, id: fileid3) -> Option<Arc<File>> {
+        self.handles.get(&id).cloned()
+    }
+
+    /// Cache a handle for `id`. Returns the handle to use (an existing one
+    /// wins if two writers raced to open the file) and, when the cache was
+    /// full, an evicted handle the caller must sync.
+    pub fn insert_handle(&mut self, id: fileid3, file: File) -> (Arc<File>, Option<Arc<File>>) {
+        if let Some(existing) = self.handles.get(&id) {
+            return (existing.clone(), None);
+        }
+
+        let evicted = if self.handles.len() >= MAX_OPEN_HANDLES {
+            let victim = *self.handles.keys().next().expect("cache is full");
+            self.handles.remove(&victim)
+        } else {
+            None
+        };
+
+        let file = Arc::new(file);
+        self.handles.insert(id, file.clone());
+        (file, evicted)
+    }
+
+    pub fn pending(&self, id: fileid3) -> Option<&InodeMetadata> {
+        self.pending.get(&id)
+    }
+
+    pub fn set_pending(&mut self, id: fileid3, metadata: InodeMetadata) {
+        self.pending.insert(id, metadata);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn take_pending(&mut self) -> Vec<(fileid3, InodeMetadata)> {
+        self.pending.drain().collect()
+    }
+
+    /// Detach the handle and pending metadata of one inode
+    pub fn take(&mut self, id: fileid3) -> (Option<Arc<File>>, Option<InodeMetadata>) {
+        (self.handles.remove(&id), self.pending.remove(&id))
+    }
+
+    pub fn take_handles(&mut self) -> Vec<Arc<File>> {
+        self.handles.drain().map(|(_, file)| file).collect()
+    }
+
+    /// Forget everything without storing it (the session was reset)
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_handle_cache_is_bounded() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut write_back = WriteBack::default();
+
+        let mut evictions = 0;
+        for id in 0..(MAX_OPEN_HANDLES as u64 + 3) {
+            let file = File::create(temp_dir.path().join(id.to_string())).unwrap();
+            let (_, evicted) = write_back.insert_handle(id, file);
+            if evicted.is_some() {
+                evictions += 1;
+            }
+        }
+
+        assert_eq!(evictions, 3);
+        assert_eq!(write_back.take_handles().len(), MAX_OPEN_HANDLES);
+    }
+
+    #[test]
+    fn test_existing_handle_wins() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut write_back = WriteBack::default();
+
+        let (first, _) = write_back.insert_handle(7, File::create(temp_dir.path().join("a")).unwrap());
+        let (second, _) = write_back.insert_handle(7, File::create(temp_dir.path().join("b")).unwrap());
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
@@ -6,6 +6,10 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Get the mount point directory for VibeFS based on the platform
+/// (`VIBE_MOUNTS_DIR`, then `paths.mounts_root` in the user config).
+/// This runs while the repository is still being located, so an invalid
+/// user config falls back to the default here; commands that load the
+/// config report it.
 pub fn get_vibe_mounts_dir() -> PathBuf {
     if let Ok(override_dir) = std::env::var("VIBE_MOUNTS_DIR") {
         if !override_dir.trim().is_empty() {
@@ -13,6 +17,10 @@ pub fn get_vibe_mounts_dir() -> PathBuf {
         }
     }
 
+    if let Some(mounts_root) = crate::config::Config::load_user().ok().and_then(|c| c.paths.mounts_root) {
+        return mounts_root;
+    }
+
     #[cfg(target_os = "macos")]
     {
         PathBuf::from(format!(
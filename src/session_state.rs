@@ -0,0 +1,277 @@
+//! Authoritative metadata for a session
+//!
+//! While the daemon runs it owns the sessions' RocksDB stores, so queries go
+//! to it over IPC and it answers from the live store (or, for sessions it
+//! doesn't serve, from the store on disk). Only when no daemon is running
+//! does the CLI open the store itself.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::daemon_client::DaemonClient;
+use crate::daemon_ipc::DaemonResponse;
+use crate::db::{InodeMetadata, MetadataStore};
+use crate::gitignore::PromoteFilter;
+
+/// Paths a session changed: dirty files, and base files it deleted (whiteouts)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionChanges {
+    pub dirty: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+impl SessionChanges {
+    pub fn read(store: &MetadataStore) -> Result<Self> {
+        Ok(Self {
+            dirty: store.get_dirty_paths().context("Failed to get dirty paths")?,
+            deleted: store.get_whiteout_paths().context("Failed to get deleted paths")?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirty.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Size of a session's metadata and delta
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub inode_count: usize,
+    pub dirty_count: usize,
+    pub deleted_count: usize,
+    /// Files in the session directory, not counting its metadata store or
+    /// artifact directories
+    pub delta_file_count: usize,
+    pub delta_size_bytes: u64,
+}
+
+impl SessionStats {
+    pub fn collect(store: &MetadataStore, session_dir: &Path) -> Result<Self> {
+        let changes = SessionChanges::read(store)?;
+        let mut stats = Self {
+            inode_count: store.get_all_inodes()?.len(),
+            dirty_count: changes.dirty.len(),
+            deleted_count: changes.deleted.len(),
+            ..Default::default()
+        };
+        if session_dir.exists() {
+            add_delta_files(session_dir, true, &mut stats)?;
+        }
+        Ok(stats)
+    }
+}
+
+fn add_delta_files(dir: &Path, top_level: bool, stats: &mut SessionStats) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() || (top_level && entry.file_name() == "metadata.db") {
+            continue;
+        }
+        if file_type.is_dir() {
+            add_delta_files(&entry.path(), false, stats)?;
+        } else if file_type.is_file() {
+            stats.delta_file_count += 1;
+            stats.delta_size_bytes += entry.metadata()?.len();
+        }
+    }
+    Ok(())
+}
+
+/// A session's metadata store on disk. Sessions spawned without their own
+/// store keep their changes in the base store.
+pub fn session_db_path(repo_path: &Path, vibe_id: &str) -> PathBuf {
+    let session_db = repo_path.join(".vibe/sessions").join(vibe_id).join("metadata.db");
+    if session_db.exists() {
+        session_db
+    } else {
+        repo_path.join(".vibe/metadata.db")
+    }
+}
+
+/// Where a session's metadata is read from
+pub enum SessionState {
+    /// The running daemon
+    Daemon { client: DaemonClient, vibe_id: String },
+    /// The store, opened read-only because no daemon holds it
+    Store { store: MetadataStore, session_dir: PathBuf },
+}
+
+impl SessionState {
+    pub async fn open(repo_path: &Path, vibe_id: &str) -> Result<Self> {
+        let session_dir = repo_path.join(".vibe/sessions").join(vibe_id);
+        if !session_dir.is_dir() {
+            anyhow::bail!("Session '{}' not found", vibe_id);
+        }
+
+        if DaemonClient::is_running(repo_path).await {
+            let client = DaemonClient::connect(repo_path).await?;
+            return Ok(Self::Daemon { client, vibe_id: vibe_id.to_string() });
+        }
+
+        let store = MetadataStore::open_readonly(session_db_path(repo_path, vibe_id))
+            .context("Failed to open metadata store")?;
+        Ok(Self::Store { store, session_dir })
+    }
+
+    /// Dirty and deleted paths, gitignored ones included
+    pub async fn changes(&mut self) -> Result<SessionChanges> {
+        match self {
+            Self::Daemon { client, vibe_id } => match client.get_dirty_paths(vibe_id).await? {
+                DaemonResponse::DirtyPaths { dirty, deleted, .. } => Ok(SessionChanges { dirty, deleted }),
+                other => Err(unexpected(other)),
+            },
+            Self::Store { store, .. } => SessionChanges::read(store),
+        }
+    }
+
+    /// Metadata for `path`, if the session has it
+    pub async fn inode(&mut self, path: &str) -> Result<Option<InodeMetadata>> {
+        match self {
+            Self::Daemon { client, vibe_id } => match client.get_inode(vibe_id, path).await? {
+                DaemonResponse::Inode { inode, .. } => Ok(inode),
+                other => Err(unexpected(other)),
+            },
+            Self::Store { store, .. } => lookup_inode(store, path),
+        }
+    }
+
+    /// `path` and everything under it (the whole tree for `""`)
+    pub async fn list_tree(&mut self, path: &str) -> Result<Vec<InodeMetadata>> {
+        match self {
+            Self::Daemon { client, vibe_id } => match client.list_tree(vibe_id, path).await? {
+                DaemonResponse::Tree { entries, .. } => Ok(entries),
+                other => Err(unexpected(other)),
+            },
+            Self::Store { store, .. } => list_tree(store, path),
+        }
+    }
+
+    pub async fn stats(&mut self) -> Result<SessionStats> {
+        match self {
+            Self::Daemon { client, vibe_id } => match client.session_stats(vibe_id).await? {
+                DaemonResponse::Stats { stats, .. } => Ok(stats),
+                other => Err(unexpected(other)),
+            },
+            Self::Store { store, session_dir } => SessionStats::collect(store, session_dir),
+        }
+    }
+}
+
+/// Dirty paths of a session that `vibe promote` would commit, i.e. without
+/// the gitignored ones
+pub async fn promotable_dirty_paths(repo_path: &Path, vibe_id: &str) -> Result<Vec<String>> {
+    let dirty = SessionState::open(repo_path, vibe_id).await?.changes().await?.dirty;
+    let session_dir = repo_path.join(".vibe/sessions").join(vibe_id);
+    Ok(match PromoteFilter::new(repo_path, Some(&session_dir)) {
+        Ok(filter) => filter.filter_promotable(&dirty).into_iter().cloned().collect(),
+        Err(_) => dirty,
+    })
+}
+
+pub fn lookup_inode(store: &MetadataStore, path: &str) -> Result<Option<InodeMetadata>> {
+    match store.get_inode_by_path(path)? {
+        Some(id) => store.get_inode(id),
+        None => Ok(None),
+    }
+}
+
+pub fn list_tree(store: &MetadataStore, path: &str) -> Result<Vec<InodeMetadata>> {
+    let mut entries: Vec<InodeMetadata> = if path.is_empty() {
+        store.get_all_inodes()?.into_iter().map(|(_, meta)| meta).collect()
+    } else {
+        store.get_subtree(path)?.into_iter().map(|(_, meta)| meta).collect()
+    };
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+fn unexpected(response: DaemonResponse) -> anyhow::Error {
+    match response {
+        DaemonResponse::Error { message } => anyhow::anyhow!("Daemon error: {}", message),
+        other => anyhow::anyhow!("Unexpected daemon response: {:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{init, spawn};
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn setup_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo_path).output().unwrap();
+        };
+        git(&["init"]);
+        git(&["config", "user.name", "Test User"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::create_dir_all(repo_path.join("src")).unwrap();
+        fs::write(repo_path.join("src/lib.rs"), "// lib").unwrap();
+        fs::write(repo_path.join("README.md"), "# Test").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_store_backend_reads_session_metadata() {
+        let temp_dir = setup_test_repo();
+        let repo_path = temp_dir.path();
+        init::init(repo_path).await.unwrap();
+        let head = crate::git::GitRepo::open(repo_path).unwrap().head_commit().unwrap();
+        spawn::spawn_local_from(repo_path, "s", Some(&head)).await.unwrap();
+
+        let session_dir = repo_path.join(".vibe/sessions/s");
+        {
+            let store = MetadataStore::open(session_dir.join("metadata.db")).unwrap();
+            fs::write(session_dir.join("notes.txt"), "notes").unwrap();
+            store.mark_dirty("notes.txt").unwrap();
+            let id = store.get_inode_by_path("README.md").unwrap().unwrap();
+            let readme = store.get_inode(id).unwrap().unwrap();
+            store.delete_inode(id).unwrap();
+            store.put_whiteout(id, &readme).unwrap();
+        }
+
+        let mut state = SessionState::open(repo_path, "s").await.unwrap();
+        let changes = state.changes().await.unwrap();
+        assert_eq!(changes.dirty, vec!["notes.txt"]);
+        assert_eq!(changes.deleted, vec!["README.md"]);
+
+        assert!(state.inode("src/lib.rs").await.unwrap().is_some());
+        assert!(state.inode("README.md").await.unwrap().is_none());
+        let tree: Vec<String> = state.list_tree("src").await.unwrap().into_iter().map(|m| m.path).collect();
+        assert_eq!(tree, vec!["src", "src/lib.rs"]);
+
+        let stats = state.stats().await.unwrap();
+        assert_eq!((stats.dirty_count, stats.deleted_count), (1, 1));
+        assert_eq!((stats.delta_file_count, stats.delta_size_bytes), (1, 5));
+
+        assert!(SessionState::open(repo_path, "missing").await.is_err());
+    }
+
+    #[test]
+    fn test_stats_skip_metadata_store_and_artifact_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_dir = temp_dir.path().join("session");
+        fs::create_dir_all(session_dir.join("metadata.db")).unwrap();
+        fs::write(session_dir.join("metadata.db/CURRENT"), "MANIFEST").unwrap();
+        fs::create_dir_all(session_dir.join("src")).unwrap();
+        fs::write(session_dir.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let artifacts = temp_dir.path().join("artifacts");
+        fs::create_dir_all(&artifacts).unwrap();
+        fs::write(artifacts.join("big.o"), vec![0u8; 4096]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&artifacts, session_dir.join("target")).unwrap();
+
+        let store = MetadataStore::open(temp_dir.path().join("metadata.db")).unwrap();
+        let stats = SessionStats::collect(&store, &session_dir).unwrap();
+        assert_eq!((stats.delta_file_count, stats.delta_size_bytes), (1, 12));
+    }
+}
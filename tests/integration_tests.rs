@@ -105,7 +105,7 @@ async fn test_full_workflow() -> Result<()> {
     assert!(!snapshots.is_empty());
 
     // Test 5: Promote the vibe session
-    promote::promote(repo_path, "agent-1", None, None, true).await?;
+    promote::promote(repo_path, "agent-1", None, None, true, false).await?;
 
     let git = GitRepo::open(repo_path)?;
     let vibe_ref = git.get_ref("refs/vibes/agent-1")?;
@@ -162,9 +162,9 @@ async fn test_multiple_parallel_vibes() -> Result<()> {
     } // Drop metadata before promoting
 
     // Promote all vibes
-    promote::promote(repo_path, "agent-1", None, None, true).await?;
-    promote::promote(repo_path, "agent-2", None, None, true).await?;
-    promote::promote(repo_path, "agent-3", None, None, true).await?;
+    promote::promote(repo_path, "agent-1", None, None, true, false).await?;
+    promote::promote(repo_path, "agent-2", None, None, true, false).await?;
+    promote::promote(repo_path, "agent-3", None, None, true, false).await?;
 
     // Verify all have refs
     let git = GitRepo::open(repo_path)?;
@@ -229,7 +229,7 @@ async fn test_promote_without_changes() -> Result<()> {
     spawn::spawn_local(repo_path, "agent-1").await?;
 
     // Try to promote without any changes
-    promote::promote(repo_path, "agent-1", None, None, true).await?;
+    promote::promote(repo_path, "agent-1", None, None, true, false).await?;
 
     // Should complete without error, but not create a ref
     let git = GitRepo::open(repo_path)?;
@@ -300,6 +300,16 @@ async fn test_list_dirty_files() -> Result<()> {
     fs::write(session_dir.join("file2.txt"), "content 2")?;
     fs::create_dir_all(session_dir.join("subdir"))?;
     fs::write(session_dir.join("subdir/file3.txt"), "content 3")?;
+    {
+        // The session has no store of its own, so its changes live in the base store
+        let store = MetadataStore::open(repo_path.join(".vibe/metadata.db"))?;
+        for path in ["file1.txt", "file2.txt", "subdir/file3.txt"] {
+            store.mark_dirty(path)?;
+        }
+    }
+
+    // Files the store doesn't know about are not dirty
+    fs::write(session_dir.join("._file1.txt"), "apple double")?;
 
     // Get dirty files
     let dirty_files = close::list_dirty(repo_path, "agent-1").await?;